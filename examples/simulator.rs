@@ -0,0 +1,22 @@
+//! Lay out a status screen on the host, run with `cargo run --example simulator --features std`.
+
+use lcd_lcm1602_i2c::simulator::{NoDelay, Simulator, SimulatorLcd};
+
+fn main() {
+    let simulator = Simulator::new().with_glyph_char(0, '♥');
+    let mut lcd = SimulatorLcd::<4, 20>::new(simulator, NoDelay)
+        .with_address(0x27)
+        .init()
+        .unwrap();
+
+    lcd.create_char(0, &[0x00, 0x0a, 0x1f, 0x1f, 0x0e, 0x04, 0x00, 0x00])
+        .unwrap();
+    lcd.write_str_centered(0, "Status").unwrap();
+    lcd.print_at(1, 0, "Temp:").unwrap();
+    lcd.print_at(1, 14, "21.5°C").unwrap();
+    lcd.print_at(2, 0, "Pulse:").unwrap();
+    lcd.print_at(2, 15, "72").unwrap();
+    lcd.write_custom_char(0).unwrap();
+    lcd.print_at(3, 0, "Uptime 3d 4h").unwrap();
+    lcd.print();
+}
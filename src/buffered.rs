@@ -0,0 +1,357 @@
+//! Drawing into a RAM buffer and sending only the characters that changed.
+//!
+//! Text written to a [`BufferedLcd`] only changes its [`Frame`], nothing is sent until
+//! [`flush`](BufferedLcd::flush). That compares the frame with what was sent last and writes the
+//! runs of changed cells, moving the cursor only where a run doesn't continue at the address the
+//! previous one ended at.
+//!
+//! Screens composed of many prints can be drawn into the back buffer instead, see
+//! [`back_mut`](BufferedLcd::back_mut), so a flush never sends them half drawn.
+//!
+//! ```ignore
+//! let mut lcd = BufferedLcd::new(lcd);
+//! loop {
+//!     lcd.print_at(0, 0, "Temp")?;
+//!     uwrite!(lcd.frame_mut(), "{}", temperature)?;
+//!     lcd.flush()?;
+//! }
+//! ```
+
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use embedded_hal::delay::DelayNs;
+use ufmt_write::uWrite;
+
+use crate::{
+    charset::UnmappableChar, config::LcdConfig, geometry::Geometry, glyph::GlyphHandle,
+    interface::LcdInterface, state::translate_char, sync_lcd::GenericLcd, testing::Snapshot, Error,
+    UnmappablePolicy,
+};
+
+/// Character codes of every cell of a display, with a cursor to write text at.
+///
+/// Text is truncated at the end of a row, `'\n'` continues on the next row and `'\r'` at the
+/// start of the row. Characters are translated like the display translates them, with its
+/// `CharMapper`, `Charset` and `UnmappablePolicy`.
+#[derive(Clone)]
+pub struct Frame {
+    rows: u8,
+    columns: u8,
+    cells: [u8; 160],
+    cursor: (u8, u8),
+    config: LcdConfig,
+}
+
+impl Frame {
+    /// Frame with the contents of `snapshot`.
+    fn new(snapshot: &Snapshot, config: LcdConfig) -> Self {
+        let mut cells = [b' '; 160];
+        let columns = snapshot.columns();
+        for row in 0..snapshot.rows() {
+            let start = (row * columns) as usize;
+            cells[start..start + columns as usize].copy_from_slice(snapshot.row(row));
+        }
+        Self {
+            rows: snapshot.rows(),
+            columns,
+            cells,
+            cursor: (0, 0),
+            config,
+        }
+    }
+
+    /// Number of rows.
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Number of columns.
+    pub fn columns(&self) -> u8 {
+        self.columns
+    }
+
+    /// Character codes of `row`, empty if the row is outside of the display.
+    pub fn row(&self, row: u8) -> &[u8] {
+        if row >= self.rows {
+            return &[];
+        }
+        let start = (row * self.columns) as usize;
+        &self.cells[start..start + self.columns as usize]
+    }
+
+    /// Position text is written at, as (row, col).
+    ///
+    /// The column is the number of columns once a row is written to its end.
+    pub fn cursor(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    /// Move the cursor to (row, col), clipped to the display.
+    pub fn set_cursor(&mut self, row: u8, col: u8) {
+        self.cursor = (row.min(self.rows - 1), col.min(self.columns));
+    }
+
+    /// Write character codes at the cursor, e.g. custom characters (`0..8`) or characters of the
+    /// character ROM, truncated at the end of the row.
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        let (row, col) = self.cursor;
+        let len = data.len().min((self.columns - col) as usize);
+        let start = (row * self.columns + col) as usize;
+        self.cells[start..start + len].copy_from_slice(&data[..len]);
+        self.cursor.1 = col + len as u8;
+    }
+
+    /// Write a glyph installed with `install_glyph` at the cursor.
+    pub fn write_glyph(&mut self, glyph: &GlyphHandle) {
+        self.write_bytes(&[glyph.slot()]);
+    }
+
+    /// Write the string at the cursor.
+    ///
+    /// Returns the first character the display can't show if the [`UnmappablePolicy`] is
+    /// `Error`, the frame isn't changed then.
+    pub fn write_str(&mut self, s: &str) -> Result<(), UnmappableChar> {
+        if self.config.unmappable_policy == UnmappablePolicy::Error {
+            if let Some((index, character)) = s
+                .char_indices()
+                .find(|&(_, c)| !is_control(c) && translate_char(&self.config, c).is_none())
+            {
+                return Err(UnmappableChar { index, character });
+            }
+        }
+        for c in s.chars() {
+            self.write_one(c);
+        }
+        Ok(())
+    }
+
+    /// Write a single character at the cursor, see [`write_str`](Self::write_str).
+    pub fn write_char(&mut self, c: char) -> Result<(), UnmappableChar> {
+        let mut buf = [0; 4];
+        self.write_str(c.encode_utf8(&mut buf))
+    }
+
+    /// Move the cursor to (row, col) and write the string from there.
+    pub fn print_at(&mut self, row: u8, col: u8, s: &str) -> Result<(), UnmappableChar> {
+        self.set_cursor(row, col);
+        self.write_str(s)
+    }
+
+    /// Fill the frame with spaces and move the cursor home.
+    pub fn clear(&mut self) {
+        self.cells = [b' '; 160];
+        self.cursor = (0, 0);
+    }
+
+    /// Fill `row` with spaces, the cursor stays where it is.
+    pub fn clear_row(&mut self, row: u8) {
+        if row < self.rows {
+            let start = (row * self.columns) as usize;
+            self.cells[start..start + self.columns as usize].fill(b' ');
+        }
+    }
+
+    fn write_one(&mut self, c: char) {
+        match c {
+            '\n' if self.cursor.0 + 1 < self.rows => self.cursor = (self.cursor.0 + 1, 0),
+            // Text after the last row is dropped.
+            '\n' => self.cursor.1 = self.columns,
+            '\r' => self.cursor.1 = 0,
+            _ => {
+                let (code, second) = match translate_char(&self.config, c) {
+                    Some(codes) => codes,
+                    None => match self.config.unmappable_policy {
+                        UnmappablePolicy::Replace(byte) => (byte, None),
+                        UnmappablePolicy::Error => return,
+                    },
+                };
+                self.write_bytes(&[code]);
+                if let Some(second) = second {
+                    self.write_bytes(&[second]);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `c` moves the cursor instead of being written.
+fn is_control(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+impl fmt::Write for Frame {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Frame::write_str(self, s).map_err(|_| fmt::Error)
+    }
+}
+
+impl uWrite for Frame {
+    type Error = UnmappableChar;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        Frame::write_str(self, s)
+    }
+}
+
+/// A display drawn through a [`Frame`], sent with [`flush`](Self::flush).
+///
+/// The frame starts with what the display shows. The display has to write from left to right
+/// without shifting, the default. Call [`flush_all`](Self::flush_all) after changing the display
+/// with [`lcd_mut`](Self::lcd_mut), e.g. after `re_init`, so the frame is sent as a whole again.
+pub struct BufferedLcd<G, I, D>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    lcd: GenericLcd<G, I, D>,
+    frame: Frame,
+    back: Frame,
+    sent: [u8; 160],
+    redraw: bool,
+}
+
+impl<G, I, D> BufferedLcd<G, I, D>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+{
+    /// Draw on an initialized display.
+    pub fn new(lcd: GenericLcd<G, I, D>) -> Self {
+        let frame = Frame::new(&lcd.snapshot(), lcd.state().config);
+        Self {
+            lcd,
+            sent: frame.cells,
+            back: frame.clone(),
+            frame,
+            redraw: false,
+        }
+    }
+
+    /// The frame drawn into.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// The frame drawn into, for `write!` and `uwrite!`.
+    pub fn frame_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    /// The back buffer, drawn into without changing the frame.
+    pub fn back(&self) -> &Frame {
+        &self.back
+    }
+
+    /// The back buffer, for widgets to draw the next frame into.
+    ///
+    /// Nothing drawn into it is sent before [`swap_and_flush`](Self::swap_and_flush).
+    pub fn back_mut(&mut self) -> &mut Frame {
+        &mut self.back
+    }
+
+    /// Make the back buffer the frame and send what changed.
+    ///
+    /// The back buffer keeps its contents, the next frame is drawn on top of this one.
+    pub fn swap_and_flush(&mut self) -> Result<(), Error<I::Error>> {
+        self.frame = self.back.clone();
+        self.flush()
+    }
+
+    /// Send the cells of the frame that changed since the last flush.
+    ///
+    /// Runs of changed cells are written in one go, with a single unchanged cell between two runs
+    /// written along instead of moving the cursor. If the cursor is shown, it's moved to the
+    /// cursor of the frame afterwards.
+    pub fn flush(&mut self) -> Result<(), Error<I::Error>> {
+        let columns = self.frame.columns;
+        for row in 0..self.frame.rows {
+            let start = (row * columns) as usize;
+            let mut col = 0;
+            while let Some(first) = self.next_change(start, col) {
+                let mut end = first + 1;
+                // Changes at most one cell apart are written as one run.
+                while let Some(next) = self.next_change(start, end) {
+                    if next > end + 1 {
+                        break;
+                    }
+                    end = next + 1;
+                }
+                self.move_to(row, first)?;
+                let run = start + first as usize..start + end as usize;
+                self.lcd.write_bytes(&self.frame.cells[run.clone()])?;
+                self.sent[run.clone()].copy_from_slice(&self.frame.cells[run]);
+                col = end;
+            }
+        }
+        self.redraw = false;
+        if self.lcd.state().cursor_visible() {
+            let (row, col) = self.frame.cursor;
+            self.move_to(row, col.min(columns - 1))?;
+        }
+        Ok(())
+    }
+
+    /// Send the whole frame, e.g. after `re_init` cleared the display.
+    pub fn flush_all(&mut self) -> Result<(), Error<I::Error>> {
+        self.redraw = true;
+        self.flush()
+    }
+
+    /// The display, e.g. to switch the backlight.
+    pub fn lcd(&self) -> &GenericLcd<G, I, D> {
+        &self.lcd
+    }
+
+    /// The display, call [`flush_all`](Self::flush_all) after changing what it shows.
+    pub fn lcd_mut(&mut self) -> &mut GenericLcd<G, I, D> {
+        &mut self.lcd
+    }
+
+    /// Return the display, without flushing the frame.
+    pub fn into_inner(self) -> GenericLcd<G, I, D> {
+        self.lcd
+    }
+
+    /// Column of the first cell of the row starting at `start` that changed, from `col` on.
+    fn next_change(&self, start: usize, col: u8) -> Option<u8> {
+        (col..self.frame.columns).find(|&col| {
+            let i = start + col as usize;
+            self.redraw || self.frame.cells[i] != self.sent[i]
+        })
+    }
+
+    /// Set the cursor to (row, col) unless the display is there already.
+    fn move_to(&mut self, row: u8, col: u8) -> Result<(), Error<I::Error>> {
+        let state = self.lcd.state();
+        if state.address_counter == state.address_of(row, col) {
+            return Ok(());
+        }
+        self.lcd.set_cursor(row, col)
+    }
+}
+
+impl<G, I, D> Deref for BufferedLcd<G, I, D>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    type Target = Frame;
+
+    fn deref(&self) -> &Self::Target {
+        &self.frame
+    }
+}
+
+impl<G, I, D> DerefMut for BufferedLcd<G, I, D>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.frame
+    }
+}
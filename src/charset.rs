@@ -0,0 +1,266 @@
+//! Translation of characters to the character codes of the display ROM.
+
+use core::fmt;
+
+use crate::Charset;
+
+/// Translation of characters to character codes, e.g. for displays with a custom ROM or for
+/// custom characters.
+///
+/// Installed with `with_char_mapper`, the mapper is asked first for every written character.
+/// Text written with `write_bytes` isn't translated. Mappers are shared by reference, they are
+/// `Sync` so the driver can be sent to another thread.
+///
+/// ```
+/// use lcd_lcm1602_i2c::charset::CharMapper;
+///
+/// /// Write a smiley installed in CGRAM slot 0 for `'☺'`.
+/// struct Smiley;
+///
+/// impl CharMapper for Smiley {
+///     fn map(&self, c: char) -> Option<u8> {
+///         (c == '☺').then_some(0)
+///     }
+/// }
+/// ```
+pub trait CharMapper: Sync {
+    /// Character code of `c`, or `None` to translate it with the [`Charset`] of the display.
+    fn map(&self, c: char) -> Option<u8>;
+}
+
+/// A character of a string the display can't show, see `validate_str`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UnmappableChar {
+    /// Byte index of the character in the string.
+    pub index: usize,
+    /// The character.
+    pub character: char,
+}
+
+impl fmt::Display for UnmappableChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at {} can't be shown", self.character, self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnmappableChar {}
+
+/// Translate `c` to the character codes of `charset`.
+///
+/// Returns the code and the code of a second character written after it, e.g. the sound mark of
+/// a full-width Katakana.
+pub(crate) fn translate(charset: Charset, c: char) -> Option<(u8, Option<u8>)> {
+    match charset {
+        // The A00 ROM has the yen sign and an arrow instead of the backslash and the tilde.
+        Charset::A00 if c == '\\' || c == '~' => None,
+        _ if c.is_ascii() => Some((c as u8, None)),
+        Charset::A00 => {
+            if let Some(code) = symbols_a00(c) {
+                return Some((code, None));
+            }
+            #[cfg(feature = "katakana")]
+            return katakana_a00(c);
+            #[cfg(not(feature = "katakana"))]
+            None
+        }
+        #[cfg(feature = "rom-a02")]
+        Charset::A02 => a02(c).map(|code| (code, None)),
+    }
+}
+
+/// Symbols and the closest letters of the A00 ROM with their codes. The first character of a code
+/// is the one the code stands for.
+const SYMBOLS_A00: [(char, u8); 28] = [
+    ('¥', 0x5C),
+    ('→', 0x7E),
+    ('←', 0x7F),
+    ('°', 0xDF),
+    ('α', 0xE0),
+    ('ä', 0xE1),
+    ('Ä', 0xE1),
+    ('ß', 0xE2),
+    ('β', 0xE2),
+    ('ε', 0xE3),
+    ('µ', 0xE4),
+    ('μ', 0xE4),
+    ('σ', 0xE5),
+    ('ρ', 0xE6),
+    ('√', 0xE8),
+    ('¢', 0xEC),
+    ('ñ', 0xEE),
+    ('ö', 0xEF),
+    ('Ö', 0xEF),
+    ('θ', 0xF2),
+    ('∞', 0xF3),
+    ('Ω', 0xF4),
+    ('ü', 0xF5),
+    ('Ü', 0xF5),
+    ('Σ', 0xF6),
+    ('π', 0xF7),
+    ('÷', 0xFD),
+    ('█', 0xFF),
+];
+
+/// Translate the symbols and the closest letters of the A00 ROM.
+fn symbols_a00(c: char) -> Option<u8> {
+    SYMBOLS_A00
+        .iter()
+        .find(|&&(symbol, _)| symbol == c)
+        .map(|&(_, code)| code)
+}
+
+/// Character shown for `code` by the A00 ROM, custom characters as the number of their CGRAM
+/// slot and codes without a matching character as `'?'`.
+pub(crate) fn shown_char(code: u8) -> char {
+    match code {
+        0x00..=0x0F => char::from(b'0' + (code & 7)),
+        _ => decode_a00(code).unwrap_or('?'),
+    }
+}
+
+/// Character shown for `code` by the A00 ROM, `None` for the CGRAM and codes without a
+/// matching character.
+fn decode_a00(code: u8) -> Option<char> {
+    match code {
+        0x5C => Some('¥'),
+        0x20..=0x7D => Some(code as char),
+        // Half-width punctuation and Katakana are in the same order in the ROM.
+        0xA1..=0xDE => char::from_u32(0xFF61 + u32::from(code - 0xA1)),
+        _ => SYMBOLS_A00
+            .iter()
+            .find(|&&(_, symbol)| symbol == code)
+            .map(|&(c, _)| c),
+    }
+}
+
+/// Dakuten, the voiced sound mark.
+#[cfg(feature = "katakana")]
+const DAKUTEN: u8 = 0xDE;
+/// Handakuten, the semi-voiced sound mark.
+#[cfg(feature = "katakana")]
+const HANDAKUTEN: u8 = 0xDF;
+
+/// Codes of the full-width Katakana `'ァ'` (U+30A1) up to `'ー'` (U+30FC), followed by the
+/// code of the sound mark if there is one.
+///
+/// Small and archaic Katakana missing from the ROM are written with the closest Katakana.
+#[cfg(feature = "katakana")]
+#[rustfmt::skip]
+const FULL_WIDTH_KATAKANA: [(u8, u8); 92] = [
+    // ァ ア ィ イ ゥ ウ
+    (0xA7, 0), (0xB1, 0), (0xA8, 0), (0xB2, 0), (0xA9, 0), (0xB3, 0),
+    // ェ エ ォ オ カ ガ
+    (0xAA, 0), (0xB4, 0), (0xAB, 0), (0xB5, 0), (0xB6, 0), (0xB6, 0xDE),
+    // キ ギ ク グ ケ ゲ
+    (0xB7, 0), (0xB7, 0xDE), (0xB8, 0), (0xB8, 0xDE), (0xB9, 0), (0xB9, 0xDE),
+    // コ ゴ サ ザ シ ジ
+    (0xBA, 0), (0xBA, 0xDE), (0xBB, 0), (0xBB, 0xDE), (0xBC, 0), (0xBC, 0xDE),
+    // ス ズ セ ゼ ソ ゾ
+    (0xBD, 0), (0xBD, 0xDE), (0xBE, 0), (0xBE, 0xDE), (0xBF, 0), (0xBF, 0xDE),
+    // タ ダ チ ヂ ッ ツ
+    (0xC0, 0), (0xC0, 0xDE), (0xC1, 0), (0xC1, 0xDE), (0xAF, 0), (0xC2, 0),
+    // ヅ テ デ ト ド ナ
+    (0xC2, 0xDE), (0xC3, 0), (0xC3, 0xDE), (0xC4, 0), (0xC4, 0xDE), (0xC5, 0),
+    // ニ ヌ ネ ノ ハ バ
+    (0xC6, 0), (0xC7, 0), (0xC8, 0), (0xC9, 0), (0xCA, 0), (0xCA, 0xDE),
+    // パ ヒ ビ ピ フ ブ
+    (0xCA, 0xDF), (0xCB, 0), (0xCB, 0xDE), (0xCB, 0xDF), (0xCC, 0), (0xCC, 0xDE),
+    // プ ヘ ベ ペ ホ ボ
+    (0xCC, 0xDF), (0xCD, 0), (0xCD, 0xDE), (0xCD, 0xDF), (0xCE, 0), (0xCE, 0xDE),
+    // ポ マ ミ ム メ モ
+    (0xCE, 0xDF), (0xCF, 0), (0xD0, 0), (0xD1, 0), (0xD2, 0), (0xD3, 0),
+    // ャ ヤ ュ ユ ョ ヨ
+    (0xAC, 0), (0xD4, 0), (0xAD, 0), (0xD5, 0), (0xAE, 0), (0xD6, 0),
+    // ラ リ ル レ ロ ヮ
+    (0xD7, 0), (0xD8, 0), (0xD9, 0), (0xDA, 0), (0xDB, 0), (0xDC, 0),
+    // ワ ヰ ヱ ヲ ン ヴ
+    (0xDC, 0), (0xB2, 0), (0xB4, 0), (0xA6, 0), (0xDD, 0), (0xB3, 0xDE),
+    // ヵ ヶ ヷ ヸ ヹ ヺ
+    (0xB6, 0), (0xB9, 0), (0xDC, 0xDE), (0xB2, 0xDE), (0xB4, 0xDE), (0xA6, 0xDE),
+    // ・ ー
+    (0xA5, 0), (0xB0, 0),
+];
+
+/// Translate Japanese punctuation and Katakana to the codes of the A00 ROM.
+///
+/// Full-width Katakana with sound marks are written as two half-width characters.
+#[cfg(feature = "katakana")]
+fn katakana_a00(c: char) -> Option<(u8, Option<u8>)> {
+    let code = match c {
+        // Half-width punctuation and Katakana are in the same order in the ROM.
+        '\u{FF61}'..='\u{FF9F}' => (c as u32 - 0xFF61 + 0xA1) as u8,
+        '\u{3002}' => 0xA1,
+        '\u{300C}' => 0xA2,
+        '\u{300D}' => 0xA3,
+        '\u{3001}' => 0xA4,
+        '\u{3099}' | '\u{309B}' => DAKUTEN,
+        '\u{309A}' | '\u{309C}' => HANDAKUTEN,
+        '\u{30A1}'..='\u{30FC}' => {
+            let (code, mark) = FULL_WIDTH_KATAKANA[(c as u32 - 0x30A1) as usize];
+            return Some((code, Some(mark).filter(|&m| m != 0)));
+        }
+        _ => return None,
+    };
+    Some((code, None))
+}
+
+/// Translate Latin-1, Greek and Cyrillic letters and a few symbols to the codes of the A02 ROM.
+///
+/// Cyrillic capitals looking like Latin ones are written with the Latin letter.
+#[cfg(feature = "rom-a02")]
+fn a02(c: char) -> Option<u8> {
+    let code = match c {
+        // Latin-1 symbols and letters are mostly at their Latin-1 code.
+        '\u{A1}'..='\u{A7}' | '\u{A9}'..='\u{AB}' | '\u{AE}' => c as u8,
+        '\u{B0}'..='\u{B3}' | '\u{B5}'..='\u{B7}' | '\u{B9}'..='\u{BF}' => c as u8,
+        '\u{C0}'..='\u{FF}' => c as u8,
+        '↑' => 0x18,
+        '↓' => 0x19,
+        '→' => 0x1A,
+        '←' => 0x1B,
+        '≤' => 0x1C,
+        '≥' => 0x1D,
+        'Б' => 0x80,
+        'Д' => 0x81,
+        'Ж' => 0x82,
+        'З' => 0x83,
+        'И' => 0x84,
+        'Й' => 0x85,
+        'Л' => 0x86,
+        'П' => 0x87,
+        'У' => 0x88,
+        'Ц' => 0x89,
+        'Ч' => 0x8A,
+        'Ш' => 0x8B,
+        'Щ' => 0x8C,
+        'Ъ' => 0x8D,
+        'Ы' => 0x8E,
+        'Э' => 0x8F,
+        'А' => b'A',
+        'В' => b'B',
+        'Е' => b'E',
+        'К' => b'K',
+        'М' => b'M',
+        'Н' => b'H',
+        'О' => b'O',
+        'Р' => b'P',
+        'С' => b'C',
+        'Т' => b'T',
+        'Х' => b'X',
+        'α' => 0x90,
+        'Γ' | 'Г' => 0x92,
+        'π' => 0x93,
+        'Σ' => 0x94,
+        'σ' => 0x95,
+        'τ' => 0x97,
+        'Θ' => 0x99,
+        'Ω' => 0x9A,
+        'δ' => 0x9B,
+        '∞' => 0x9C,
+        'ε' => 0x9E,
+        _ => return None,
+    };
+    Some(code)
+}
@@ -0,0 +1,94 @@
+//! Adapters for HALs that only implement the embedded-hal 0.2 traits.
+//!
+//! ```ignore
+//! use lcd_lcm1602_i2c::compat::{DelayCompat, I2cCompat};
+//!
+//! let mut lcd = lcd_lcm1602_i2c::LCD16x2::new(I2cCompat(i2c), DelayCompat(delay))
+//!     .with_address(0x27)
+//!     .init()?;
+//! ```
+
+use core::fmt::Debug;
+
+use eh0::blocking::{
+    delay::{DelayMs, DelayUs},
+    i2c::{Read, Write},
+};
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorKind, ErrorType, I2c, Operation},
+};
+
+/// I2C bus implementing the embedded-hal 0.2 blocking `Write` and `Read` traits.
+///
+/// The operations of a transaction are run one after another without a repeated start, the driver
+/// only uses single writes and reads.
+#[derive(Debug)]
+pub struct I2cCompat<T>(pub T);
+
+/// Error of the bus wrapped by [`I2cCompat`].
+///
+/// embedded-hal 0.2 errors don't tell what went wrong, so every error is
+/// [`ErrorKind::Other`] and `probe` and `detect_address` report a missing display as error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CompatError<E>(pub E);
+
+impl<E: Debug> embedded_hal::i2c::Error for CompatError<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<T, E> ErrorType for I2cCompat<T>
+where
+    T: Write<Error = E> + Read<Error = E>,
+    E: Debug,
+{
+    type Error = CompatError<E>;
+}
+
+impl<T, E> I2c for I2cCompat<T>
+where
+    T: Write<Error = E> + Read<Error = E>,
+    E: Debug,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.0.write(address, bytes),
+                Operation::Read(buf) => self.0.read(address, buf),
+            }
+            .map_err(CompatError)?;
+        }
+        Ok(())
+    }
+}
+
+/// Delay implementing the embedded-hal 0.2 `DelayUs<u32>` and `DelayMs<u32>` traits.
+///
+/// All [`Timings`](crate::config::Timings) are whole µs and passed on unchanged, shorter waits
+/// are rounded up to 1 µs.
+#[derive(Debug)]
+pub struct DelayCompat<T>(pub T);
+
+impl<T> DelayNs for DelayCompat<T>
+where
+    T: DelayUs<u32> + DelayMs<u32>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns.div_ceil(1_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.0.delay_ms(ms);
+    }
+}
@@ -0,0 +1,493 @@
+use core::fmt;
+
+#[cfg(doc)]
+use crate::Error;
+use crate::{
+    charset::CharMapper, Backlight, Charset, Controller, Font, NewlineMode, OverflowPolicy,
+    Polarity, UnmappablePolicy,
+};
+
+/// Options the LCD is set up with, applied by `init()`.
+///
+/// ```
+/// use lcd_lcm1602_i2c::{config::LcdConfig, Backlight};
+///
+/// let config = LcdConfig::default()
+///     .with_address(0x27)
+///     .with_backlight(Backlight::Off)
+///     .with_cursor_on(true);
+/// ```
+#[derive(Copy, Clone)]
+pub struct LcdConfig {
+    pub(crate) address: u8,
+    pub(crate) backlight: Backlight,
+    pub(crate) backlight_polarity: Polarity,
+    pub(crate) pins: PinMapping,
+    pub(crate) cursor_on: bool,
+    pub(crate) cursor_blink: bool,
+    pub(crate) font: Font,
+    pub(crate) newline_mode: NewlineMode,
+    pub(crate) clamp_regions: bool,
+    pub(crate) tab_width: u8,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) unmappable_policy: UnmappablePolicy,
+    pub(crate) charset: Charset,
+    pub(crate) char_mapper: Option<&'static dyn CharMapper>,
+    pub(crate) busy_flag_polling: bool,
+    pub(crate) timings: Timings,
+    pub(crate) i2c_retries: u8,
+    pub(crate) i2c_transfer_limit: Option<u8>,
+    pub(crate) row_offsets: Option<[u8; 4]>,
+    pub(crate) controller: Controller,
+    pub(crate) split_row: bool,
+}
+
+// The char mapper is a trait object, only whether one is installed is shown.
+impl fmt::Debug for LcdConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LcdConfig")
+            .field("address", &self.address)
+            .field("backlight", &self.backlight)
+            .field("backlight_polarity", &self.backlight_polarity)
+            .field("pins", &self.pins)
+            .field("cursor_on", &self.cursor_on)
+            .field("cursor_blink", &self.cursor_blink)
+            .field("font", &self.font)
+            .field("newline_mode", &self.newline_mode)
+            .field("clamp_regions", &self.clamp_regions)
+            .field("tab_width", &self.tab_width)
+            .field("overflow_policy", &self.overflow_policy)
+            .field("unmappable_policy", &self.unmappable_policy)
+            .field("charset", &self.charset)
+            .field("char_mapper", &self.char_mapper.is_some())
+            .field("busy_flag_polling", &self.busy_flag_polling)
+            .field("timings", &self.timings)
+            .field("i2c_retries", &self.i2c_retries)
+            .field("i2c_transfer_limit", &self.i2c_transfer_limit)
+            .field("row_offsets", &self.row_offsets)
+            .field("controller", &self.controller)
+            .field("split_row", &self.split_row)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for LcdConfig {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "LcdConfig {{ address: {=u8:#x}, backlight: {}, backlight_polarity: {}, pins: {}, \
+             cursor_on: {}, cursor_blink: {}, font: {}, newline_mode: {}, clamp_regions: {}, \
+             tab_width: {}, overflow_policy: {}, unmappable_policy: {}, charset: {}, \
+             char_mapper: {}, busy_flag_polling: {}, timings: {}, i2c_retries: {}, \
+             i2c_transfer_limit: {}, row_offsets: {}, controller: {}, split_row: {} }}",
+            self.address,
+            self.backlight,
+            self.backlight_polarity,
+            self.pins,
+            self.cursor_on,
+            self.cursor_blink,
+            self.font,
+            self.newline_mode,
+            self.clamp_regions,
+            self.tab_width,
+            self.overflow_policy,
+            self.unmappable_policy,
+            self.charset,
+            self.char_mapper.is_some(),
+            self.busy_flag_polling,
+            self.timings,
+            self.i2c_retries,
+            self.i2c_transfer_limit,
+            self.row_offsets,
+            self.controller,
+            self.split_row,
+        )
+    }
+}
+
+impl Default for LcdConfig {
+    fn default() -> Self {
+        Self {
+            address: 0,
+            backlight: Backlight::On,
+            backlight_polarity: Polarity::ActiveHigh,
+            pins: PinMapping::default(),
+            cursor_on: false,
+            cursor_blink: false,
+            font: Font::Font5x8,
+            newline_mode: NewlineMode::Clip,
+            clamp_regions: true,
+            tab_width: 4,
+            overflow_policy: OverflowPolicy::Truncate,
+            unmappable_policy: UnmappablePolicy::Replace(b'?'),
+            charset: Charset::A00,
+            char_mapper: None,
+            busy_flag_polling: false,
+            timings: Timings::default(),
+            i2c_retries: 0,
+            i2c_transfer_limit: None,
+            row_offsets: None,
+            controller: Controller::Hd44780,
+            split_row: false,
+        }
+    }
+}
+
+impl LcdConfig {
+    /// Set I2C address, see [lcd address].
+    ///
+    /// There is no default, `init()` fails with [`NoAddressConfigured`] if no address is set and
+    /// with [`ReservedAddress`] for addresses reserved by the I2C specification.
+    ///
+    /// [lcd address]: https://badboi.dev/rust,/microcontrollers/2020/11/09/i2c-hello-world.html
+    /// [`NoAddressConfigured`]: crate::Error::NoAddressConfigured
+    /// [`ReservedAddress`]: crate::Error::ReservedAddress
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Set the backlight state after init. Defaults to [`Backlight::On`].
+    pub fn with_backlight(mut self, backlight: Backlight) -> Self {
+        self.backlight = backlight;
+        self
+    }
+
+    /// Set the level of the expander pin switching the backlight on. Defaults to
+    /// [`Polarity::ActiveHigh`].
+    pub fn with_backlight_polarity(mut self, polarity: Polarity) -> Self {
+        self.backlight_polarity = polarity;
+        self
+    }
+
+    /// Set how the display is wired to the expander, see [`PinMapping`]. Defaults to
+    /// [`PinMapping::default()`].
+    pub fn with_pin_mapping(mut self, pins: PinMapping) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Set if the cursor is visible. Defaults to `false`.
+    pub fn with_cursor_on(mut self, on: bool) -> Self {
+        self.cursor_on = on;
+        self
+    }
+
+    /// Set if the cursor is blinking. Defaults to `false`.
+    pub fn with_cursor_blink(mut self, blink: bool) -> Self {
+        self.cursor_blink = blink;
+        self
+    }
+
+    /// Set the font. Defaults to [`Font::Font5x8`].
+    ///
+    /// [`Font::Font5x10`] only works on displays with one row, `init()` fails with
+    /// [`UnsupportedFontForGeometry`] otherwise.
+    ///
+    /// [`UnsupportedFontForGeometry`]: crate::Error::UnsupportedFontForGeometry
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Set what a newline on the last row does, see [`NewlineMode`]. Defaults to
+    /// [`NewlineMode::Clip`].
+    pub fn with_newline_mode(mut self, mode: NewlineMode) -> Self {
+        self.newline_mode = mode;
+        self
+    }
+
+    /// Set if `clear_region` clamps regions reaching past the last column instead of returning
+    /// an error. Defaults to `true`.
+    pub fn with_clamp_regions(mut self, clamp: bool) -> Self {
+        self.clamp_regions = clamp;
+        self
+    }
+
+    /// Set the distance between tab stops for `'\t'`. Defaults to 4.
+    pub fn with_tab_width(mut self, width: u8) -> Self {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    /// Set what happens when text is written past the end of a row, see [`OverflowPolicy`].
+    /// Defaults to [`OverflowPolicy::Truncate`].
+    ///
+    /// The policy only applies while writing left to right without autoscroll.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set what happens to characters the display can't show, see [`UnmappablePolicy`].
+    /// Defaults to [`UnmappablePolicy::Replace`] with `b'?'`.
+    pub fn with_unmappable_policy(mut self, policy: UnmappablePolicy) -> Self {
+        self.unmappable_policy = policy;
+        self
+    }
+
+    /// Set the character ROM of the display text is translated for. Defaults to
+    /// [`Charset::A00`].
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Set the controller of the display, see [`Controller`]. Defaults to
+    /// [`Controller::Hd44780`].
+    pub fn with_controller(mut self, controller: Controller) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    /// Install a [`CharMapper`] asked before the [`Charset`] to translate written characters.
+    pub fn with_char_mapper(mut self, mapper: &'static dyn CharMapper) -> Self {
+        self.char_mapper = Some(mapper);
+        self
+    }
+
+    /// Set if the busy flag of the controller is polled instead of waiting a fixed time after
+    /// each write. Defaults to `false`.
+    ///
+    /// Needs the RW pin of the display connected to the expander. If the busy flag doesn't clear
+    /// in time, e.g. because RW is tied to ground, the driver falls back to fixed delays.
+    pub fn with_busy_flag_polling(mut self, poll: bool) -> Self {
+        self.busy_flag_polling = poll;
+        self
+    }
+
+    /// Set the delays the driver waits for the display, see [`Timings`]. Defaults to
+    /// [`Timings::default()`].
+    pub fn with_timings(mut self, timings: Timings) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Set how often a failed I2C transfer is retried before the error is returned, e.g. on a
+    /// bus shared with other devices. Defaults to 0.
+    ///
+    /// A transaction clocking a nibble or a byte into the controller is only retried if the
+    /// expander didn't acknowledge it, repeating one that was cut off later could clock it in
+    /// twice.
+    pub fn with_i2c_retries(mut self, retries: u8) -> Self {
+        self.i2c_retries = retries;
+        self
+    }
+
+    /// Limit the bytes written to the expander in one I2C transaction, for HALs that can't write
+    /// more at once. Defaults to no limit.
+    ///
+    /// Characters written together, e.g. by `write_str`, are sent as 6 expander writes each in
+    /// transactions of up to 16 characters, or as many as fit into the limit. With a limit below
+    /// 6 bytes every character is sent on its own, split into transactions of at most `bytes`.
+    pub fn with_i2c_transfer_limit(mut self, bytes: u8) -> Self {
+        self.i2c_transfer_limit = Some(bytes);
+        self
+    }
+
+    /// Override the DDRAM address of the first column of each row.
+    ///
+    /// By default, the offsets are picked based on the size of the display. Offsets of `0x68` and
+    /// above are outside of the DDRAM and make `init()` fail, like `0x28` to `0x3f` on displays
+    /// with more than one row, which sit between the two lines.
+    pub fn with_row_offsets(mut self, offsets: [u8; 4]) -> Self {
+        self.row_offsets = Some(offsets);
+        self
+    }
+
+    /// Address the single row of the display in two halves, the left one at DDRAM `0x00` and the
+    /// right one at `0x40`, like most 16x1 modules are wired: they are 8x2 displays with both
+    /// lines side by side. Defaults to `false`, only used for displays with one row.
+    ///
+    /// The controller is set up with two lines, `set_cursor` and written text reach the right
+    /// half transparently. Text written with [`QueuedLcd`](crate::queued::QueuedLcd) doesn't
+    /// continue in the right half, set the cursor to its first column there.
+    pub fn with_split_row(mut self, split: bool) -> Self {
+        self.split_row = split;
+        self
+    }
+
+    /// Bit of the expander switching the backlight to `backlight`, taking the polarity into account.
+    pub(crate) fn backlight_bit(&self, backlight: Backlight) -> u8 {
+        match self.backlight_polarity {
+            Polarity::ActiveHigh => backlight as u8,
+            Polarity::ActiveLow => backlight as u8 ^ Backlight::On as u8,
+        }
+    }
+
+    /// Expander byte probing for a backpack: only the backlight state, EN and the data lines low
+    /// so the controller isn't affected.
+    pub(crate) fn probe_byte(&self) -> u8 {
+        self.pins.map_out(self.backlight_bit(self.backlight))
+    }
+}
+
+/// Delays the driver waits for the display to process instructions, in µs.
+///
+/// The defaults work for the common HD44780 backpacks, some displays need longer delays or work
+/// with shorter ones.
+///
+/// ```
+/// use lcd_lcm1602_i2c::config::{LcdConfig, Timings};
+///
+/// let config = LcdConfig::default().with_timings(Timings {
+///     power_on_us: 50_000,
+///     ..Timings::default()
+/// });
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timings {
+    /// Wait for the display to power up at the start of `init()`.
+    pub power_on_us: u32,
+    /// Wait after switching the backlight on at the start of `init()`.
+    pub backlight_us: u32,
+    /// Wait after each of the function sets putting the display into a known state in `init()`.
+    pub function_set_us: u32,
+    /// Wait after each nibble `init()` sends on its own while switching the display to 4 bit
+    /// mode. Bytes are sent with both nibbles back to back.
+    pub nibble_us: u32,
+    /// Wait for the display to execute an instruction or to store a character.
+    pub command_us: u32,
+    /// Wait for the display to clear or to return the cursor home, instead of `command_us`.
+    pub clear_us: u32,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            power_on_us: 80_000,
+            backlight_us: 1_000,
+            function_set_us: 5_000,
+            nibble_us: 700,
+            command_us: 50,
+            clear_us: 2_000,
+        }
+    }
+}
+
+impl Timings {
+    /// Generous delays for slow displays, e.g. at low temperatures or with a weak supply.
+    pub fn conservative() -> Self {
+        Self {
+            power_on_us: 150_000,
+            backlight_us: 1_000,
+            function_set_us: 10_000,
+            nibble_us: 1_000,
+            command_us: 100,
+            clear_us: 5_000,
+        }
+    }
+
+    /// Delays close to the minimums of the HD44780U datasheet.
+    pub fn fast() -> Self {
+        Self {
+            power_on_us: 40_000,
+            backlight_us: 0,
+            function_set_us: 4_100,
+            nibble_us: 40,
+            command_us: 37,
+            clear_us: 1_600,
+        }
+    }
+}
+
+/// Expander pins (0 to 7) the pins of the display are connected to.
+///
+/// The default is the wiring of the common PCF8574 backpacks: RS on P0, RW on P1, EN on P2, the
+/// backlight on P3 and D4 to D7 on P4 to P7.
+///
+/// ```
+/// use lcd_lcm1602_i2c::config::{LcdConfig, PinMapping};
+///
+/// // Backpack with the data lines on the lower pins
+/// let config = LcdConfig::default().with_pin_mapping(PinMapping {
+///     rs: 4,
+///     rw: 5,
+///     en: 6,
+///     backlight: 7,
+///     data: [0, 1, 2, 3],
+/// });
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PinMapping {
+    /// Register select.
+    pub rs: u8,
+    /// Read/write select, or the enable pin of the second controller of displays with two, see
+    /// [`Dual`](crate::geometry::Dual).
+    pub rw: u8,
+    /// Enable.
+    pub en: u8,
+    /// Backlight.
+    pub backlight: u8,
+    /// D4 to D7.
+    pub data: [u8; 4],
+}
+
+impl Default for PinMapping {
+    fn default() -> Self {
+        Self {
+            rs: 0,
+            rw: 1,
+            en: 2,
+            backlight: 3,
+            data: [4, 5, 6, 7],
+        }
+    }
+}
+
+impl PinMapping {
+    /// Wiring of the MCP23008 backpacks by Adafruit: RS on GP1, EN on GP2, D4 to D7 on GP3 to
+    /// GP6 and the backlight on GP7. RW is tied to ground, GP0 is left unused for it.
+    pub fn mcp23008() -> Self {
+        Self {
+            rs: 1,
+            rw: 0,
+            en: 2,
+            backlight: 7,
+            data: [3, 4, 5, 6],
+        }
+    }
+
+    /// Expander pin of each bit of the default wiring.
+    fn pins(&self) -> [u8; 8] {
+        let [d4, d5, d6, d7] = self.data;
+        [self.rs, self.rw, self.en, self.backlight, d4, d5, d6, d7]
+    }
+
+    /// Check that every pin exists and is used once.
+    pub(crate) fn is_valid(&self) -> bool {
+        let mut used = 0u8;
+        for pin in self.pins() {
+            if pin >= 8 || used & (1 << pin) != 0 {
+                return false;
+            }
+            used |= 1 << pin;
+        }
+        true
+    }
+
+    /// Move the bits of `byte` from the default wiring to the pins of this mapping.
+    pub(crate) fn map_out(&self, byte: u8) -> u8 {
+        let mut pins = 0;
+        for (bit, &pin) in self.pins().iter().enumerate() {
+            if byte & (1 << bit) != 0 {
+                pins |= 1 << pin;
+            }
+        }
+        pins
+    }
+
+    /// Move the bits read from the pins of this mapping to the default wiring.
+    pub(crate) fn map_in(&self, pins: u8) -> u8 {
+        let mut byte = 0;
+        for (bit, &pin) in self.pins().iter().enumerate() {
+            if pins & (1 << pin) != 0 {
+                byte |= 1 << bit;
+            }
+        }
+        byte
+    }
+}
@@ -0,0 +1,147 @@
+//! Formatting numbers without `core::fmt`, shared by the sync and async driver.
+
+/// Large enough for every `i32` and `u32`, including the sign.
+pub(crate) const NUMBER_LEN: usize = 11;
+
+/// Format `value` into the end of `buf`, returning the digits.
+pub(crate) fn format_u32(value: u32, buf: &mut [u8; NUMBER_LEN]) -> &[u8] {
+    let mut value = value;
+    let mut start = NUMBER_LEN;
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    &buf[start..]
+}
+
+/// Format `value` into the end of `buf`, returning the sign and digits.
+pub(crate) fn format_i32(value: i32, buf: &mut [u8; NUMBER_LEN]) -> &[u8] {
+    let start = NUMBER_LEN - format_u32(value.unsigned_abs(), buf).len();
+    if value < 0 {
+        buf[start - 1] = b'-';
+        &buf[start - 1..]
+    } else {
+        &buf[start..]
+    }
+}
+
+/// Decimals supported by [`format_fixed`], all digits of an `i32`.
+pub(crate) const MAX_DECIMALS: u8 = 10;
+
+/// Large enough for every value of [`format_fixed`], including sign, leading zero and point.
+pub(crate) const FIXED_LEN: usize = NUMBER_LEN + 2;
+
+/// Format `value / 10^decimals` into the end of `buf`, returning the sign, digits and point.
+///
+/// Returns `None` if `decimals` is more than [`MAX_DECIMALS`].
+pub(crate) fn format_fixed(value: i32, decimals: u8, buf: &mut [u8; FIXED_LEN]) -> Option<&[u8]> {
+    if decimals > MAX_DECIMALS {
+        return None;
+    }
+    let mut rest = value.unsigned_abs();
+    let mut start = FIXED_LEN;
+    for _ in 0..decimals {
+        start -= 1;
+        buf[start] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+    }
+    if decimals > 0 {
+        start -= 1;
+        buf[start] = b'.';
+    }
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        start -= 1;
+        buf[start] = b'-';
+    }
+    Some(&buf[start..])
+}
+
+/// The part of some formatted text starting at byte `start`, at most one DDRAM worth of bytes,
+/// for writing formatted text with the async driver.
+///
+/// Formatting stops once the part is full, the rest is written by formatting the text again into
+/// a part starting at [`next`](Self::next).
+#[cfg(feature = "async")]
+pub(crate) struct TextChunk {
+    start: usize,
+    /// Bytes of the formatted text seen so far.
+    offset: usize,
+    bytes: [u8; 80],
+    len: usize,
+    next: Option<usize>,
+}
+
+#[cfg(feature = "async")]
+impl TextChunk {
+    pub(crate) fn new(start: usize) -> Self {
+        Self {
+            start,
+            offset: 0,
+            bytes: [0; 80],
+            len: 0,
+            next: None,
+        }
+    }
+
+    /// The characters of the part with their byte index in the whole text.
+    pub(crate) fn char_indices(&self) -> impl Iterator<Item = (usize, char)> + '_ {
+        // Only whole chars are copied in.
+        let text = core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default();
+        text.char_indices()
+            .map(move |(index, c)| (self.start + index, c))
+    }
+
+    /// Where the next part starts, `None` if this one ends the text.
+    pub(crate) fn next(&self) -> Option<usize> {
+        self.next
+    }
+
+    fn push(&mut self, s: &str) -> core::fmt::Result {
+        if self.next.is_some() {
+            return Err(core::fmt::Error);
+        }
+        for (index, c) in s.char_indices() {
+            let index = self.offset + index;
+            if index < self.start {
+                continue;
+            }
+            let end = self.len + c.len_utf8();
+            if end > self.bytes.len() {
+                self.next = Some(index);
+                return Err(core::fmt::Error);
+            }
+            c.encode_utf8(&mut self.bytes[self.len..end]);
+            self.len = end;
+        }
+        self.offset += s.len();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl core::fmt::Write for TextChunk {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.push(s)
+    }
+}
+
+#[cfg(feature = "async")]
+impl ufmt_write::uWrite for TextChunk {
+    type Error = core::fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.push(s)
+    }
+}
@@ -0,0 +1,176 @@
+//! Size of the display, either known at compile time or only at runtime.
+
+/// Number of rows and columns of a display.
+pub trait Geometry {
+    fn rows(&self) -> u8;
+    fn columns(&self) -> u8;
+
+    /// Number of HD44780 controllers driving the display, see [`Dual`].
+    fn controllers(&self) -> u8 {
+        1
+    }
+}
+
+/// Geometry of a display driven by a single controller, every one but [`Dual`].
+pub trait SingleController: Geometry {}
+
+/// Size known at compile time. Creating a driver with an unsupported size fails the build, like
+/// one with five rows:
+///
+/// ```compile_fail
+/// use lcd_lcm1602_i2c::sync_lcd::Lcd;
+/// # use embedded_hal::i2c::{ErrorType, I2c, Operation};
+/// # struct Bus;
+/// # impl ErrorType for Bus {
+/// #     type Error = core::convert::Infallible;
+/// # }
+/// # impl I2c for Bus {
+/// #     fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// # struct Delay;
+/// # impl embedded_hal::delay::DelayNs for Delay {
+/// #     fn delay_ns(&mut self, _ns: u32) {}
+/// # }
+///
+/// let lcd = Lcd::<5, 20, _, _>::new(Bus, Delay);
+/// ```
+///
+/// Or one with more columns than a line of the DDRAM holds:
+///
+/// ```compile_fail
+/// use lcd_lcm1602_i2c::sync_lcd::Lcd;
+/// # use embedded_hal::i2c::{ErrorType, I2c, Operation};
+/// # struct Bus;
+/// # impl ErrorType for Bus {
+/// #     type Error = core::convert::Infallible;
+/// # }
+/// # impl I2c for Bus {
+/// #     fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// # struct Delay;
+/// # impl embedded_hal::delay::DelayNs for Delay {
+/// #     fn delay_ns(&mut self, _ns: u32) {}
+/// # }
+///
+/// let lcd = Lcd::<2, 41, _, _>::new(Bus, Delay);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fixed<const ROWS: u8, const COLUMNS: u8>(());
+
+impl<const ROWS: u8, const COLUMNS: u8> Fixed<ROWS, COLUMNS> {
+    pub(crate) const fn new() -> Self {
+        const {
+            assert!(ROWS > 0, "ROWS needs to be larger than zero!");
+            assert!(COLUMNS > 0, "COLUMNS needs to be larger than zero!");
+            assert!(
+                ROWS < 5,
+                "This library only supports LCDs with up to four rows!"
+            ); // Because we don't have offsets for more than four rows
+            assert!(
+                COLUMNS <= max_columns(ROWS),
+                "COLUMNS doesn't fit into the DDRAM of the display!"
+            );
+        };
+        Self(())
+    }
+}
+
+impl<const ROWS: u8, const COLUMNS: u8> Geometry for Fixed<ROWS, COLUMNS> {
+    fn rows(&self) -> u8 {
+        ROWS
+    }
+
+    fn columns(&self) -> u8 {
+        COLUMNS
+    }
+}
+
+impl<const ROWS: u8, const COLUMNS: u8> SingleController for Fixed<ROWS, COLUMNS> {}
+
+/// Size only known at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Dynamic {
+    rows: u8,
+    columns: u8,
+}
+
+impl Dynamic {
+    /// Returns `None` if the size isn't supported, i.e. there are no rows or columns, more than
+    /// four rows or more columns than fit into the DDRAM.
+    pub const fn new(rows: u8, columns: u8) -> Option<Self> {
+        if rows == 0 || rows > 4 || columns == 0 || columns > max_columns(rows) {
+            return None;
+        }
+        Some(Self { rows, columns })
+    }
+}
+
+impl Geometry for Dynamic {
+    fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    fn columns(&self) -> u8 {
+        self.columns
+    }
+}
+
+impl SingleController for Dynamic {}
+
+/// Four rows of up to 40 columns driven by two controllers, like on 40x4 displays.
+///
+/// Such a display is two displays with two lines each sharing RS, RW and the data lines. Rows 0
+/// and 1 are on the first controller, rows 2 and 3 on the second one, each has its own enable
+/// pin. The driver sends instructions to both controllers, characters to the one holding the
+/// cursor and only lets that one show the cursor.
+///
+/// The enable pin of the second controller is wired to the RW pin of the [`PinMapping`], RW of
+/// the display is tied to ground, so the controllers aren't read from.
+///
+/// [`PinMapping`]: crate::config::PinMapping
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Dual<const COLUMNS: u8>(());
+
+impl<const COLUMNS: u8> Dual<COLUMNS> {
+    pub(crate) const fn new() -> Self {
+        const {
+            assert!(COLUMNS > 0, "COLUMNS needs to be larger than zero!");
+            assert!(
+                COLUMNS <= max_columns(2),
+                "COLUMNS doesn't fit into the DDRAM of the display!"
+            );
+        };
+        Self(())
+    }
+}
+
+impl<const COLUMNS: u8> Geometry for Dual<COLUMNS> {
+    fn rows(&self) -> u8 {
+        4
+    }
+
+    fn columns(&self) -> u8 {
+        COLUMNS
+    }
+
+    fn controllers(&self) -> u8 {
+        2
+    }
+}
+
+/// Number of columns fitting into the 80 bytes of DDRAM, split into two lines of 40 bytes on
+/// displays with more than one row and into four rows of 20 bytes on displays with more than two.
+const fn max_columns(rows: u8) -> u8 {
+    match rows {
+        1 => 80,
+        2 => 40,
+        _ => 20,
+    }
+}
@@ -0,0 +1,129 @@
+//! Sharing the eight CGRAM slots between users of custom characters.
+
+/// A custom character installed in a CGRAM slot, see `install_glyph`.
+///
+/// The slot stays reserved until the handle is given back with `uninstall_glyph`.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GlyphHandle {
+    slot: u8,
+}
+
+impl GlyphHandle {
+    /// CGRAM slot of the glyph, usable with `write_custom_char`.
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Slot {
+    bitmap: [u8; 8],
+    users: u8,
+}
+
+/// Keeps track of the glyphs installed in the CGRAM slots.
+#[derive(Default)]
+pub(crate) struct GlyphAllocator {
+    slots: [Option<Slot>; 8],
+}
+
+impl GlyphAllocator {
+    /// Reserve a slot for `bitmap`, reusing a slot with the same bitmap.
+    ///
+    /// Returns the handle and whether the bitmap still needs to be written to the slot, or
+    /// `None` if all slots are taken. A slot installed `u8::MAX` times stays reserved for good,
+    /// the users aren't counted any further then.
+    pub(crate) fn allocate(&mut self, bitmap: &[u8; 8]) -> Option<(GlyphHandle, bool)> {
+        let bitmap = bitmap.map(|row| row & 0x1f);
+        if let Some(slot) = self
+            .slots
+            .iter()
+            .position(|s| matches!(s, Some(s) if s.bitmap == bitmap))
+        {
+            if let Some(s) = &mut self.slots[slot] {
+                s.users = s.users.saturating_add(1);
+            }
+            return Some((GlyphHandle { slot: slot as u8 }, false));
+        }
+        let slot = self.slots.iter().position(Option::is_none)?;
+        self.slots[slot] = Some(Slot { bitmap, users: 1 });
+        Some((GlyphHandle { slot: slot as u8 }, true))
+    }
+
+    /// Give back a slot, freeing it once all users gave it back.
+    pub(crate) fn free(&mut self, handle: GlyphHandle) {
+        let slot = &mut self.slots[handle.slot as usize];
+        if let Some(s) = slot.as_mut().filter(|s| s.users < u8::MAX) {
+            s.users -= 1;
+            if s.users == 0 {
+                *slot = None;
+            }
+        }
+    }
+}
+
+/// Build a custom character from eight rows of five pixels, `'#'` or `'1'` for a pixel that is
+/// on, `'.'` or `'0'` for a pixel that is off.
+///
+/// Evaluated in a const context, rows of the wrong length or invalid pixels fail the build. The
+/// [`glyph!`](crate::glyph!) macro does this for you.
+///
+/// ```
+/// use lcd_lcm1602_i2c::glyph::glyph_from_rows;
+///
+/// const DEGREE: [u8; 8] = glyph_from_rows([
+///     ".##..", "#..#.", "#..#.", ".##..", ".....", ".....", ".....", ".....",
+/// ]);
+/// assert_eq!(DEGREE, [0x0c, 0x12, 0x12, 0x0c, 0, 0, 0, 0]);
+/// ```
+pub const fn glyph_from_rows(rows: [&str; 8]) -> [u8; 8] {
+    let mut glyph = [0; 8];
+    let mut r = 0;
+    while r < 8 {
+        let row = rows[r].as_bytes();
+        assert!(row.len() == 5, "Glyph rows need to have five pixels!");
+        let mut c = 0;
+        while c < 5 {
+            glyph[r] <<= 1;
+            match row[c] {
+                b'#' | b'1' => glyph[r] |= 1,
+                b'.' | b'0' => {}
+                _ => panic!("Glyph pixels need to be '#', '1', '.' or '0'!"),
+            }
+            c += 1;
+        }
+        r += 1;
+    }
+    glyph
+}
+
+/// Build a custom character at compile time, see [`glyph_from_rows`](crate::glyph::glyph_from_rows).
+///
+/// ```
+/// let heart = lcd_lcm1602_i2c::glyph!(
+///     ".....",
+///     ".#.#.",
+///     "#####",
+///     "#####",
+///     ".###.",
+///     "..#..",
+///     ".....",
+///     ".....",
+/// );
+/// assert_eq!(heart, [0x00, 0x0a, 0x1f, 0x1f, 0x0e, 0x04, 0x00, 0x00]);
+/// ```
+///
+/// A row with six pixels doesn't build:
+///
+/// ```compile_fail
+/// let wide = lcd_lcm1602_i2c::glyph!(
+///     "......", ".....", ".....", ".....", ".....", ".....", ".....", ".....",
+/// );
+/// ```
+#[macro_export]
+macro_rules! glyph {
+    ($($row:literal),* $(,)?) => {
+        const { $crate::glyph::glyph_from_rows([$($row),*]) }
+    };
+}
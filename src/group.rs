@@ -0,0 +1,225 @@
+//! Showing the same content on several displays.
+
+use core::fmt;
+
+use embedded_hal::delay::DelayNs;
+use ufmt_write::uWrite;
+
+use crate::{geometry::Geometry, interface::LcdInterface, sync_lcd::GenericLcd, Backlight, Error};
+
+/// Several displays that get the same operations, e.g. a front and a rear display showing the
+/// same content.
+///
+/// The displays are usually on the same bus with different addresses, each with its own shared
+/// bus device. Every operation is run on each display in order, a failing display doesn't stop the
+/// others. Use [`get_mut`](Self::get_mut) for content only shown on one display.
+///
+/// Each display owns its delay, a copy of a `Copy` one like `embassy_time::Delay` does:
+///
+/// ```
+/// use core::cell::RefCell;
+/// use embedded_hal_bus::i2c::RefCellDevice;
+/// use lcd_lcm1602_i2c::{group::LcdGroup, sync_lcd::Lcd};
+/// use ufmt::uwrite;
+/// # use embedded_hal::i2c::{ErrorType, I2c, Operation};
+/// # #[derive(Clone, Copy)]
+/// # struct Delay;
+/// # impl embedded_hal::delay::DelayNs for Delay {
+/// #     fn delay_ns(&mut self, _ns: u32) {}
+/// # }
+/// # /// Bus remembering the address of every write.
+/// # #[derive(Default)]
+/// # struct Bus(Vec<u8>);
+/// # impl ErrorType for Bus {
+/// #     type Error = core::convert::Infallible;
+/// # }
+/// # impl I2c for Bus {
+/// #     fn transaction(&mut self, address: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+/// #         self.0.push(address);
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// let bus = RefCell::new(Bus::default());
+/// let delay = Delay;
+/// let front = Lcd::<2, 16, _, _>::new(RefCellDevice::new(&bus), delay)
+///     .with_address(0x26)
+///     .init()
+///     .unwrap();
+/// let rear = Lcd::<2, 16, _, _>::new(RefCellDevice::new(&bus), delay)
+///     .with_address(0x27)
+///     .init()
+///     .unwrap();
+/// let mut group = LcdGroup::new([front, rear]);
+/// # bus.borrow_mut().0.clear();
+/// let rpm = 1200;
+/// uwrite!(group, "{} rpm", rpm).unwrap();
+/// # drop(group);
+/// # let addresses = bus.into_inner().0;
+/// # assert!(addresses.contains(&0x26) && addresses.contains(&0x27));
+/// ```
+pub struct LcdGroup<G, I, D, const N: usize>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    displays: [GenericLcd<G, I, D>; N],
+}
+
+impl<G, I, D, const N: usize> LcdGroup<G, I, D, N>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+{
+    /// Group initialized displays, they are indexed in the order given.
+    pub fn new(displays: [GenericLcd<G, I, D>; N]) -> Self {
+        Self { displays }
+    }
+
+    /// Return the displays.
+    pub fn into_inner(self) -> [GenericLcd<G, I, D>; N] {
+        self.displays
+    }
+
+    /// The display at `index`, for content only shown on that display.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut GenericLcd<G, I, D>> {
+        self.displays.get_mut(index)
+    }
+
+    /// Run `f` on each display in order, collecting the errors of the displays it failed on.
+    pub fn for_each<F>(&mut self, mut f: F) -> Result<(), GroupError<I::Error, N>>
+    where
+        F: FnMut(&mut GenericLcd<G, I, D>) -> Result<(), Error<I::Error>>,
+    {
+        let mut failed = false;
+        let errors = core::array::from_fn(|i| {
+            let result = f(&mut self.displays[i]).err();
+            failed |= result.is_some();
+            result
+        });
+        if failed {
+            return Err(GroupError { errors });
+        }
+        Ok(())
+    }
+
+    /// Run the init sequence of each display again, see [`GenericLcd::re_init`].
+    pub fn re_init(&mut self) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.re_init())
+    }
+
+    /// See [`GenericLcd::write_str`].
+    pub fn write_str(&mut self, data: &str) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.write_str(data))
+    }
+
+    /// See [`GenericLcd::print_at`].
+    pub fn print_at(&mut self, row: u8, col: u8, s: &str) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.print_at(row, col, s).map(|_| ()))
+    }
+
+    /// See [`GenericLcd::set_cursor`].
+    pub fn set_cursor(&mut self, row: u8, col: u8) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.set_cursor(row, col))
+    }
+
+    /// See [`GenericLcd::clear`].
+    pub fn clear(&mut self) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.clear())
+    }
+
+    /// See [`GenericLcd::create_char`].
+    pub fn create_char(
+        &mut self,
+        location: u8,
+        charmap: &[u8; 8],
+    ) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.create_char(location, charmap))
+    }
+
+    /// See [`GenericLcd::write_custom_char`].
+    pub fn write_custom_char(&mut self, index: u8) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.write_custom_char(index))
+    }
+
+    /// See [`GenericLcd::backlight`].
+    pub fn backlight(&mut self, backlight: Backlight) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.backlight(backlight))
+    }
+
+    /// See [`GenericLcd::cursor_on`].
+    pub fn cursor_on(&mut self, on: bool) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.cursor_on(on))
+    }
+
+    /// See [`GenericLcd::cursor_blink`].
+    pub fn cursor_blink(&mut self, blink: bool) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.cursor_blink(blink))
+    }
+
+    /// See [`GenericLcd::display_on`].
+    pub fn display_on(&mut self) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.display_on())
+    }
+
+    /// See [`GenericLcd::display_off`].
+    pub fn display_off(&mut self) -> Result<(), GroupError<I::Error, N>> {
+        self.for_each(|lcd| lcd.display_off())
+    }
+}
+
+impl<G, I, D, const N: usize> uWrite for LcdGroup<G, I, D, N>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+{
+    type Error = GroupError<I::Error, N>;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write_str(s)
+    }
+}
+
+/// Errors of an operation on a [`LcdGroup`], one for each display it failed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GroupError<E, const N: usize> {
+    errors: [Option<Error<E>>; N],
+}
+
+impl<E, const N: usize> GroupError<E, N> {
+    /// Error of the display at `index`, `None` if the operation succeeded on it.
+    pub fn get(&self, index: usize) -> Option<&Error<E>> {
+        self.errors.get(index)?.as_ref()
+    }
+
+    /// Index and error of every display the operation failed on.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Error<E>)> {
+        self.errors
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| Some((i, e.as_ref()?)))
+    }
+
+    /// Errors indexed like the displays of the group.
+    pub fn into_errors(self) -> [Option<Error<E>>; N] {
+        self.errors
+    }
+}
+
+impl<E: fmt::Debug, const N: usize> fmt::Display for GroupError<E, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, (i, e)) in self.iter().enumerate() {
+            if n > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "display {}: {}", i, e)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug, const N: usize> std::error::Error for GroupError<E, N> {}
@@ -0,0 +1,158 @@
+//! Controllers with native I2C, like the AIP31068 of the Grove LCD modules.
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{I2c, Operation},
+};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::LcdInterface;
+use crate::BitMode;
+
+/// I2C address of the AIP31068, it can't be changed.
+const ADDRESS: u8 = 0x3e;
+
+/// Control byte of a single instruction.
+const COMMAND: u8 = 0x80;
+/// Control byte of data for the RAM, every following byte is written to it.
+const DATA: u8 = 0x40;
+
+/// AIP31068 or compatible controller speaking I2C itself, without an expander.
+///
+/// Every byte is sent whole after a control byte selecting the instruction or data register,
+/// the controller runs in 8 bit mode. There is no backlight on the bus, it's separate hardware
+/// on these modules, so switching it does nothing. The controller isn't read from.
+///
+/// [`write_bytes`](crate::sync_lcd::GenericLcd::write_bytes) sends all bytes in one transfer.
+/// Each byte takes 90 µs on a 100 kHz bus, longer than the controller takes to write it to its
+/// RAM, so the bus shouldn't be run faster than that.
+///
+/// ```ignore
+/// let interface = Aip31068Interface::new(i2c);
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct Aip31068Interface<I> {
+    i2c: I,
+}
+
+impl<I> Aip31068Interface<I> {
+    /// Interface to the controller at its fixed address 0x3e.
+    pub fn new(i2c: I) -> Self {
+        Self { i2c }
+    }
+
+    /// I2C address of the controller.
+    pub fn address(&self) -> u8 {
+        ADDRESS
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+}
+
+/// Control byte selecting the register for `rs`.
+fn control(rs: bool) -> u8 {
+    match rs {
+        true => DATA,
+        false => COMMAND,
+    }
+}
+
+impl<I: I2c> LcdInterface for Aip31068Interface<I> {
+    type Error = I::Error;
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    /// Send `nibble` as the upper four bits of a byte, what an 8 bit bus with D0 to D3 low would
+    /// clock in.
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay)
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.i2c.write(ADDRESS, &[control(rs), byte])
+    }
+
+    fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.i2c.transaction(
+            ADDRESS,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )?;
+        Ok(true)
+    }
+
+    fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for Aip31068Interface<I> {
+    type Error = I::Error;
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay).await
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, ADDRESS, &[control(rs), byte]).await
+    }
+
+    async fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        embedded_hal_async::i2c::I2c::transaction(
+            &mut self.i2c,
+            ADDRESS,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! The 74HC595 shift register on a SPI bus.
+
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::{backlight_latch, nibble_latches, LcdInterface};
+use crate::config::PinMapping;
+
+/// 74HC595 shift register on a SPI bus, with RS, EN, the backlight and D4 to D7 on its outputs.
+///
+/// Every state of the outputs is shifted out as one byte in its own SPI transfer. The storage
+/// register clock (RCLK) is expected on the chip select of the [`SpiDevice`], so the outputs
+/// change when the transfer ends. RW is expected to be tied to ground, the controller isn't read
+/// from.
+///
+/// ```ignore
+/// let interface = Hc595Interface::new(spi).with_pin_mapping(pins).unwrap();
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct Hc595Interface<S> {
+    spi: S,
+    pins: PinMapping,
+}
+
+impl<S> Hc595Interface<S> {
+    /// Interface to the shift register on `spi`, with QA to QH wired like
+    /// [`PinMapping::default()`].
+    pub fn new(spi: S) -> Self {
+        Self {
+            spi,
+            pins: PinMapping::default(),
+        }
+    }
+
+    /// Set how the display is wired to the outputs, pin 0 is QA and pin 7 is QH. Returns `None`
+    /// if a pin doesn't exist or is used twice.
+    pub fn with_pin_mapping(mut self, pins: PinMapping) -> Option<Self> {
+        if !pins.is_valid() {
+            return None;
+        }
+        self.pins = pins;
+        Some(self)
+    }
+
+    /// Return the SPI device.
+    pub fn into_inner(self) -> S {
+        self.spi
+    }
+}
+
+impl<S: SpiDevice> LcdInterface for Hc595Interface<S> {
+    type Error = S::Error;
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        // Each transfer takes longer than the EN pulse has to be.
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            self.spi.write(&[latch])?;
+        }
+        Ok(())
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        self.spi.write(&[backlight_latch(&self.pins, backlight)])
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: embedded_hal_async::spi::SpiDevice> AsyncLcdInterface for Hc595Interface<S> {
+    type Error = S::Error;
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            embedded_hal_async::spi::SpiDevice::write(&mut self.spi, &[latch]).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        let latch = backlight_latch(&self.pins, backlight);
+        embedded_hal_async::spi::SpiDevice::write(&mut self.spi, &[latch]).await
+    }
+}
@@ -0,0 +1,389 @@
+//! The MCP23008 and MCP23017 I2C expanders with registers.
+
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::{backlight_latch, nibble_latches, LcdInterface};
+use crate::{config::PinMapping, BitMode};
+
+/// Register of the MCP23008 with the direction of each pin, set bits are inputs.
+const IODIR: u8 = 0x00;
+/// Register of the MCP23008 with the output latch of each pin.
+const OLAT: u8 = 0x0a;
+
+// Registers of the MCP23017 like `IODIR` and `OLAT` of port A, the ones of port B follow them.
+// These are the addresses with `IOCON.BANK` cleared, as after power on.
+const IODIRA: u8 = 0x00;
+const OLATA: u8 = 0x14;
+
+/// MCP23008 I2C expander, with RS, EN, the backlight and D4 to D7 on its pins.
+///
+/// Unlike the PCF8574, the MCP23008 has registers: [`init`](LcdInterface::init) makes every pin
+/// an output, the pins are then set through the output latch. RW is expected to be tied to
+/// ground, the controller isn't read from.
+///
+/// ```ignore
+/// let interface = Mcp23008Interface::new(i2c, 0x20);
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct Mcp23008Interface<I> {
+    i2c: I,
+    address: u8,
+    pins: PinMapping,
+}
+
+impl<I> Mcp23008Interface<I> {
+    /// Interface to the expander at `address`, 0x20 to 0x27 depending on the address pins, with
+    /// the wiring of [`PinMapping::mcp23008()`].
+    pub fn new(i2c: I, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            pins: PinMapping::mcp23008(),
+        }
+    }
+
+    /// Set how the display is wired to the expander, returns `None` if a pin doesn't exist or is
+    /// used twice.
+    pub fn with_pin_mapping(mut self, pins: PinMapping) -> Option<Self> {
+        if !pins.is_valid() {
+            return None;
+        }
+        self.pins = pins;
+        Some(self)
+    }
+
+    /// I2C address of the expander.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+}
+
+impl<I: I2c> LcdInterface for Mcp23008Interface<I> {
+    type Error = I::Error;
+
+    fn init(&mut self, _delay: &mut impl DelayNs) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[IODIR, 0x00])
+    }
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        // The I2C transfers are slower than the EN pulse has to be.
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            self.i2c.write(self.address, &[OLAT, latch])?;
+        }
+        Ok(())
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        let latch = backlight_latch(&self.pins, backlight);
+        self.i2c.write(self.address, &[OLAT, latch])
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for Mcp23008Interface<I> {
+    type Error = I::Error;
+
+    async fn init(
+        &mut self,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[IODIR, 0x00]).await
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[OLAT, latch])
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        let latch = backlight_latch(&self.pins, backlight);
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[OLAT, latch]).await
+    }
+}
+
+/// Port of the MCP23017 the display is connected to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Port {
+    /// GPA0 to GPA7.
+    A,
+    /// GPB0 to GPB7.
+    B,
+}
+
+/// MCP23017 I2C expander with the display on one of its two ports, the other one is left to the
+/// application, e.g. for buttons.
+///
+/// [`init`](LcdInterface::init) makes every pin of the port of the display an output, the pins are
+/// then set through its output latch. Only the registers of that port are written, so the
+/// direction and outputs of the other port are never changed. RW is expected to be tied to
+/// ground, the controller isn't read from.
+///
+/// With [`with_8_bit_bus`](Self::with_8_bit_bus), D0 to D7 are on the other port and the
+/// controller runs in 8 bit mode, every byte then takes one EN pulse instead of two.
+///
+/// ```ignore
+/// let interface = Mcp23017Interface::new(i2c, 0x20, Port::B);
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct Mcp23017Interface<I> {
+    i2c: I,
+    address: u8,
+    port: Port,
+    pins: PinMapping,
+    data_port: Option<Port>,
+}
+
+impl<I> Mcp23017Interface<I> {
+    /// Interface to the expander at `address`, 0x20 to 0x27 depending on the address pins, with
+    /// the display on `port` wired like [`PinMapping::default()`], pin 0 to 7 of the port.
+    pub fn new(i2c: I, address: u8, port: Port) -> Self {
+        Self {
+            i2c,
+            address,
+            port,
+            pins: PinMapping::default(),
+            data_port: None,
+        }
+    }
+
+    /// Drive D0 to D7 from pin 0 to 7 of the other port and run the controller in 8 bit mode.
+    ///
+    /// RS, EN and the backlight stay on the port of the display as set by the pin mapping, its
+    /// data pins are left low. The other port isn't left to the application then.
+    pub fn with_8_bit_bus(mut self) -> Self {
+        self.data_port = Some(match self.port {
+            Port::A => Port::B,
+            Port::B => Port::A,
+        });
+        self
+    }
+
+    /// Set how the display is wired to the pins of its port, returns `None` if a pin doesn't
+    /// exist or is used twice.
+    pub fn with_pin_mapping(mut self, pins: PinMapping) -> Option<Self> {
+        if !pins.is_valid() {
+            return None;
+        }
+        self.pins = pins;
+        Some(self)
+    }
+
+    /// I2C address of the expander.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Port the display is connected to.
+    pub fn port(&self) -> Port {
+        self.port
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+
+    /// Register of port A `register` for the port of the display.
+    fn register(&self, register: u8) -> u8 {
+        port_register(self.port, register)
+    }
+
+    /// Width of the data bus.
+    fn bus_width(&self) -> BitMode {
+        match self.data_port {
+            Some(_) => BitMode::Bit8,
+            None => BitMode::Bit4,
+        }
+    }
+}
+
+/// Register of port A `register` for `port`.
+fn port_register(port: Port, register: u8) -> u8 {
+    match port {
+        Port::A => register,
+        Port::B => register + 1,
+    }
+}
+
+impl<I: I2c> Mcp23017Interface<I> {
+    /// Clock the lower four bits of `nibble` into D4 to D7 of the port of the display.
+    fn write_latches(&mut self, rs: bool, nibble: u8, backlight: bool) -> Result<(), I::Error> {
+        // The I2C transfers are slower than the EN pulse has to be.
+        let olat = self.register(OLATA);
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            self.i2c.write(self.address, &[olat, latch])?;
+        }
+        Ok(())
+    }
+
+    /// Set D0 to D7 on `port` to `byte`, then pulse EN with the data lines of the port of the
+    /// display left low.
+    fn write_bus(&mut self, port: Port, rs: bool, byte: u8, bl: bool) -> Result<(), I::Error> {
+        self.i2c
+            .write(self.address, &[port_register(port, OLATA), byte])?;
+        self.write_latches(rs, 0, bl)
+    }
+}
+
+impl<I: I2c> LcdInterface for Mcp23017Interface<I> {
+    type Error = I::Error;
+
+    fn init(&mut self, _delay: &mut impl DelayNs) -> Result<(), Self::Error> {
+        if let Some(port) = self.data_port {
+            self.i2c
+                .write(self.address, &[port_register(port, IODIRA), 0x00])?;
+        }
+        self.i2c.write(self.address, &[self.register(IODIRA), 0x00])
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        self.bus_width()
+    }
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        match self.data_port {
+            Some(port) => self.write_bus(port, rs, nibble << 4, backlight),
+            None => self.write_latches(rs, nibble, backlight),
+        }
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        match self.data_port {
+            Some(port) => self.write_bus(port, rs, byte, backlight),
+            None => {
+                self.write_latches(rs, byte >> 4, backlight)?;
+                self.write_latches(rs, byte & 0x0f, backlight)
+            }
+        }
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        let latch = backlight_latch(&self.pins, backlight);
+        self.i2c.write(self.address, &[self.register(OLATA), latch])
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> Mcp23017Interface<I> {
+    /// See [`write_latches`](Self::write_latches).
+    async fn write_latches_async(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+    ) -> Result<(), I::Error> {
+        let olat = self.register(OLATA);
+        for latch in nibble_latches(&self.pins, rs, nibble, backlight) {
+            embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[olat, latch])
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// See [`write_bus`](Self::write_bus).
+    async fn write_bus_async(
+        &mut self,
+        port: Port,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+    ) -> Result<(), I::Error> {
+        let data = [port_register(port, OLATA), byte];
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &data).await?;
+        self.write_latches_async(rs, 0, backlight).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for Mcp23017Interface<I> {
+    type Error = I::Error;
+
+    async fn init(
+        &mut self,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        if let Some(port) = self.data_port {
+            let iodir = port_register(port, IODIRA);
+            embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[iodir, 0x00])
+                .await?;
+        }
+        let iodir = self.register(IODIRA);
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[iodir, 0x00]).await
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        self.bus_width()
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        match self.data_port {
+            Some(port) => self.write_bus_async(port, rs, nibble << 4, backlight).await,
+            None => self.write_latches_async(rs, nibble, backlight).await,
+        }
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        match self.data_port {
+            Some(port) => self.write_bus_async(port, rs, byte, backlight).await,
+            None => {
+                self.write_latches_async(rs, byte >> 4, backlight).await?;
+                self.write_latches_async(rs, byte & 0x0f, backlight).await
+            }
+        }
+    }
+
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        let latch = backlight_latch(&self.pins, backlight);
+        let olat = self.register(OLATA);
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[olat, latch]).await
+    }
+}
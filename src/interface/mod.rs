@@ -0,0 +1,239 @@
+//! Wiring between the driver and the controller.
+//!
+//! The driver talks to the HD44780 through a [`LcdInterface`], which only has to clock nibbles
+//! into D4 to D7 and switch the backlight. Interfaces with all eight data lines, like the
+//! [`Aip31068Interface`] of controllers with native I2C, send whole bytes instead. [`Pcf8574`] is the interface of the
+//! usual I2C backpacks, used by `Lcd::new`. Other wiring is used with `init_with_interface`, like
+//! [`ParallelInterface`] for a display connected to GPIO pins of the microcontroller or
+//! [`Hc595Interface`] for a shift register on a SPI bus.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{config::PinMapping, Backlight, BitMode};
+
+mod aip31068;
+mod hc595;
+mod mcp230xx;
+mod parallel;
+mod pcf8574;
+mod st7032i;
+mod us2066;
+
+pub use aip31068::Aip31068Interface;
+pub use hc595::Hc595Interface;
+pub use mcp230xx::{Mcp23008Interface, Mcp23017Interface, Port};
+pub use parallel::{DataBus, NoPin, ParallelInterface, ReadableBus};
+pub use pcf8574::Pcf8574;
+pub use st7032i::St7032iInterface;
+pub use us2066::Us2066Interface;
+
+// Bits of the control lines of expanders in the wiring of `PinMapping::default()`, moved to the
+// actual pins with `PinMapping::map_out`.
+
+/// Expander pin selecting the data register instead of the instruction register.
+const RS: u8 = 0x01;
+/// Expander pin selecting if the controller is read from or written to.
+const RW: u8 = 0x02;
+/// Expander pin clocking the data lines into the controller.
+const EN: u8 = 0x04;
+
+/// Output latch of the backlight alone, with EN and the data lines low.
+fn backlight_latch(pins: &PinMapping, backlight: bool) -> u8 {
+    match backlight {
+        true => pins.map_out(Backlight::On as u8),
+        false => 0,
+    }
+}
+
+/// Output latches clocking the lower four bits of `nibble` into D4 to D7 with RS set for `rs`:
+/// set up the lines, raise EN, lower EN.
+fn nibble_latches(pins: &PinMapping, rs: bool, nibble: u8, backlight: bool) -> [u8; 3] {
+    let byte = (nibble << 4) | if rs { RS } else { 0 };
+    let latch = pins.map_out(byte) | backlight_latch(pins, backlight);
+    [latch, latch | pins.map_out(EN), latch]
+}
+
+/// Wiring of the control and data lines of a HD44780.
+///
+/// The driver sends bytes with [`write_byte`](Self::write_byte), nibbles on their own only while
+/// switching the controller to 4 bit mode. `rs` is `true` to select the data register, `false`
+/// for the instruction register.
+/// `backlight` is whether the backlight is on, for wiring where the backlight shares a port with
+/// the data lines. `delay` is the one of the driver, for the timing of the EN pulse.
+pub trait LcdInterface {
+    /// Error of the underlying bus, returned as [`Error::I2c`](crate::Error::I2c).
+    type Error;
+
+    /// Prepare the wiring for the init sequence, e.g. set the direction of expander pins.
+    ///
+    /// Called by `init()` and `re_init()` before anything else is sent to the controller.
+    fn init(&mut self, delay: &mut impl DelayNs) -> Result<(), Self::Error> {
+        let _ = delay;
+        Ok(())
+    }
+
+    /// Width of the data bus, the controller is put into this mode by the init sequence.
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit4
+    }
+
+    /// Set D4 to D7 to the lower four bits of `nibble` with RW low, then pulse EN so the
+    /// controller latches them.
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error>;
+
+    /// Clock `byte` into the controller, as two nibbles with the high one first by default.
+    ///
+    /// The controller doesn't need any time between the nibbles, only the EN pulses have to be
+    /// long enough.
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_nibble(rs, byte >> 4, backlight, delay)?;
+        self.write_nibble(rs, byte & 0x0f, backlight, delay)
+    }
+
+    /// Write `data` to the RAM of the controller in one go, if the wiring can, and return
+    /// `false` if it can't.
+    ///
+    /// The interface has to give the controller the time to execute every byte but the last,
+    /// the driver waits for the last one.
+    fn write_data(
+        &mut self,
+        data: &[u8],
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        let _ = (data, backlight, delay);
+        Ok(false)
+    }
+
+    /// Write `commands` to the instruction register in one go, if the wiring can, and return
+    /// `false` if it can't.
+    ///
+    /// Only used for instructions that execute as fast as a character, like shifting the
+    /// display. As with [`write_data`](Self::write_data), the driver waits for the last one.
+    fn write_commands(
+        &mut self,
+        commands: &[u8],
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        let _ = (commands, backlight, delay);
+        Ok(false)
+    }
+
+    /// Switch the backlight without clocking anything into the controller.
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error>;
+
+    /// Clock everything from now on into the `controllers` of a display with two, bit 0 selects
+    /// the first one and bit 1 the second one. Wirings with a single enable pin ignore it.
+    fn select_controllers(&mut self, controllers: u8) {
+        let _ = controllers;
+    }
+
+    /// Read four bits from D4 to D7 with RW high while EN is raised, or `None` if the wiring
+    /// can't read from the controller, e.g. because RW is tied to ground.
+    fn read_nibble(
+        &mut self,
+        rs: bool,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        let _ = (rs, backlight, delay);
+        Ok(None)
+    }
+}
+
+/// Async version of [`LcdInterface`], used by the driver in `async_lcd`.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncLcdInterface {
+    /// See [`LcdInterface::Error`].
+    type Error;
+
+    /// See [`LcdInterface::init`].
+    async fn init(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        let _ = delay;
+        Ok(())
+    }
+
+    /// See [`LcdInterface::bit_mode`].
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit4
+    }
+
+    /// See [`LcdInterface::write_nibble`].
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error>;
+
+    /// See [`LcdInterface::write_byte`].
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_nibble(rs, byte >> 4, backlight, delay).await?;
+        self.write_nibble(rs, byte & 0x0f, backlight, delay).await
+    }
+
+    /// See [`LcdInterface::write_data`].
+    async fn write_data(
+        &mut self,
+        data: &[u8],
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        let _ = (data, backlight, delay);
+        Ok(false)
+    }
+
+    /// See [`LcdInterface::write_commands`].
+    async fn write_commands(
+        &mut self,
+        commands: &[u8],
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        let _ = (commands, backlight, delay);
+        Ok(false)
+    }
+
+    /// See [`LcdInterface::set_backlight`].
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error>;
+
+    /// See [`LcdInterface::select_controllers`].
+    fn select_controllers(&mut self, controllers: u8) {
+        let _ = controllers;
+    }
+
+    /// See [`LcdInterface::read_nibble`].
+    async fn read_nibble(
+        &mut self,
+        rs: bool,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        let _ = (rs, backlight, delay);
+        Ok(None)
+    }
+}
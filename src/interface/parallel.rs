@@ -0,0 +1,362 @@
+//! HD44780 wired straight to GPIO pins of the microcontroller.
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{Error as _, ErrorKind, ErrorType, InputPin, OutputPin},
+};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::LcdInterface;
+use crate::BitMode;
+
+/// Time EN is held high and then low in ns. The HD44780 needs a pulse of at least 450 ns in a
+/// cycle of at least 1 µs and has the data ready for reading after 360 ns.
+const EN_PULSE_NS: u32 = 1_000;
+
+/// Placeholder for an optional pin that isn't connected, e.g. a backlight that is always on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoPin;
+
+impl ErrorType for NoPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Data lines of a [`ParallelInterface`].
+///
+/// Four [`OutputPin`]s in the order D4 to D7 only write to the controller, a [`ReadableBus`] can
+/// read from it too. Eight [`OutputPin`]s in the order D0 to D7 write whole bytes in 8 bit mode.
+pub trait DataBus {
+    /// Drive D4 to D7 with the lower four bits of `bits`, or D0 to D7 with all of them on a bus
+    /// with eight data lines, with RW low.
+    fn write(&mut self, bits: u8) -> Result<(), ErrorKind>;
+
+    /// Width of the bus, the controller is set up with it.
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit4
+    }
+
+    /// Release D4 to D7 and set RW high so the controller can drive them, returns `false` if the
+    /// bus can only write.
+    fn release(&mut self) -> Result<bool, ErrorKind> {
+        Ok(false)
+    }
+
+    /// Read D4 to D7 after [`release`](Self::release) into the lower four bits.
+    fn sample(&mut self) -> Result<u8, ErrorKind> {
+        Ok(0)
+    }
+}
+
+impl<D: OutputPin> DataBus for [D; 4] {
+    fn write(&mut self, bits: u8) -> Result<(), ErrorKind> {
+        for (bit, pin) in self.iter_mut().enumerate() {
+            set(pin, bits & (1 << bit) != 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<D: OutputPin> DataBus for [D; 8] {
+    fn write(&mut self, bits: u8) -> Result<(), ErrorKind> {
+        for (bit, pin) in self.iter_mut().enumerate() {
+            set(pin, bits & (1 << bit) != 0)?;
+        }
+        Ok(())
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+}
+
+/// Data lines D4 to D7 that are read from too, with the RW pin.
+///
+/// The data pins have to be open drain outputs with pull-ups that can be read back, like the
+/// quasi-bidirectional pins of the PCF8574. They are set high to release them to the controller.
+#[derive(Debug)]
+pub struct ReadableBus<D, RW> {
+    data: [D; 4],
+    rw: RW,
+}
+
+impl<D, RW> ReadableBus<D, RW> {
+    /// Bus of the data pins in the order D4 to D7 and the RW pin.
+    pub fn new(data: [D; 4], rw: RW) -> Self {
+        Self { data, rw }
+    }
+
+    /// Return the data pins and the RW pin.
+    pub fn into_inner(self) -> ([D; 4], RW) {
+        (self.data, self.rw)
+    }
+}
+
+impl<D, RW> DataBus for ReadableBus<D, RW>
+where
+    D: InputPin + OutputPin,
+    RW: OutputPin,
+{
+    fn write(&mut self, nibble: u8) -> Result<(), ErrorKind> {
+        set(&mut self.rw, false)?;
+        self.data.write(nibble)
+    }
+
+    fn release(&mut self) -> Result<bool, ErrorKind> {
+        // Release the data lines first, so they are never driven from both sides.
+        self.data.write(0x0f)?;
+        set(&mut self.rw, true)?;
+        Ok(true)
+    }
+
+    fn sample(&mut self) -> Result<u8, ErrorKind> {
+        let mut bits = 0;
+        for (bit, pin) in self.data.iter_mut().enumerate() {
+            if pin.is_high().map_err(|e| e.kind())? {
+                bits |= 1 << bit;
+            }
+        }
+        Ok(bits)
+    }
+}
+
+/// HD44780 wired to GPIO pins: RS, EN and D4 to D7, optionally RW and the backlight.
+///
+/// With all eight data lines D0 to D7 connected, the controller runs in 8 bit mode and every byte
+/// takes a single EN pulse instead of two. Errors of the pins are returned as their
+/// [`ErrorKind`].
+///
+/// ```ignore
+/// let interface = ParallelInterface::new(rs, en, [d4, d5, d6, d7]).with_backlight(backlight);
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct ParallelInterface<RS, EN, B, BL = NoPin> {
+    rs: RS,
+    en: EN,
+    bus: B,
+    backlight: BL,
+}
+
+impl<RS, EN, B> ParallelInterface<RS, EN, B> {
+    /// Interface with the RS and EN pins and the data lines `bus`, four [`OutputPin`]s for D4
+    /// to D7, eight for D0 to D7 or a [`ReadableBus`].
+    pub fn new(rs: RS, en: EN, bus: B) -> Self {
+        Self {
+            rs,
+            en,
+            bus,
+            backlight: NoPin,
+        }
+    }
+}
+
+impl<RS, EN, B, BL> ParallelInterface<RS, EN, B, BL> {
+    /// Switch the backlight with `pin`, high is on.
+    pub fn with_backlight<P>(self, pin: P) -> ParallelInterface<RS, EN, B, P> {
+        ParallelInterface {
+            rs: self.rs,
+            en: self.en,
+            bus: self.bus,
+            backlight: pin,
+        }
+    }
+
+    /// Return the RS and EN pins, the data lines and the backlight pin.
+    pub fn into_parts(self) -> (RS, EN, B, BL) {
+        (self.rs, self.en, self.bus, self.backlight)
+    }
+}
+
+impl<RS, EN, B, BL> ParallelInterface<RS, EN, B, BL>
+where
+    RS: OutputPin,
+    EN: OutputPin,
+    B: DataBus,
+    BL: OutputPin,
+{
+    /// Set RS and the data lines, then raise EN.
+    fn start_write(&mut self, rs: bool, bits: u8) -> Result<(), ErrorKind> {
+        set(&mut self.rs, rs)?;
+        self.bus.write(bits)?;
+        set(&mut self.en, true)
+    }
+
+    /// Bits of the bus for `nibble`, the upper four bits of a byte with eight data lines.
+    fn nibble_bits(&self, nibble: u8) -> u8 {
+        match self.bus.bit_mode() {
+            BitMode::Bit4 => nibble,
+            BitMode::Bit8 => nibble << 4,
+        }
+    }
+
+    /// Set RS and release the data lines, then raise EN. Returns `false` if the bus can't read.
+    fn start_read(&mut self, rs: bool) -> Result<bool, ErrorKind> {
+        set(&mut self.rs, rs)?;
+        if !self.bus.release()? {
+            return Ok(false);
+        }
+        set(&mut self.en, true)?;
+        Ok(true)
+    }
+
+    /// Read the data lines, then lower EN.
+    fn finish_read(&mut self) -> Result<u8, ErrorKind> {
+        let bits = self.bus.sample()?;
+        set(&mut self.en, false)?;
+        Ok(bits)
+    }
+}
+
+impl<RS, EN, B, BL> LcdInterface for ParallelInterface<RS, EN, B, BL>
+where
+    RS: OutputPin,
+    EN: OutputPin,
+    B: DataBus,
+    BL: OutputPin,
+{
+    type Error = ErrorKind;
+
+    fn bit_mode(&self) -> BitMode {
+        self.bus.bit_mode()
+    }
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        _backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.start_write(rs, self.nibble_bits(nibble))?;
+        delay.delay_ns(EN_PULSE_NS);
+        set(&mut self.en, false)?;
+        delay.delay_ns(EN_PULSE_NS);
+        Ok(())
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        if self.bus.bit_mode() == BitMode::Bit4 {
+            LcdInterface::write_nibble(self, rs, byte >> 4, backlight, delay)?;
+            return LcdInterface::write_nibble(self, rs, byte & 0x0f, backlight, delay);
+        }
+        self.start_write(rs, byte)?;
+        delay.delay_ns(EN_PULSE_NS);
+        set(&mut self.en, false)?;
+        delay.delay_ns(EN_PULSE_NS);
+        Ok(())
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        set(&mut self.backlight, backlight)
+    }
+
+    fn read_nibble(
+        &mut self,
+        rs: bool,
+        _backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        if !self.start_read(rs)? {
+            return Ok(None);
+        }
+        delay.delay_ns(EN_PULSE_NS);
+        let bits = self.finish_read()?;
+        delay.delay_ns(EN_PULSE_NS);
+        Ok(Some(bits))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<RS, EN, B, BL> AsyncLcdInterface for ParallelInterface<RS, EN, B, BL>
+where
+    RS: OutputPin,
+    EN: OutputPin,
+    B: DataBus,
+    BL: OutputPin,
+{
+    type Error = ErrorKind;
+
+    fn bit_mode(&self) -> BitMode {
+        self.bus.bit_mode()
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        _backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.start_write(rs, self.nibble_bits(nibble))?;
+        delay.delay_ns(EN_PULSE_NS).await;
+        set(&mut self.en, false)?;
+        delay.delay_ns(EN_PULSE_NS).await;
+        Ok(())
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        if self.bus.bit_mode() == BitMode::Bit4 {
+            AsyncLcdInterface::write_nibble(self, rs, byte >> 4, backlight, delay).await?;
+            return AsyncLcdInterface::write_nibble(self, rs, byte & 0x0f, backlight, delay).await;
+        }
+        self.start_write(rs, byte)?;
+        delay.delay_ns(EN_PULSE_NS).await;
+        set(&mut self.en, false)?;
+        delay.delay_ns(EN_PULSE_NS).await;
+        Ok(())
+    }
+
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        set(&mut self.backlight, backlight)
+    }
+
+    async fn read_nibble(
+        &mut self,
+        rs: bool,
+        _backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        if !self.start_read(rs)? {
+            return Ok(None);
+        }
+        delay.delay_ns(EN_PULSE_NS).await;
+        let bits = self.finish_read()?;
+        delay.delay_ns(EN_PULSE_NS).await;
+        Ok(Some(bits))
+    }
+}
+
+/// Set `pin` high or low.
+fn set<P: OutputPin>(pin: &mut P, high: bool) -> Result<(), ErrorKind> {
+    let result = match high {
+        true => pin.set_high(),
+        false => pin.set_low(),
+    };
+    result.map_err(|e| e.kind())
+}
@@ -0,0 +1,442 @@
+//! The PCF8574 I2C expander of the usual backpacks.
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorKind, I2c},
+};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::{LcdInterface, EN, RS, RW};
+use crate::{
+    config::{LcdConfig, PinMapping},
+    Backlight, Polarity,
+};
+
+/// Characters sent in one transaction by [`write_data`](LcdInterface::write_data).
+const STREAM_CHARS: usize = 16;
+/// Expander writes clocking one byte into the controller.
+const BYTE_WRITES: usize = 6;
+/// Expander writes of the longest transaction.
+const STREAM_WRITES: usize = STREAM_CHARS * BYTE_WRITES;
+
+/// PCF8574 I2C expander of the usual backpacks, with RS, RW, EN, the backlight and D4 to D7 on
+/// its pins.
+///
+/// Created by `init()` from the address, [`PinMapping`], backlight [`Polarity`] and I2C retries of
+/// the config. On displays with two controllers, the RW pin is the enable pin of the second one.
+#[derive(Debug)]
+pub struct Pcf8574<I> {
+    i2c: I,
+    address: u8,
+    pins: PinMapping,
+    polarity: Polarity,
+    retries: u8,
+    retry_count: u32,
+    /// Most bytes written in one transaction.
+    transfer_limit: usize,
+    /// EN and RW as the enable pins of the selected controllers.
+    enable: u8,
+}
+
+impl<I> Pcf8574<I> {
+    pub(crate) fn new(i2c: I, config: &LcdConfig) -> Self {
+        Self {
+            i2c,
+            address: config.address,
+            pins: config.pins,
+            polarity: config.backlight_polarity,
+            retries: config.i2c_retries,
+            retry_count: 0,
+            transfer_limit: config
+                .i2c_transfer_limit
+                .map_or(STREAM_WRITES, |limit| usize::from(limit.max(1))),
+            enable: EN,
+        }
+    }
+
+    /// I2C address of the expander.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub(crate) fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Number of failed I2C transfers that were retried.
+    pub fn retry_count(&self) -> u32 {
+        self.retry_count
+    }
+
+    /// The I2C instance.
+    pub fn i2c(&self) -> &I {
+        &self.i2c
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+
+    /// Expander bit switching the backlight on or off, taking the polarity into account.
+    pub(crate) fn backlight_bit(&self, backlight: bool) -> u8 {
+        let on = match self.polarity {
+            Polarity::ActiveHigh => backlight,
+            Polarity::ActiveLow => !backlight,
+        };
+        match on {
+            true => Backlight::On as u8,
+            false => Backlight::Off as u8,
+        }
+    }
+
+    /// Expander writes clocking the lower four bits of `nibble` into D4 to D7 with RS set for
+    /// `rs` and RW low: set up the lines, raise EN, lower EN.
+    pub(crate) fn nibble_bytes(&self, rs: bool, nibble: u8, backlight: bool) -> [u8; 3] {
+        let byte = (nibble << 4) | if rs { RS } else { 0 } | self.backlight_bit(backlight);
+        [byte, byte | self.enable, self.backlight_bit(backlight)]
+    }
+
+    /// Expander writes clocking `byte` into the controller as two nibbles, high nibble first.
+    fn byte_bytes(&self, rs: bool, byte: u8, backlight: bool) -> [u8; BYTE_WRITES] {
+        let [a, b, c] = self.nibble_bytes(rs, byte >> 4, backlight);
+        let [d, e, f] = self.nibble_bytes(rs, byte & 0x0f, backlight);
+        [a, b, c, d, e, f]
+    }
+
+    /// `bytes` mapped to the pins of the expander, for up to [`STREAM_WRITES`] bytes.
+    fn map_out(&self, bytes: &[u8]) -> [u8; STREAM_WRITES] {
+        let mut mapped = [0; STREAM_WRITES];
+        for (out, &byte) in mapped.iter_mut().zip(bytes) {
+            *out = self.pins.map_out(byte);
+        }
+        mapped
+    }
+
+    /// Expander writes clocking `data` into the register selected by `rs`, for up to
+    /// [`STREAM_CHARS`] bytes.
+    fn stream_bytes(&self, rs: bool, backlight: bool, data: &[u8]) -> [u8; STREAM_WRITES] {
+        let mut bytes = [0; STREAM_WRITES];
+        for (chunk, &code) in bytes.chunks_mut(BYTE_WRITES).zip(data) {
+            chunk.copy_from_slice(&self.byte_bytes(rs, code, backlight));
+        }
+        bytes
+    }
+
+    /// Characters sent in one transaction by [`write_data`](LcdInterface::write_data), 0 if
+    /// not even one fits into the transfer limit. The same goes for instructions.
+    fn stream_chars(&self) -> usize {
+        (self.transfer_limit / BYTE_WRITES).min(STREAM_CHARS)
+    }
+
+    /// Whether a failed transaction of several expander writes can be repeated.
+    ///
+    /// The expander acknowledges every byte it receives, so a missing acknowledge means it
+    /// received nothing. A transaction cut off later may have latched EN already, repeating it
+    /// would clock a nibble into the controller a second time.
+    fn can_repeat<E: embedded_hal::i2c::Error>(error: &E) -> bool {
+        matches!(error.kind(), ErrorKind::NoAcknowledge(_))
+    }
+
+    /// Expander state for reading four bits with RS set for `rs`, RW high and the data lines
+    /// high so the controller can pull them low. EN is raised on top of it to read.
+    fn read_bits(&self, rs: bool, backlight: bool) -> u8 {
+        0xf0 | RW | if rs { RS } else { 0 } | self.backlight_bit(backlight)
+    }
+}
+
+/// Enable pins of `controllers`, EN for the first one and RW for the second one.
+fn enable_bits(controllers: u8) -> u8 {
+    let first = if controllers & 0b01 != 0 { EN } else { 0 };
+    let second = if controllers & 0b10 != 0 { RW } else { 0 };
+    first | second
+}
+
+impl<I: I2c> Pcf8574<I> {
+    /// Write one byte to the expander, retrying according to [`LcdConfig::with_i2c_retries`] if
+    /// the expander didn't acknowledge it.
+    ///
+    /// Only the failed byte is repeated. Repeating the whole nibble sequence would clock in the
+    /// nibbles the controller already latched a second time.
+    pub(crate) fn write_port(&mut self, byte: u8) -> Result<(), I::Error> {
+        let mut retries = 0;
+        loop {
+            match self.i2c.write(self.address, &[self.pins.map_out(byte)]) {
+                Ok(()) => return Ok(()),
+                Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                    retries += 1;
+                    self.retry_count = self.retry_count.saturating_add(1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Write `bytes` to the expander in one transaction, or several within the transfer limit,
+    /// it latches each of them in turn. A transaction the expander didn't acknowledge is retried
+    /// according to [`LcdConfig::with_i2c_retries`].
+    fn write_ports(&mut self, bytes: &[u8]) -> Result<(), I::Error> {
+        let mapped = self.map_out(bytes);
+        for chunk in mapped[..bytes.len()].chunks(self.transfer_limit) {
+            let mut retries = 0;
+            loop {
+                match self.i2c.write(self.address, chunk) {
+                    Ok(()) => break,
+                    Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                        retries += 1;
+                        self.retry_count = self.retry_count.saturating_add(1);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Clock `data` into the register selected by `rs` in transactions of up to
+    /// [`stream_chars`](Self::stream_chars) bytes, returning `false` if not even one fits.
+    fn write_stream(&mut self, rs: bool, data: &[u8], backlight: bool) -> Result<bool, I::Error> {
+        let chars = self.stream_chars();
+        if chars == 0 {
+            return Ok(false);
+        }
+        for chunk in data.chunks(chars) {
+            let bytes = self.stream_bytes(rs, backlight, chunk);
+            self.write_ports(&bytes[..chunk.len() * BYTE_WRITES])?;
+        }
+        Ok(true)
+    }
+
+    /// Read one byte from the expander, retrying according to [`LcdConfig::with_i2c_retries`] if
+    /// the expander didn't acknowledge its address.
+    fn read_port(&mut self) -> Result<u8, I::Error> {
+        let mut retries = 0;
+        loop {
+            let mut buf = [0];
+            match self.i2c.read(self.address, &mut buf) {
+                Ok(()) => return Ok(self.pins.map_in(buf[0])),
+                Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                    retries += 1;
+                    self.retry_count = self.retry_count.saturating_add(1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<I: I2c> LcdInterface for Pcf8574<I> {
+    type Error = I::Error;
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        // The I2C transfers are slower than the EN pulse has to be.
+        self.write_ports(&self.nibble_bytes(rs, nibble, backlight))
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_ports(&self.byte_bytes(rs, byte, backlight))
+    }
+
+    /// Send the characters in transactions of up to 16, see
+    /// [`LcdConfig::with_i2c_transfer_limit`].
+    ///
+    /// RS stays high throughout. The controller stores a character while the next high nibble is
+    /// set up, three expander writes take longer than it needs at bus clocks up to 400 kHz.
+    fn write_data(
+        &mut self,
+        data: &[u8],
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.write_stream(true, data, backlight)
+    }
+
+    /// Send the instructions like [`write_data`](LcdInterface::write_data) sends characters,
+    /// with RS low throughout.
+    fn write_commands(
+        &mut self,
+        commands: &[u8],
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.write_stream(false, commands, backlight)
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        // EN and the data lines stay low, so the controller isn't affected.
+        self.write_port(self.backlight_bit(backlight))
+    }
+
+    fn select_controllers(&mut self, controllers: u8) {
+        self.enable = enable_bits(controllers);
+    }
+
+    fn read_nibble(
+        &mut self,
+        rs: bool,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        let read = self.read_bits(rs, backlight);
+        self.write_port(read)?;
+        self.write_port(read | EN)?;
+        let pins = self.read_port()?;
+        self.write_port(read)?;
+        Ok(Some(pins >> 4))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> Pcf8574<I> {
+    /// See [`write_port`](Self::write_port).
+    async fn write_port_async(&mut self, byte: u8) -> Result<(), I::Error> {
+        let mut retries = 0;
+        loop {
+            let bytes = [self.pins.map_out(byte)];
+            match embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &bytes).await {
+                Ok(()) => return Ok(()),
+                Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                    retries += 1;
+                    self.retry_count = self.retry_count.saturating_add(1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// See [`write_ports`](Self::write_ports).
+    async fn write_ports_async(&mut self, bytes: &[u8]) -> Result<(), I::Error> {
+        let mapped = self.map_out(bytes);
+        for chunk in mapped[..bytes.len()].chunks(self.transfer_limit) {
+            let mut retries = 0;
+            loop {
+                match embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, chunk).await
+                {
+                    Ok(()) => break,
+                    Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                        retries += 1;
+                        self.retry_count = self.retry_count.saturating_add(1);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// See [`write_stream`](Self::write_stream).
+    async fn write_stream_async(
+        &mut self,
+        rs: bool,
+        data: &[u8],
+        backlight: bool,
+    ) -> Result<bool, I::Error> {
+        let chars = self.stream_chars();
+        if chars == 0 {
+            return Ok(false);
+        }
+        for chunk in data.chunks(chars) {
+            let bytes = self.stream_bytes(rs, backlight, chunk);
+            self.write_ports_async(&bytes[..chunk.len() * BYTE_WRITES])
+                .await?;
+        }
+        Ok(true)
+    }
+
+    /// See [`read_port`](Self::read_port).
+    async fn read_port_async(&mut self) -> Result<u8, I::Error> {
+        let mut retries = 0;
+        loop {
+            let mut buf = [0];
+            match embedded_hal_async::i2c::I2c::read(&mut self.i2c, self.address, &mut buf).await {
+                Ok(()) => return Ok(self.pins.map_in(buf[0])),
+                Err(e) if retries < self.retries && Self::can_repeat(&e) => {
+                    retries += 1;
+                    self.retry_count = self.retry_count.saturating_add(1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for Pcf8574<I> {
+    type Error = I::Error;
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_ports_async(&self.nibble_bytes(rs, nibble, backlight))
+            .await
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_ports_async(&self.byte_bytes(rs, byte, backlight))
+            .await
+    }
+
+    async fn write_data(
+        &mut self,
+        data: &[u8],
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.write_stream_async(true, data, backlight).await
+    }
+
+    async fn write_commands(
+        &mut self,
+        commands: &[u8],
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.write_stream_async(false, commands, backlight).await
+    }
+
+    async fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        // EN and the data lines stay low, so the controller isn't affected.
+        self.write_port_async(self.backlight_bit(backlight)).await
+    }
+
+    fn select_controllers(&mut self, controllers: u8) {
+        self.enable = enable_bits(controllers);
+    }
+
+    async fn read_nibble(
+        &mut self,
+        rs: bool,
+        backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<Option<u8>, Self::Error> {
+        let read = self.read_bits(rs, backlight);
+        self.write_port_async(read).await?;
+        self.write_port_async(read | EN).await?;
+        let pins = self.read_port_async().await?;
+        self.write_port_async(read).await?;
+        Ok(Some(pins >> 4))
+    }
+}
@@ -0,0 +1,275 @@
+//! The ST7032i, a controller with native I2C and a contrast voltage generator.
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{I2c, Operation},
+};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::LcdInterface;
+use crate::BitMode;
+
+/// I2C address of the ST7032i, it can't be changed.
+const ADDRESS: u8 = 0x3e;
+
+/// Control byte of instructions.
+const COMMAND: u8 = 0x00;
+/// Control byte of data for the RAM, every following byte is written to it.
+const DATA: u8 = 0x40;
+
+/// Function set with 8 bit mode and two lines, as used by the extended init.
+const FUNCTION_SET: u8 = 0x38;
+/// Bit of the function set selecting the extended instruction set.
+const EXTENDED: u8 = 0x01;
+/// Internal oscillator frequency: 1/5 bias, 183 Hz frame frequency.
+const OSCILLATOR: u8 = 0x14;
+/// Contrast set with the lower four bits of the contrast.
+const CONTRAST: u8 = 0x70;
+/// Power, icon and contrast set with the upper two bits of the contrast.
+const POWER: u8 = 0x50;
+/// Bit of the power command switching the booster on.
+const BOOSTER: u8 = 0x04;
+/// Follower control with the follower circuit on.
+const FOLLOWER: u8 = 0x68;
+
+/// Time an instruction of the extended instruction set takes in µs.
+const EXECUTION_US: u32 = 27;
+/// Time the voltage of the follower circuit takes to settle in ms.
+const FOLLOWER_MS: u32 = 200;
+
+/// ST7032i controller of many 3.3 V I2C displays, speaking I2C itself.
+///
+/// The controller runs in 8 bit mode with every byte sent whole after a control byte.
+/// [`init`](LcdInterface::init) powers up its contrast voltage generation through the extended
+/// instruction set, then the usual init sequence runs. The contrast is changed at runtime with
+/// [`set_contrast`](crate::sync_lcd::GenericLcd::set_contrast). There is no backlight on the bus,
+/// so switching it does nothing, and the controller isn't read from. `Font5x10` selects double
+/// height lines on this controller.
+///
+/// ```ignore
+/// let interface = St7032iInterface::new(i2c).with_contrast(32).unwrap();
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// lcd.set_contrast(40)?;
+/// ```
+#[derive(Debug)]
+pub struct St7032iInterface<I> {
+    i2c: I,
+    contrast: u8,
+    booster: bool,
+    follower_ratio: u8,
+}
+
+impl<I> St7032iInterface<I> {
+    /// Interface to the controller at its fixed address 0x3e, for a supply of 3.3 V: booster on,
+    /// contrast 40 and follower amplification ratio 4.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            contrast: 40,
+            booster: true,
+            follower_ratio: 4,
+        }
+    }
+
+    /// Set the contrast set by init, returns `None` if `level` is not in `0..64`.
+    pub fn with_contrast(mut self, level: u8) -> Option<Self> {
+        if level >= 64 {
+            return None;
+        }
+        self.contrast = level;
+        Some(self)
+    }
+
+    /// Switch the booster on for a supply of 3.3 V, off for 5 V.
+    pub fn with_booster(mut self, booster: bool) -> Self {
+        self.booster = booster;
+        self
+    }
+
+    /// Set the amplification ratio of the follower circuit, the `Rab` bits of the follower
+    /// control, returns `None` if `ratio` is not in `0..8`.
+    pub fn with_follower_ratio(mut self, ratio: u8) -> Option<Self> {
+        if ratio >= 8 {
+            return None;
+        }
+        self.follower_ratio = ratio;
+        Some(self)
+    }
+
+    /// Contrast of the display, `0..64`.
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// I2C address of the controller.
+    pub fn address(&self) -> u8 {
+        ADDRESS
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+
+    /// Switch to `level` and return the instructions setting it, switching from the function set
+    /// `function_set` to the extended instruction set and back.
+    pub(crate) fn set_contrast(&mut self, level: u8, function_set: u8) -> [u8; 4] {
+        self.contrast = level;
+        let [contrast, power] = self.contrast_commands();
+        [function_set | EXTENDED, contrast, power, function_set]
+    }
+
+    /// Contrast set and power, icon and contrast set with the current contrast and booster.
+    fn contrast_commands(&self) -> [u8; 2] {
+        let booster = if self.booster { BOOSTER } else { 0 };
+        [
+            CONTRAST | (self.contrast & 0x0f),
+            POWER | booster | (self.contrast >> 4),
+        ]
+    }
+
+    /// Instructions of the extended init before the follower circuit settles.
+    fn init_commands(&self) -> [u8; 6] {
+        let [contrast, power] = self.contrast_commands();
+        [
+            FUNCTION_SET,
+            FUNCTION_SET | EXTENDED,
+            OSCILLATOR,
+            contrast,
+            power,
+            FOLLOWER | self.follower_ratio,
+        ]
+    }
+}
+
+/// Control byte selecting the register for `rs`.
+fn control(rs: bool) -> u8 {
+    match rs {
+        true => DATA,
+        false => COMMAND,
+    }
+}
+
+impl<I: I2c> LcdInterface for St7032iInterface<I> {
+    type Error = I::Error;
+
+    fn init(&mut self, delay: &mut impl DelayNs) -> Result<(), Self::Error> {
+        for command in self.init_commands() {
+            self.i2c.write(ADDRESS, &[COMMAND, command])?;
+            delay.delay_us(EXECUTION_US);
+        }
+        delay.delay_ms(FOLLOWER_MS);
+        // Back to the normal instruction set.
+        self.i2c.write(ADDRESS, &[COMMAND, FUNCTION_SET])?;
+        delay.delay_us(EXECUTION_US);
+        Ok(())
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    /// Send `nibble` as the upper four bits of a byte, what an 8 bit bus with D0 to D3 low would
+    /// clock in.
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay)
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.i2c.write(ADDRESS, &[control(rs), byte])
+    }
+
+    fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.i2c.transaction(
+            ADDRESS,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )?;
+        Ok(true)
+    }
+
+    fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for St7032iInterface<I> {
+    type Error = I::Error;
+
+    async fn init(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        for command in self.init_commands() {
+            embedded_hal_async::i2c::I2c::write(&mut self.i2c, ADDRESS, &[COMMAND, command])
+                .await?;
+            delay.delay_us(EXECUTION_US).await;
+        }
+        delay.delay_ms(FOLLOWER_MS).await;
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, ADDRESS, &[COMMAND, FUNCTION_SET])
+            .await?;
+        delay.delay_us(EXECUTION_US).await;
+        Ok(())
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay).await
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, ADDRESS, &[control(rs), byte]).await
+    }
+
+    async fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        embedded_hal_async::i2c::I2c::transaction(
+            &mut self.i2c,
+            ADDRESS,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! The US2066 of character OLEDs, also sold as SSD1311, with native I2C.
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{I2c, Operation},
+};
+
+#[cfg(feature = "async")]
+use super::AsyncLcdInterface;
+use super::LcdInterface;
+use crate::BitMode;
+
+/// I2C address of the US2066 with SA0 low.
+const ADDRESS: u8 = 0x3c;
+
+/// Control byte of instructions.
+const COMMAND: u8 = 0x00;
+/// Control byte of data for the RAM, every following byte is written to it.
+const DATA: u8 = 0x40;
+
+/// Bit of the function set selecting the extended instruction set.
+const EXTENDED: u8 = 0x02;
+/// Enable the OLED instruction set, needs the extended instruction set.
+const OLED_ON: u8 = 0x79;
+/// Disable the OLED instruction set.
+const OLED_OFF: u8 = 0x78;
+/// Set the contrast to the following byte, an OLED instruction.
+const SET_CONTRAST: u8 = 0x81;
+
+/// Function selection A with the internal regulator for 5 V, followed by a data byte.
+const REGULATOR_ON: u8 = 0x5c;
+
+/// US2066 controller of character OLEDs like the Newhaven NHD-0216CW, speaking I2C itself.
+///
+/// The controller runs in 8 bit mode with every byte sent whole after a control byte.
+/// [`init`](LcdInterface::init) sets up the oscillator, the segment and COM wiring, the contrast
+/// and the driving voltages through the extended and OLED instruction sets, with the values of the
+/// Newhaven examples. The usual init sequence runs after that, then the controller behaves like a
+/// HD44780 with 8 custom characters in its CGRAM. The contrast is changed at runtime with
+/// [`set_contrast`](crate::sync_lcd::GenericLcd::set_contrast).
+///
+/// Only modules with one or two lines are supported, four line modules use a DDRAM layout the
+/// driver doesn't track. There is no backlight to switch and the controller isn't read from.
+///
+/// ```ignore
+/// let interface = Us2066Interface::new(i2c).with_contrast(0xc0);
+/// let mut lcd = Lcd::<2, 16, _, _>::init_with_interface(interface, delay, LcdConfig::default())?;
+/// ```
+#[derive(Debug)]
+pub struct Us2066Interface<I> {
+    i2c: I,
+    address: u8,
+    contrast: u8,
+    regulator: bool,
+}
+
+impl<I> Us2066Interface<I> {
+    /// Interface to the controller at 0x3c with the internal regulator on and contrast 0x7f.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            address: ADDRESS,
+            contrast: 0x7f,
+            regulator: true,
+        }
+    }
+
+    /// Use the controller at `address`, 0x3d if SA0 is high.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Set the contrast set by init.
+    pub fn with_contrast(mut self, level: u8) -> Self {
+        self.contrast = level;
+        self
+    }
+
+    /// Use the internal regulator of the controller, needed for a supply of 5 V.
+    pub fn with_internal_regulator(mut self, regulator: bool) -> Self {
+        self.regulator = regulator;
+        self
+    }
+
+    /// Contrast of the display.
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// I2C address of the controller.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+
+    /// Switch to `level` and return the instructions setting it, switching from the function set
+    /// `function_set` to the OLED instruction set and back.
+    pub(crate) fn set_contrast(&mut self, level: u8, function_set: u8) -> [u8; 6] {
+        self.contrast = level;
+        [
+            function_set | EXTENDED,
+            OLED_ON,
+            SET_CONTRAST,
+            level,
+            OLED_OFF,
+            function_set,
+        ]
+    }
+
+    /// Bytes of the init before the usual init sequence, with `true` for data bytes.
+    fn init_bytes(&self) -> [(bool, u8); 30] {
+        let regulator = if self.regulator { REGULATOR_ON } else { 0x00 };
+        [
+            // Function selection A: internal regulator.
+            (false, 0x28 | EXTENDED),
+            (false, 0x71),
+            (true, regulator),
+            // Display off in the fundamental instruction set.
+            (false, 0x28),
+            (false, 0x08),
+            // Unlock the OLED instructions and set the display clock.
+            (false, 0x28 | EXTENDED),
+            (false, OLED_ON),
+            (false, 0xfd),
+            (false, 0x12),
+            (false, 0xd5),
+            (false, 0x70),
+            (false, OLED_OFF),
+            // Extended function set: 5 dot wide font, one or two lines.
+            (false, 0x08),
+            // COM from top to bottom, segments from left to right.
+            (false, 0x06),
+            // Function selection B: ROM A with 8 CGRAM characters.
+            (false, 0x72),
+            (true, 0x00),
+            // SEG pins, function selection C, contrast, phase length and VCOMH level.
+            (false, 0x28 | EXTENDED),
+            (false, OLED_ON),
+            (false, 0xda),
+            (false, 0x10),
+            (false, 0xdc),
+            (false, 0x00),
+            (false, SET_CONTRAST),
+            (false, self.contrast),
+            (false, 0xd9),
+            (false, 0xf1),
+            (false, 0xdb),
+            (false, 0x40),
+            (false, OLED_OFF),
+            // Back to the fundamental instruction set.
+            (false, 0x28),
+        ]
+    }
+}
+
+/// Control byte selecting the register for `rs`.
+fn control(rs: bool) -> u8 {
+    match rs {
+        true => DATA,
+        false => COMMAND,
+    }
+}
+
+impl<I: I2c> LcdInterface for Us2066Interface<I> {
+    type Error = I::Error;
+
+    fn init(&mut self, _delay: &mut impl DelayNs) -> Result<(), Self::Error> {
+        for (rs, byte) in self.init_bytes() {
+            self.i2c.write(self.address, &[control(rs), byte])?;
+        }
+        Ok(())
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    /// Send `nibble` as the upper four bits of a byte, what an 8 bit bus with D0 to D3 low would
+    /// clock in.
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay)
+    }
+
+    fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[control(rs), byte])
+    }
+
+    fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<bool, Self::Error> {
+        self.i2c.transaction(
+            self.address,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )?;
+        Ok(true)
+    }
+
+    fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncLcdInterface for Us2066Interface<I> {
+    type Error = I::Error;
+
+    async fn init(
+        &mut self,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        for (rs, byte) in self.init_bytes() {
+            embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[control(rs), byte])
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn bit_mode(&self) -> BitMode {
+        BitMode::Bit8
+    }
+
+    async fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        self.write_byte(rs, nibble << 4, backlight, delay).await
+    }
+
+    async fn write_byte(
+        &mut self,
+        rs: bool,
+        byte: u8,
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Self::Error> {
+        embedded_hal_async::i2c::I2c::write(&mut self.i2c, self.address, &[control(rs), byte]).await
+    }
+
+    async fn write_data(
+        &mut self,
+        data: &[u8],
+        _backlight: bool,
+        _delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<bool, Self::Error> {
+        embedded_hal_async::i2c::I2c::transaction(
+            &mut self.i2c,
+            self.address,
+            &mut [Operation::Write(&[DATA]), Operation::Write(data)],
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn set_backlight(&mut self, _backlight: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -1,43 +1,128 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! Driver to write characters to LCD displays with a LM1602 connected via i2c like [this one] with
-//! 16x2 characters. It requires a I2C instance implementing [`embedded_hal::blocking::i2c::Write`]
-//! and a instance to delay execution with [`embedded_hal::blocking::delay::DelayMs`].
+//! 16x2 characters. It takes an I2C instance implementing [`embedded_hal::i2c::I2c`] and an
+//! instance to delay execution with [`embedded_hal::delay::DelayNs`], both by value, so the
+//! driver can be kept in a struct or a `static`.
 //! Other LCD sizes are supported, up to displays with 20x4 characters. Everything that uses a
 //! HD44780U or comparable controller and is connected via i2c should work
 //!
-//! Usage:
+//! Usage, e.g. with `arduino_hal::I2c` and `arduino_hal::Delay`:
 //! ```
+//! use embedded_hal::{delay::DelayNs, i2c::I2c};
+//! use lcd_lcm1602_i2c::{Error, LCD16x2};
+//!
 //! const LCD_ADDRESS: u8 = 0x27; // Address depends on hardware, see link below
 //!
-//! // Create a I2C instance, needs to implement embedded_hal::blocking::i2c::Write, this
-//! // particular uses the arduino_hal crate for avr microcontrollers like the arduinos.
-//! let dp = arduino_hal::Peripherals::take().unwrap();
-//! let pins = arduino_hal::pins!(dp);
-//! let mut i2c = arduino_hal::I2c::new(
-//!     dp.TWI, //
-//!     pins.a4.into_pull_up_input(), // use respective pins
-//!     pins.a5.into_pull_up_input(),
-//!     50000,
-//! );
-//! let mut delay = arduino_hal::Delay::new();
+//! fn hello<I: I2c, D: DelayNs>(i2c: I, delay: D) -> Result<(I, D), Error<I::Error>> {
+//!     // `new` returns a builder for the settings, only `init` returns the driver writing to
+//!     // the display.
+//!     let mut lcd = LCD16x2::new(i2c, delay)
+//!         .with_address(LCD_ADDRESS)
+//!         .with_cursor_on(false) // no visible cursor
+//!         .init()?;
+//!     lcd.write_str("Hello")?;
+//!     // The I2C and delay instance are given back by the driver.
+//!     Ok(lcd.into_parts())
+//! }
+//! ```
+//!
+//! With the `async` feature, `async_lcd` has the same driver for `embedded-hal-async`, every
+//! method talking to the display is `async` there.
+//!
+//! The driver owns its I2C instance and only uses it during an operation, so the display can
+//! share the bus with other devices through the device types of [`embedded-hal-bus`]. Each
+//! device locks the bus for one transfer at a time:
+//! ```
+//! use core::cell::RefCell;
+//! use embedded_hal::i2c::{ErrorType, I2c, Operation};
+//! use embedded_hal_bus::i2c::RefCellDevice;
+//! # struct Delay;
+//! # impl embedded_hal::delay::DelayNs for Delay {
+//! #     fn delay_ns(&mut self, _ns: u32) {}
+//! # }
+//! # /// Bus remembering the address of every write.
+//! # #[derive(Default)]
+//! # struct Bus(Vec<u8>);
+//! # impl ErrorType for Bus {
+//! #     type Error = core::convert::Infallible;
+//! # }
+//! # impl I2c for Bus {
+//! #     fn transaction(&mut self, address: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+//! #         self.0.push(address);
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! let bus = RefCell::new(Bus::default());
+//! let mut lcd = lcd_lcm1602_i2c::LCD16x2::new(RefCellDevice::new(&bus), Delay)
+//!     .with_address(0x27)
+//!     .init()
+//!     .unwrap();
+//! let mut sensor = RefCellDevice::new(&bus);
+//!
+//! lcd.write_str("23 C").unwrap();
+//! sensor.write(0x48, &[0x00]).unwrap();
+//! lcd.write_str(" ok").unwrap();
 //!
-//! let mut lcd = lcd_lcm1602_i2c::LCD16x2::new(&mut i2c, &mut delay)
-//!     .with_address(LCD_ADDRESS)
-//!     .with_cursor_on(false) // no visible cursor
-//!     .init().unwrap();
+//! let addresses = bus.into_inner().0;
+//! let sensor_write = addresses.iter().position(|&a| a == 0x48).unwrap();
+//! assert!(addresses[..sensor_write].iter().all(|&a| a == 0x27));
+//! assert!(addresses[sensor_write + 1..].iter().all(|&a| a == 0x27));
 //! ```
 //!
+//! With the `std` feature, the error types implement `std::error::Error`, e.g. to use the driver
+//! with `linux-embedded-hal` on a Raspberry Pi. The driver is `Send` if the I2C and delay
+//! instance are, so it can be moved to its own thread. `simulator` has a simulated display to
+//! try out layouts on the host.
+//!
+//! The driver talks to the controller through an [`LcdInterface`](interface::LcdInterface), the
+//! PCF8574 of the I2C backpacks is the one `new` and `init` use. Displays wired differently, e.g.
+//! straight to GPIO pins with [`ParallelInterface`](interface::ParallelInterface), are
+//! initialized with `init_with_interface`. So are modules where the controller speaks I2C itself,
+//! with [`Aip31068Interface`](interface::Aip31068Interface) or
+//! [`St7032iInterface`](interface::St7032iInterface), and character OLEDs with
+//! [`Us2066Interface`](interface::Us2066Interface).
+//! The `rgb` feature adds the `rgb` module for the RGB backlight of the Grove LCD modules.
+//! The `buffered` feature adds the `buffered` module, to draw into a RAM buffer and send only the
+//! characters that changed.
+//! 40x4 displays, which have two controllers, are driven by [`DualLcd`] with
+//! the enable pin of the second controller on the RW pin of the backpack.
+//!
 //! This [site][lcd address] describes how to find the address of your LCD devices.
 //!
 //! [this one]: https://funduinoshop.com/elektronische-module/displays/lcd/16x02-i2c-lcd-modul-hintergrundbeleuchtung-blau
 //! [lcd address]: https://www.ardumotive.com/i2clcden.html
+//! [`embedded-hal-bus`]: https://docs.rs/embedded-hal-bus
+
+use core::fmt;
 
-use sync_lcd::Lcd;
+use sync_lcd::{DualLcd, Lcd};
 
 #[cfg(feature = "async")]
 pub mod async_lcd;
+#[cfg(feature = "buffered")]
+pub mod buffered;
+pub mod charset;
+#[cfg(feature = "eh0")]
+pub mod compat;
+pub mod config;
+mod format;
+pub mod geometry;
+pub mod glyph;
+pub mod group;
+pub mod interface;
+mod plan;
+pub mod queued;
+#[cfg(feature = "rgb")]
+pub mod rgb;
+#[cfg(feature = "std")]
+pub mod simulator;
+mod state;
 pub mod sync_lcd;
+pub mod testing;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DisplayControl {
     Off = 0x00,
     CursorBlink = 0x01,
@@ -45,23 +130,36 @@ pub enum DisplayControl {
     DisplayOn = 0x04,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Backlight {
     Off = 0x00,
     On = 0x08,
 }
 
+/// Level of an expander pin that switches something on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Polarity {
+    /// The pin is high when on, like on most backpacks.
+    ActiveHigh,
+    /// The pin is low when on.
+    ActiveLow,
+}
+
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Mode {
     Cmd = 0x00,
     Data = 0x01,
     EntrySet = 0x04,
     DisplayControl = 0x08,
     FunctionSet = 0x20,
+    CGRAMAddr = 0x40,
     DDRAMAddr = 0x80,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Commands {
     Clear = 0x01,
     ReturnHome = 0x02,
@@ -71,43 +169,354 @@ enum Commands {
     ShiftDisplayRight = 16 | 8 | 4,
 }
 
-enum BitMode {
+/// Width of the data bus between an [`LcdInterface`](interface::LcdInterface) and the
+/// controller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BitMode {
+    /// D4 to D7, every byte is sent as two nibbles.
     Bit4 = 0x0 << 4,
+    /// D0 to D7, every byte is sent at once.
     Bit8 = 0x1 << 4,
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Font {
     Font5x8 = 0x00,
     Font5x10 = 0x04,
 }
 
+/// Bit of the entry mode set instruction moving the address counter after each character.
+///
+/// The variants are named after the side the text flows from, not the way the cursor moves:
+/// [`Left`](Self::Left) increments the address counter and writes from left to right, see
+/// `left_to_right`, [`Right`](Self::Right) decrements it and writes from right to left.
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CursorMoveDir {
     Right = 0x00,
     Left = 0x02,
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DisplayShift {
     Decrement = 0x00,
     Increment = 0x01,
 }
 
+/// Direction to shift the display or cursor in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ShiftDirection {
+    Left,
+    Right,
+}
+
+/// Behaviour of a newline on the last row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum NewlineMode {
+    /// Drop everything written after the newline until the cursor is moved.
+    Clip,
+    /// Continue on the first row.
+    Wrap,
+    /// Move the rows up by one row and continue at the start of the cleared last row.
+    Scroll,
+}
+
+/// What happens when text is written past the end of a row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Drop the characters past the end of the row.
+    Truncate,
+    /// Continue at the start of the next row. On the last row, the [`NewlineMode`] applies.
+    WrapRow,
+    /// Continue at the start of the next row. On the last row, the rows are moved up by one row
+    /// and writing continues at the start of the cleared last row.
+    Scroll,
+    /// Return [`Error::RowOverflow`].
+    Error,
+}
+
+/// Character ROM of the display, see the HD44780U datasheet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Charset {
+    /// Japanese ROM with half-width Katakana, the most common one.
+    ///
+    /// Symbols like `'°'`, `'µ'` and `'→'` and German umlauts are translated to their codes.
+    /// There is no `'\\'` and `'~'`, the ROM has `'¥'` and `'→'` at their codes instead.
+    A00,
+    /// European ROM with Latin-1, Greek and Cyrillic letters.
+    #[cfg(feature = "rom-a02")]
+    A02,
+}
+
+/// Controller of the display, for the ones needing another init sequence than the HD44780.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Controller {
+    /// HD44780 or a compatible controller, like on most displays.
+    Hd44780,
+    /// RS0010, also sold as WS0010, of the Winstar WEH character OLEDs.
+    ///
+    /// The init doesn't wait for the power on, synchronizes with a single function set in 8 bit
+    /// mode and then switches the controller to character mode with its internal power on. The
+    /// font table bits of the function set stay at the English/Japanese table, the one like
+    /// [`Charset::A00`].
+    Rs0010,
+}
+
+/// What happens to characters the display can't show.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UnmappablePolicy {
+    /// Write the given character code instead, e.g. `b'?'` or `0xFF` for a full block.
+    Replace(u8),
+    /// Return [`Error::UnmappableChar`].
+    Error,
+}
+
+/// Errors returned by the driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// Error of the underlying I2C bus, or of the [`LcdInterface`](interface::LcdInterface)
+    /// the display is wired through.
+    I2c(E),
+    /// Row is outside of the display.
+    InvalidRow,
+    /// Column is outside of the display.
+    InvalidColumn,
+    /// Custom character slot is not in `0..8`.
+    InvalidCgramSlot,
+    /// The operation isn't available with the font currently used.
+    WrongFont,
+    /// All CGRAM slots are used by other glyphs.
+    CgramFull,
+    /// The cursor position was saved already and not restored yet.
+    CursorAlreadySaved,
+    /// No cursor position was saved.
+    NoSavedCursor,
+    /// A row offset is outside of the DDRAM.
+    InvalidRowOffset,
+    /// The pin mapping uses a pin that doesn't exist or a pin twice.
+    InvalidPinMapping,
+    /// The size of the display isn't supported.
+    InvalidGeometry,
+    /// Text was written past the end of a row with [`OverflowPolicy::Error`].
+    RowOverflow {
+        /// Number of characters written before the end of the row was reached.
+        written: usize,
+    },
+    /// A character can't be shown with [`UnmappablePolicy::Error`].
+    UnmappableChar {
+        /// Byte index of the character in the string, like the index of `str::char_indices`.
+        index: usize,
+    },
+    /// Reading from the display failed, probably because its RW pin isn't connected.
+    ReadUnavailable,
+    /// No I2C address was set, see [`LcdConfig::with_address`](config::LcdConfig::with_address).
+    NoAddressConfigured,
+    /// The I2C address is reserved by the I2C specification, i.e. `0x00` to `0x07` or `0x78` to
+    /// `0x7f`.
+    ReservedAddress(u8),
+    /// [`Font::Font5x10`] was selected for a display with more than one row, where the controller
+    /// only supports [`Font::Font5x8`].
+    UnsupportedFontForGeometry,
+    /// The operation doesn't fit into the queue of a [`QueuedLcd`](queued::QueuedLcd).
+    QueueFull,
+    /// The contrast is outside of `0..64`.
+    InvalidContrast,
+    /// The LED driver of an RGB backlight doesn't answer, see `rgb::RgbBacklight::init`.
+    BacklightNotFound,
+    /// More decimals than an `i32` has digits were asked for, see `write_fixed`.
+    InvalidDecimals,
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "I2C error: {:?}", e),
+            Error::InvalidRow => f.write_str("row is outside of the display"),
+            Error::InvalidColumn => f.write_str("column is outside of the display"),
+            Error::InvalidCgramSlot => f.write_str("custom character slot is out of range"),
+            Error::WrongFont => f.write_str("operation isn't available with the current font"),
+            Error::CgramFull => f.write_str("all custom character slots are used"),
+            Error::CursorAlreadySaved => f.write_str("cursor position is saved already"),
+            Error::NoSavedCursor => f.write_str("no cursor position is saved"),
+            Error::InvalidRowOffset => f.write_str("row offset is outside of the DDRAM"),
+            Error::InvalidPinMapping => f.write_str("pin mapping is invalid"),
+            Error::InvalidGeometry => f.write_str("display size isn't supported"),
+            Error::RowOverflow { written } => {
+                write!(f, "row overflowed after {} characters", written)
+            }
+            Error::UnmappableChar { index } => {
+                write!(f, "character at {} can't be shown", index)
+            }
+            Error::ReadUnavailable => f.write_str("display can't be read from"),
+            Error::NoAddressConfigured => f.write_str("no I2C address is configured"),
+            Error::ReservedAddress(address) => {
+                write!(f, "I2C address {:#04x} is reserved", address)
+            }
+            Error::UnsupportedFontForGeometry => {
+                f.write_str("font isn't supported with more than one row")
+            }
+            Error::QueueFull => f.write_str("queue is full"),
+            Error::InvalidContrast => f.write_str("contrast is out of range"),
+            Error::BacklightNotFound => f.write_str("backlight LED driver doesn't answer"),
+            Error::InvalidDecimals => f.write_str("more decimals than an i32 has digits"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+/// Number of times the busy flag is polled before falling back to fixed delays.
+const BUSY_POLLS: u8 = 40;
+/// Delay between two polls of the busy flag in µs.
+const BUSY_POLL_DELAY: u32 = 50;
+/// Status read from a board with RW tied to ground: the data lines stay high, which no
+/// controller reports since the address counter never reaches 0x7f.
+const RW_GROUNDED: u8 = 0xff;
+
+/// Most characters `write_str` sends together, a row of the widest displays.
+const BATCH_LEN: usize = 40;
+
+/// Mode and power instruction of the RS0010, a cursor or display shift on the HD44780: character
+/// mode with the graphic mode bit (0x08) cleared and the internal power on (0x04).
+const RS0010_CHARACTER_MODE: u8 = 0x17;
+
 // offsets taken from the NewLiquidCrystal library
 const OFFSETS_NORMAL: [u8; 4] = [0x00, 0x40, 0x14, 0x54]; // For regular LCDs
 const OFFSETS_16X4: [u8; 4] = [0x00, 0x40, 0x10, 0x50]; // For 16x4 LCDs
+const OFFSETS_DUAL: [u8; 4] = [0x00, 0x40, 0x80, 0xc0]; // For LCDs with two controllers
+
+/// DDRAM addresses of the first column of each row for a display of the given size.
+///
+/// Bit 7 of the addresses selects the second controller of displays with two, see
+/// [`geometry::Dual`].
+fn default_row_offsets(rows: u8, columns: u8, controllers: u8) -> [u8; 4] {
+    if controllers == 2 {
+        OFFSETS_DUAL
+    } else if rows == 4 && columns == 16 {
+        OFFSETS_16X4
+    } else {
+        OFFSETS_NORMAL
+    }
+}
+
+/// Index of the DDRAM address `addr` in a copy of the 80 bytes of DDRAM of each controller.
+fn ddram_index(rows: u8, addr: u8) -> usize {
+    let controller = (addr >> 7) as usize * 80;
+    let addr = addr & 0x7f;
+    if rows > 1 && addr >= 0x40 {
+        controller + (addr - 0x40 + 0x28) as usize
+    } else {
+        controller + addr as usize
+    }
+}
+
+/// DDRAM address after `addr` when moving forward or backward.
+///
+/// In one line mode the DDRAM is one block of 80 addresses, in two line mode there are two
+/// blocks of 40 addresses at 0x00 and 0x40. The address stays on its controller.
+fn next_address(rows: u8, addr: u8, forward: bool) -> u8 {
+    let controller = addr & 0x80;
+    let addr = addr & 0x7f;
+    let (first, last) = if rows == 1 {
+        ((0x00, 0x00), (0x4f, 0x4f))
+    } else {
+        ((0x00, 0x40), (0x27, 0x67))
+    };
+    controller
+        | match forward {
+            true if addr == last.0 => first.1,
+            true if addr == last.1 => first.0,
+            true => addr + 1,
+            false if addr == first.0 => last.1,
+            false if addr == first.1 => last.0,
+            false => addr - 1,
+        }
+}
+
+/// I2C addresses of the PCF8574 (`0x20` to `0x27`) and PCF8574A (`0x38` to `0x3f`) backpacks, with
+/// the usual factory settings first. Meant for `detect_address`.
+pub const BACKPACK_ADDRESSES: [u8; 16] = [
+    0x27, 0x3f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e,
+];
+
+/// Whether a probe write was acknowledged, a missing acknowledge of the address isn't an error.
+fn acknowledged<E: embedded_hal::i2c::Error>(result: Result<(), E>) -> Result<bool, Error<E>> {
+    use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) => match e.kind() {
+            ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address | NoAcknowledgeSource::Unknown,
+            ) => Ok(false),
+            _ => Err(Error::I2c(e)),
+        },
+    }
+}
+
+/// Check that `address` is set and usable by a device.
+fn check_address<E>(address: u8) -> Result<(), Error<E>> {
+    match address {
+        0 => Err(Error::NoAddressConfigured),
+        0x01..=0x07 | 0x78..=0x7f => Err(Error::ReservedAddress(address)),
+        _ => Ok(()),
+    }
+}
+
+/// Number of DDRAM addresses from the start of the row at `offset` to the end of its line.
+fn row_capacity(rows: u8, offset: u8) -> u8 {
+    if rows == 1 {
+        0x50u8.saturating_sub(offset)
+    } else {
+        ((offset & 0xc0) + 0x28).saturating_sub(offset)
+    }
+}
 
-pub type LCD16x2<'a, I, D> = Lcd<'a, 2, 16, I, D>;
-pub type LCD16x4<'a, I, D> = Lcd<'a, 4, 16, I, D>;
-pub type LCD20x4<'a, I, D> = Lcd<'a, 4, 20, I, D>;
+/// Position (row, col) of the DDRAM address `addr`.
+///
+/// The position is relative to the row with the closest offset below `addr`, so the column can be
+/// outside of the visible display.
+fn cursor_position(rows: u8, offsets: &[u8; 4], addr: u8) -> (u8, u8) {
+    let mut pos = None;
+    for row in 0..rows {
+        let offset = offsets[row as usize];
+        if offset <= addr && pos.is_none_or(|(_, col)| addr - offset < col) {
+            pos = Some((row, addr - offset));
+        }
+    }
+    // An address before every row, e.g. after returning home, is left of the first one.
+    pos.unwrap_or((0, 0))
+}
+
+pub type LCD16x2<I, D> = Lcd<2, 16, I, D>;
+pub type LCD16x4<I, D> = Lcd<4, 16, I, D>;
+pub type LCD20x4<I, D> = Lcd<4, 20, I, D>;
+pub type LCD40x4<I, D> = DualLcd<40, I, D>;
 
 #[cfg(feature = "async")]
-pub type AsyncLCD16x2<'a, I, D> = async_lcd::Lcd<'a, 2, 16, I, D>;
+pub type AsyncLCD16x2<I, D> = async_lcd::Lcd<2, 16, I, D>;
+#[cfg(feature = "async")]
+pub type AsyncLCD16x4<I, D> = async_lcd::Lcd<4, 16, I, D>;
 #[cfg(feature = "async")]
-pub type AsyncLCD16x4<'a, I, D> = async_lcd::Lcd<'a, 4, 16, I, D>;
+pub type AsyncLCD20x4<I, D> = async_lcd::Lcd<4, 20, I, D>;
 #[cfg(feature = "async")]
-pub type AsyncLCD20x4<'a, I, D> = async_lcd::Lcd<'a, 4, 20, I, D>;
+pub type AsyncLCD40x4<I, D> = async_lcd::DualLcd<40, I, D>;
@@ -0,0 +1,2067 @@
+//! The operations of the driver, worked out from the [`State`] one [`Op`] at a time so the sync
+//! and async driver only carry out the ops.
+//!
+//! The driver steps a [`Plan`] until it's done, passing the [`Outcome`] of each op into the next
+//! step. The state is updated once the ops it depends on went through, a failed op ends the plan
+//! with the state the ops before it left.
+
+use core::{
+    iter,
+    str::{CharIndices, SplitWhitespace},
+};
+
+use crate::{
+    geometry::Geometry, state::State, Backlight, BitMode, Commands, Controller, CursorMoveDir,
+    DisplayControl, Error, Mode, NewlineMode, OverflowPolicy, ShiftDirection, BATCH_LEN,
+    RS0010_CHARACTER_MODE,
+};
+
+/// Hand out the ops of a nested plan, evaluating to its output once it's done.
+///
+/// The outcome is only passed on once, a plan started afterwards begins with [`Outcome::Done`].
+macro_rules! nested {
+    ($plan:expr, $state:expr, $outcome:expr) => {
+        match $plan.step($state, core::mem::take($outcome)) {
+            Ok(Step::Op(op)) => return Ok(Step::Op(op)),
+            Ok(Step::Done(output)) => output,
+            Err(e) => return Err(e),
+        }
+    };
+}
+
+/// Something the driver does with the interface and the delay.
+#[derive(Clone, Copy)]
+pub(crate) enum Op<'a> {
+    /// Wait the given µs.
+    Delay(u32),
+    /// Wait the given ms.
+    DelayMs(u32),
+    /// Prepare the interface for the init sequence.
+    Init,
+    /// Select the controllers the following writes go to.
+    Select(u8),
+    /// Switch the backlight without affecting the controller.
+    Backlight(bool),
+    /// Write the lower four bits of `nibble` to D4 to D7, without waiting.
+    Nibble { rs: bool, nibble: u8 },
+    /// Write `byte` to the selected controllers, without waiting.
+    Write { rs: bool, byte: u8 },
+    /// Write `byte` to `controllers` and wait until it's executed, `wait_us` without busy flag
+    /// polling.
+    Send {
+        controllers: u8,
+        rs: bool,
+        byte: u8,
+        wait_us: u32,
+    },
+    /// Write `bytes` to `controllers` in one go if the interface can, answered with
+    /// [`Outcome::Sent`]. The wait afterwards is up to the plan.
+    Batch {
+        controllers: u8,
+        rs: bool,
+        bytes: Bytes<'a>,
+    },
+    /// Wait until the controller is ready, the given µs without busy flag polling.
+    Wait(u32),
+    /// Read a byte, the status or the RAM at the address counter, answered with
+    /// [`Outcome::Read`].
+    Read { rs: bool },
+    /// Poll the busy flag, answered with [`Outcome::Ready`].
+    PollReady,
+}
+
+/// Bytes of an [`Op::Batch`], borrowed from the caller or worked out by the plan.
+#[derive(Clone, Copy)]
+pub(crate) enum Bytes<'a> {
+    Borrowed(&'a [u8]),
+    /// Up to a row of the widest displays.
+    Owned([u8; 80], u8),
+}
+
+impl Bytes<'_> {
+    /// Copy up to 80 bytes.
+    fn copy(bytes: &[u8]) -> Self {
+        let mut owned = [0; 80];
+        owned[..bytes.len()].copy_from_slice(bytes);
+        Bytes::Owned(owned, bytes.len() as u8)
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        match self {
+            Bytes::Borrowed(bytes) => bytes,
+            Bytes::Owned(bytes, len) => &bytes[..usize::from(*len)],
+        }
+    }
+}
+
+/// What the driver got back from the last op.
+#[derive(Default)]
+pub(crate) enum Outcome {
+    /// The op went through, or the plan just started.
+    #[default]
+    Done,
+    /// Whether the bytes of an [`Op::Batch`] were written.
+    Sent(bool),
+    /// The byte of an [`Op::Read`].
+    Read(u8),
+    /// The address counter after [`Op::PollReady`], `None` if the busy flag didn't clear in time.
+    Ready(Option<u8>),
+}
+
+/// The next op of a plan, or its output once it's done.
+pub(crate) enum Step<'a, T> {
+    Op(Op<'a>),
+    Done(T),
+}
+
+/// An operation of the driver, stepped through until it's done.
+pub(crate) trait Plan<'a, G> {
+    type Output;
+
+    /// Work out the next op, after the last one went through with `outcome`.
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        outcome: Outcome,
+    ) -> Result<Step<'a, Self::Output>, Error<E>>;
+}
+
+fn op<'a, T, E>(op: Op<'a>) -> Result<Step<'a, T>, Error<E>> {
+    Ok(Step::Op(op))
+}
+
+fn done<'a, T, E>(output: T) -> Result<Step<'a, T>, Error<E>> {
+    Ok(Step::Done(output))
+}
+
+/// Write `byte` with `mode` to `controllers`, waiting as long as the controller takes for it.
+fn send_op<G: Geometry>(state: &State<G>, controllers: u8, byte: u8, mode: Mode) -> Op<'static> {
+    Op::Send {
+        controllers,
+        rs: mode == Mode::Data,
+        byte,
+        wait_us: state.execution_us(byte, mode),
+    }
+}
+
+/// Set the address counter of the controller to `address_counter` again, after a status read
+/// went to a board with RW tied to ground or a read failed partway.
+///
+/// Only boards with a single controller can read, RW would be the enable pin of a second one.
+pub(crate) fn restore_address<G: Geometry>(state: &State<G>) -> Op<'static> {
+    let command = Mode::DDRAMAddr as u8 | (state.address_counter & 0x7f);
+    send_op(state, state.all_controllers(), command, Mode::Cmd)
+}
+
+/// Send a byte to the controllers `mode` goes to, tracking characters in the state.
+pub(crate) struct Send {
+    byte: u8,
+    mode: Mode,
+    stage: SendStage,
+}
+
+enum SendStage {
+    Start,
+    Sent,
+    /// Moving the address counter over the seam of a split row.
+    Seam(SetAddress),
+}
+
+impl Send {
+    pub(crate) fn command(byte: u8) -> Self {
+        Self::new(byte, Mode::Cmd)
+    }
+
+    pub(crate) fn data(byte: u8) -> Self {
+        Self::new(byte, Mode::Data)
+    }
+
+    fn new(byte: u8, mode: Mode) -> Self {
+        Self {
+            byte,
+            mode,
+            stage: SendStage::Start,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Send {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                SendStage::Start => {
+                    self.stage = SendStage::Sent;
+                    let controllers = state.controllers_for(self.mode);
+                    return op(send_op(state, controllers, self.byte, self.mode));
+                }
+                SendStage::Sent => {
+                    if self.mode != Mode::Data {
+                        return done(());
+                    }
+                    let previous = state.address_counter;
+                    state.record_data(self.byte);
+                    match state.seam_address(previous) {
+                        Some(address) => self.stage = SendStage::Seam(SetAddress::new(address)),
+                        None => return done(()),
+                    }
+                }
+                SendStage::Seam(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    state.address_counter = plan.address;
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Set the DDRAM address of the controller holding `address` and let it show the cursor.
+pub(crate) struct SetAddress {
+    address: u8,
+    cursor: Option<ShowCursor>,
+}
+
+impl SetAddress {
+    pub(crate) fn new(address: u8) -> Self {
+        Self {
+            address,
+            cursor: None,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for SetAddress {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        match &mut self.cursor {
+            None => {
+                let controller = state.controller_of(self.address);
+                self.cursor = Some(ShowCursor::new(controller));
+                let command = Mode::DDRAMAddr as u8 | (self.address & 0x7f);
+                op(send_op(state, controller, command, Mode::Cmd))
+            }
+            Some(plan) => {
+                nested!(plan, state, &mut outcome);
+                done(())
+            }
+        }
+    }
+}
+
+/// Move the cursor to `controller` if another controller shows it.
+pub(crate) struct ShowCursor {
+    controller: u8,
+    control: Option<Control>,
+}
+
+impl ShowCursor {
+    pub(crate) fn new(controller: u8) -> Self {
+        Self {
+            controller,
+            control: None,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for ShowCursor {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        let control = match &mut self.control {
+            Some(control) => control,
+            None if self.controller == state.cursor_controller || !state.cursor_visible() => {
+                return done(())
+            }
+            None => self.control.insert(Control::to(self.controller)),
+        };
+        nested!(control, state, &mut outcome);
+        done(())
+    }
+}
+
+/// Send the display control to all controllers, with the cursor only shown by one of them.
+pub(crate) struct Control {
+    stage: ControlStage,
+}
+
+enum ControlStage {
+    Start(Option<u8>),
+    /// The other controllers still need the display control without the cursor.
+    Others {
+        controller: u8,
+        control: u8,
+    },
+    Sent(u8),
+}
+
+impl Control {
+    /// With the cursor shown by `controller`.
+    pub(crate) fn to(controller: u8) -> Self {
+        Self {
+            stage: ControlStage::Start(Some(controller)),
+        }
+    }
+
+    /// With the cursor shown by the controller holding it, after the display or cursor settings
+    /// changed.
+    pub(crate) fn update() -> Self {
+        Self {
+            stage: ControlStage::Start(None),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Control {
+    type Output = ();
+
+    fn step<E>(&mut self, state: &mut State<G>, _: Outcome) -> Result<Step<'a, ()>, Error<E>> {
+        match self.stage {
+            ControlStage::Start(controller) => {
+                let controller =
+                    controller.unwrap_or_else(|| state.controller_of(state.address_counter));
+                let control = state.display_control();
+                let all = state.all_controllers();
+                if controller == all || !state.cursor_visible() {
+                    self.stage = ControlStage::Sent(controller);
+                    return op(send_op(state, all, control, Mode::Cmd));
+                }
+                self.stage = ControlStage::Others {
+                    controller,
+                    control,
+                };
+                op(send_op(state, controller, control, Mode::Cmd))
+            }
+            ControlStage::Others {
+                controller,
+                control,
+            } => {
+                let cursor = DisplayControl::CursorOn as u8 | DisplayControl::CursorBlink as u8;
+                let others = state.all_controllers() & !controller;
+                self.stage = ControlStage::Sent(controller);
+                op(send_op(state, others, control & !cursor, Mode::Cmd))
+            }
+            ControlStage::Sent(controller) => {
+                state.cursor_controller = controller;
+                done(())
+            }
+        }
+    }
+}
+
+/// Move the cursor, see `set_cursor`, `set_cursor_ddram` and `restore_cursor`.
+pub(crate) struct MoveTo {
+    stage: MoveStage,
+}
+
+enum MoveStage {
+    Cursor(u8, u8),
+    Ddram(u8, u8),
+    Saved,
+    Moving {
+        address: u8,
+        offscreen: bool,
+        set: SetAddress,
+    },
+}
+
+impl MoveTo {
+    /// To (row, col) on the display.
+    pub(crate) fn cursor(row: u8, col: u8) -> Self {
+        Self {
+            stage: MoveStage::Cursor(row, col),
+        }
+    }
+
+    /// To (row, col), allowing columns up to the end of the DDRAM of the row.
+    pub(crate) fn ddram(row: u8, col: u8) -> Self {
+        Self {
+            stage: MoveStage::Ddram(row, col),
+        }
+    }
+
+    /// Back to the saved position, see `save_cursor`.
+    pub(crate) fn saved() -> Self {
+        Self {
+            stage: MoveStage::Saved,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for MoveTo {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        let (address, offscreen) = match self.stage {
+            MoveStage::Cursor(row, col) => {
+                state.check_position(row, col)?;
+                (state.address_of(row, col), false)
+            }
+            MoveStage::Ddram(row, col) => (state.ddram_address(row, col)?, true),
+            MoveStage::Saved => (
+                state.saved_cursor.take().ok_or(Error::NoSavedCursor)?,
+                false,
+            ),
+            MoveStage::Moving {
+                address,
+                offscreen,
+                ref mut set,
+            } => {
+                nested!(set, state, &mut outcome);
+                state.set_address_counter(address);
+                state.offscreen = offscreen;
+                return done(());
+            }
+        };
+        self.stage = MoveStage::Moving {
+            address,
+            offscreen,
+            set: SetAddress::new(address),
+        };
+        self.step(state, outcome)
+    }
+}
+
+/// Send bytes in one go if the interface can, one after another otherwise, see `write_bytes`.
+pub(crate) struct Batch<'a> {
+    mode: Mode,
+    bytes: Bytes<'a>,
+    stage: BatchStage,
+}
+
+enum BatchStage {
+    Start,
+    Sent,
+    Waited,
+    /// Sending the byte at the index on its own.
+    Each(usize, Send),
+}
+
+impl<'a> Batch<'a> {
+    pub(crate) fn data(bytes: Bytes<'a>) -> Self {
+        Self::new(bytes, Mode::Data)
+    }
+
+    /// Instructions that execute as fast as a character.
+    pub(crate) fn commands(bytes: Bytes<'a>) -> Self {
+        Self::new(bytes, Mode::Cmd)
+    }
+
+    fn new(bytes: Bytes<'a>, mode: Mode) -> Self {
+        Self {
+            mode,
+            bytes,
+            stage: BatchStage::Start,
+        }
+    }
+
+    fn each(&mut self, index: usize) -> bool {
+        match self.bytes.as_slice().get(index) {
+            Some(&byte) => {
+                self.stage = BatchStage::Each(index, Send::new(byte, self.mode));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'a, 'b: 'a, G: Geometry> Plan<'a, G> for Batch<'b> {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                BatchStage::Start => {
+                    if self.bytes.as_slice().is_empty() {
+                        return done(());
+                    }
+                    // A split row needs the address set at the seam.
+                    if self.mode == Mode::Data && state.split_column().is_some() {
+                        self.each(0);
+                        continue;
+                    }
+                    self.stage = BatchStage::Sent;
+                    return op(Op::Batch {
+                        controllers: state.controllers_for(self.mode),
+                        rs: self.mode == Mode::Data,
+                        bytes: self.bytes,
+                    });
+                }
+                BatchStage::Sent => {
+                    if !matches!(outcome, Outcome::Sent(true)) {
+                        self.each(0);
+                        continue;
+                    }
+                    let bytes = self.bytes.as_slice();
+                    if self.mode == Mode::Data {
+                        for &byte in bytes {
+                            state.record_data(byte);
+                        }
+                    }
+                    let last = bytes[bytes.len() - 1];
+                    self.stage = BatchStage::Waited;
+                    return op(Op::Wait(state.execution_us(last, self.mode)));
+                }
+                BatchStage::Waited => return done(()),
+                BatchStage::Each(index, plan) => {
+                    nested!(plan, state, &mut outcome);
+                    let next = *index + 1;
+                    if !self.each(next) {
+                        return done(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Write the characters of a string with their byte index, returning the number of characters
+/// actually written, see `write_str`.
+pub(crate) struct Text<I> {
+    chars: iter::Enumerate<I>,
+    printed: u8,
+    // Plain characters on a row go out together, see `write_bytes`.
+    batch: [u8; BATCH_LEN],
+    pending: usize,
+    stage: TextStage,
+}
+
+enum TextStage {
+    Next,
+    Flush(Batch<'static>, After),
+    /// Writing the character at the index of the string on its own.
+    Char(usize, PutChar),
+}
+
+/// What comes after flushing the batched characters.
+#[derive(Clone, Copy)]
+enum After {
+    Next,
+    Char { i: usize, index: usize, c: char },
+    End,
+}
+
+impl<I: Iterator<Item = (usize, char)>> Text<I> {
+    pub(crate) fn new(chars: I) -> Self {
+        Self {
+            chars: chars.enumerate(),
+            printed: 0,
+            batch: [0; BATCH_LEN],
+            pending: 0,
+            stage: TextStage::Next,
+        }
+    }
+
+    fn flush(&mut self, after: After) {
+        let bytes = Bytes::copy(&self.batch[..self.pending]);
+        self.pending = 0;
+        self.stage = TextStage::Flush(Batch::data(bytes), after);
+    }
+}
+
+/// Write `padding` spaces and the ASCII `digits` of a number like the characters of a string.
+pub(crate) fn number(padding: u8, digits: &[u8]) -> Text<impl Iterator<Item = (usize, char)> + '_> {
+    let spaces = iter::repeat_n(' ', padding.into());
+    let chars = spaces.chain(digits.iter().map(|&digit| char::from(digit)));
+    Text::new(chars.enumerate())
+}
+
+impl<'a, I, G> Plan<'a, G> for Text<I>
+where
+    I: Iterator<Item = (usize, char)>,
+    G: Geometry,
+{
+    type Output = u8;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, u8>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                TextStage::Next => match self.chars.next() {
+                    None => self.flush(After::End),
+                    Some((i, (index, c))) => match state.batchable(c, self.pending) {
+                        Some(code) => {
+                            self.batch[self.pending] = code;
+                            self.pending += 1;
+                            self.printed = self.printed.saturating_add(1);
+                            if self.pending == BATCH_LEN {
+                                self.flush(After::Next);
+                            }
+                        }
+                        None => self.flush(After::Char { i, index, c }),
+                    },
+                },
+                TextStage::Flush(plan, after) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = match *after {
+                        After::Next => TextStage::Next,
+                        After::Char { i, index, c } => TextStage::Char(i, PutChar::new(c, index)),
+                        After::End => return done(self.printed),
+                    };
+                }
+                TextStage::Char(i, plan) => match plan.step(state, core::mem::take(&mut outcome)) {
+                    Ok(Step::Op(op)) => return Ok(Step::Op(op)),
+                    Ok(Step::Done(printed)) => {
+                        if printed {
+                            self.printed = self.printed.saturating_add(1);
+                        }
+                        self.stage = TextStage::Next;
+                    }
+                    Err(Error::RowOverflow { .. }) => {
+                        return Err(Error::RowOverflow { written: *i })
+                    }
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+    }
+}
+
+/// Write a single character at byte `index` of a string, returning if it was actually written.
+pub(crate) struct PutChar {
+    stage: PutStage,
+}
+
+enum PutStage {
+    Start(char, usize),
+    Newline(Newline),
+    Return(MoveTo),
+    Tab(Spaces),
+    Room {
+        byte: u8,
+        mark: Option<u8>,
+        room: MakeRoom,
+    },
+    Byte {
+        mark: Option<u8>,
+        send: Send,
+    },
+    /// The second code of a character written as two.
+    MarkRoom {
+        mark: u8,
+        room: MakeRoom,
+    },
+    Mark(Send),
+}
+
+impl PutChar {
+    pub(crate) fn new(c: char, index: usize) -> Self {
+        Self {
+            stage: PutStage::Start(c, index),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for PutChar {
+    type Output = bool;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, bool>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                PutStage::Start(c, index) => {
+                    self.stage = match *c {
+                        '\n' => PutStage::Newline(Newline::new()),
+                        '\r' if !state.clipped => {
+                            PutStage::Return(MoveTo::cursor(state.get_cursor().0, 0))
+                        }
+                        _ if state.clipped => return done(false),
+                        '\t' => PutStage::Tab(Spaces::to_tab_stop()),
+                        c => {
+                            let (byte, mark) = state.map_char(c, *index)?;
+                            PutStage::Room {
+                                byte,
+                                mark,
+                                room: MakeRoom::new(),
+                            }
+                        }
+                    };
+                }
+                PutStage::Newline(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(false);
+                }
+                PutStage::Return(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(false);
+                }
+                PutStage::Tab(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(true);
+                }
+                PutStage::Room { byte, mark, room } => {
+                    if !nested!(room, state, &mut outcome) {
+                        return done(false);
+                    }
+                    self.stage = PutStage::Byte {
+                        mark: *mark,
+                        send: Send::data(*byte),
+                    };
+                }
+                PutStage::Byte { mark, send } => {
+                    nested!(send, state, &mut outcome);
+                    match *mark {
+                        Some(mark) => {
+                            self.stage = PutStage::MarkRoom {
+                                mark,
+                                room: MakeRoom::new(),
+                            }
+                        }
+                        None => return done(true),
+                    }
+                }
+                PutStage::MarkRoom { mark, room } => {
+                    if !nested!(room, state, &mut outcome) {
+                        return done(true);
+                    }
+                    self.stage = PutStage::Mark(Send::data(*mark));
+                }
+                PutStage::Mark(send) => {
+                    nested!(send, state, &mut outcome);
+                    return done(true);
+                }
+            }
+        }
+    }
+}
+
+/// Write spaces, a given number of them or up to the next tab stop.
+pub(crate) struct Spaces {
+    left: Option<u8>,
+    send: Option<Send>,
+}
+
+impl Spaces {
+    fn new(count: u8) -> Self {
+        Self {
+            left: Some(count),
+            send: None,
+        }
+    }
+
+    /// Up to the next tab stop, but not past the end of the row.
+    fn to_tab_stop() -> Self {
+        Self {
+            left: None,
+            send: None,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Spaces {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        if let Some(send) = &mut self.send {
+            nested!(send, state, &mut outcome);
+        }
+        let left = match self.left {
+            Some(left) => left,
+            None => state.next_tab_stop().saturating_sub(state.get_cursor().1),
+        };
+        if left == 0 {
+            return done(());
+        }
+        self.left = Some(left - 1);
+        self.send.insert(Send::data(b' ')).step(state, outcome)
+    }
+}
+
+/// Apply the [`OverflowPolicy`] if the cursor is past the end of the row, returning if the next
+/// character can be written.
+pub(crate) struct MakeRoom {
+    overflow: Option<Overflow>,
+}
+
+impl MakeRoom {
+    fn new() -> Self {
+        Self { overflow: None }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for MakeRoom {
+    type Output = bool;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        outcome: Outcome,
+    ) -> Result<Step<'a, bool>, Error<E>> {
+        let overflow = match &mut self.overflow {
+            Some(overflow) => overflow,
+            None => {
+                let (row, col) = state.get_cursor();
+                if col < state.columns() || !state.writes_forward() || state.offscreen {
+                    return done(true);
+                }
+                self.overflow.insert(Overflow::new(row))
+            }
+        };
+        overflow.step(state, outcome)
+    }
+}
+
+/// Leave the full `row` according to the [`OverflowPolicy`], returning if writing can continue.
+pub(crate) struct Overflow {
+    row: u8,
+    stage: OverflowStage,
+}
+
+enum OverflowStage {
+    Start,
+    Wrap(Newline),
+    Scroll(ScrollUp),
+    Next(MoveTo),
+}
+
+impl Overflow {
+    fn new(row: u8) -> Self {
+        Self {
+            row,
+            stage: OverflowStage::Start,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Overflow {
+    type Output = bool;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, bool>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                OverflowStage::Start => {
+                    self.stage = match state.config.overflow_policy {
+                        OverflowPolicy::Truncate => return done(false),
+                        OverflowPolicy::WrapRow => OverflowStage::Wrap(Newline::new()),
+                        OverflowPolicy::Scroll if self.row + 1 == state.rows() => {
+                            OverflowStage::Scroll(ScrollUp::new())
+                        }
+                        OverflowPolicy::Scroll => {
+                            OverflowStage::Next(MoveTo::cursor(self.row + 1, 0))
+                        }
+                        OverflowPolicy::Error => return Err(Error::RowOverflow { written: 0 }),
+                    };
+                }
+                OverflowStage::Wrap(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(!state.clipped);
+                }
+                OverflowStage::Scroll(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(true);
+                }
+                OverflowStage::Next(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(true);
+                }
+            }
+        }
+    }
+}
+
+/// Move the cursor to the start of the next row, on the last row according to the
+/// [`NewlineMode`].
+pub(crate) struct Newline {
+    stage: NewlineStage,
+}
+
+enum NewlineStage {
+    Start,
+    Move(MoveTo),
+    Scroll(ScrollUp),
+}
+
+impl Newline {
+    fn new() -> Self {
+        Self {
+            stage: NewlineStage::Start,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Newline {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                NewlineStage::Start => {
+                    let row = state.get_cursor().0;
+                    self.stage = if row + 1 < state.rows() {
+                        NewlineStage::Move(MoveTo::cursor(row + 1, 0))
+                    } else {
+                        match state.config.newline_mode {
+                            NewlineMode::Clip => {
+                                state.clipped = true;
+                                return done(());
+                            }
+                            NewlineMode::Wrap => NewlineStage::Move(MoveTo::cursor(0, 0)),
+                            NewlineMode::Scroll => NewlineStage::Scroll(ScrollUp::new()),
+                        }
+                    };
+                }
+                NewlineStage::Move(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+                NewlineStage::Scroll(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Move the contents of each row up by one row and clear the last row, then move the cursor to
+/// the start of the last row.
+///
+/// Rows are only rewritten if their contents change.
+pub(crate) struct ScrollUp {
+    row: u8,
+    /// Contents of the row below the one being rewritten, and the next column to write.
+    below: [u8; 80],
+    col: u8,
+    stage: ScrollStage,
+}
+
+enum ScrollStage {
+    Compare,
+    Move(MoveTo),
+    Rewrite(Send),
+    Clear(Blank),
+    Home(MoveTo),
+}
+
+impl ScrollUp {
+    fn new() -> Self {
+        Self {
+            row: 0,
+            below: [b' '; 80],
+            col: 0,
+            stage: ScrollStage::Compare,
+        }
+    }
+
+    fn rewrite_next(&mut self, columns: u8) {
+        if self.col < columns {
+            self.stage = ScrollStage::Rewrite(Send::data(self.below[usize::from(self.col)]));
+            self.col += 1;
+        } else {
+            self.row += 1;
+            self.stage = ScrollStage::Compare;
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for ScrollUp {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                ScrollStage::Compare => {
+                    let last = state.rows() - 1;
+                    if self.row < last {
+                        self.below = state.row_contents(self.row + 1);
+                        if state.row_contents(self.row) != self.below {
+                            self.stage = ScrollStage::Move(MoveTo::cursor(self.row, 0));
+                        } else {
+                            self.row += 1;
+                        }
+                    } else if state.row_contents(last).iter().any(|&byte| byte != b' ') {
+                        self.stage = ScrollStage::Clear(Blank::row(last));
+                    } else {
+                        self.stage = ScrollStage::Home(MoveTo::cursor(last, 0));
+                    }
+                }
+                ScrollStage::Move(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.col = 0;
+                    self.rewrite_next(state.columns());
+                }
+                ScrollStage::Rewrite(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.rewrite_next(state.columns());
+                }
+                ScrollStage::Clear(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+                ScrollStage::Home(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Clear characters with spaces and move the cursor to the first of them, see `clear_region`.
+pub(crate) struct Blank {
+    stage: BlankStage,
+}
+
+enum BlankStage {
+    Row(u8),
+    Region {
+        row: u8,
+        col: u8,
+        len: u8,
+    },
+    ToEndOfLine,
+    Move {
+        row: u8,
+        col: u8,
+        len: u8,
+        to: MoveTo,
+    },
+    Spaces {
+        row: u8,
+        col: u8,
+        spaces: Spaces,
+    },
+    Back(MoveTo),
+}
+
+impl Blank {
+    pub(crate) fn row(row: u8) -> Self {
+        Self {
+            stage: BlankStage::Row(row),
+        }
+    }
+
+    pub(crate) fn region(row: u8, col: u8, len: u8) -> Self {
+        Self {
+            stage: BlankStage::Region { row, col, len },
+        }
+    }
+
+    pub(crate) fn to_end_of_line() -> Self {
+        Self {
+            stage: BlankStage::ToEndOfLine,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Blank {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            let (row, col, len) = match &mut self.stage {
+                BlankStage::Row(row) => (*row, 0, state.columns()),
+                BlankStage::Region { row, col, len } => {
+                    state.check_position(*row, *col)?;
+                    let available = state.columns() - *col;
+                    let len = if *len <= available {
+                        *len
+                    } else if state.config.clamp_regions {
+                        available
+                    } else {
+                        return Err(Error::InvalidColumn);
+                    };
+                    (*row, *col, len)
+                }
+                BlankStage::ToEndOfLine => {
+                    let (row, col) = state.get_cursor();
+                    if col >= state.columns() {
+                        return done(());
+                    }
+                    self.stage = BlankStage::Region {
+                        row,
+                        col,
+                        len: state.columns() - col,
+                    };
+                    continue;
+                }
+                BlankStage::Move { row, col, len, to } => {
+                    nested!(to, state, &mut outcome);
+                    self.stage = BlankStage::Spaces {
+                        row: *row,
+                        col: *col,
+                        spaces: Spaces::new(*len),
+                    };
+                    continue;
+                }
+                BlankStage::Spaces { row, col, spaces } => {
+                    nested!(spaces, state, &mut outcome);
+                    self.stage = BlankStage::Back(MoveTo::cursor(*row, *col));
+                    continue;
+                }
+                BlankStage::Back(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            };
+            self.stage = BlankStage::Move {
+                row,
+                col,
+                len,
+                to: MoveTo::cursor(row, col),
+            };
+        }
+    }
+}
+
+/// Where a string goes on its row, see `write_line`.
+#[derive(Clone, Copy)]
+pub(crate) enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Write a string to a row, filling the rest of the row with spaces, see `write_line`.
+pub(crate) struct Row<'s> {
+    row: u8,
+    text: &'s str,
+    align: Align,
+    stage: RowStage,
+}
+
+enum RowStage {
+    Start,
+    Move(MoveTo, Bytes<'static>),
+    Write(Batch<'static>),
+}
+
+impl<'s> Row<'s> {
+    pub(crate) fn new(row: u8, text: &'s str, align: Align) -> Self {
+        Self {
+            row,
+            text,
+            align,
+            stage: RowStage::Start,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Row<'_> {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                RowStage::Start => {
+                    let (len, width) = state.fitting_len(self.text);
+                    let pad_left = match self.align {
+                        Align::Left => 0,
+                        Align::Center => (state.columns() - width) / 2,
+                        Align::Right => state.columns() - width,
+                    };
+                    state.check_position(self.row, 0)?;
+                    let contents = state.padded_row(self.text, pad_left, len)?;
+                    let contents = Bytes::copy(&contents[..usize::from(state.columns())]);
+                    self.stage = RowStage::Move(MoveTo::cursor(self.row, 0), contents);
+                }
+                RowStage::Move(plan, contents) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = RowStage::Write(Batch::data(*contents));
+                }
+                RowStage::Write(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Write text from the start of a row, breaking lines at whitespace, returning the number of rows
+/// written to, see `write_wrapped`.
+pub(crate) struct Wrapped<'s> {
+    text: &'s str,
+    words: SplitWhitespace<'s>,
+    /// Byte index of the current word and its characters left to write.
+    word: (usize, CharIndices<'s>),
+    row: u8,
+    col: u8,
+    rows: u8,
+    written: usize,
+    stage: WrapStage,
+}
+
+enum WrapStage {
+    Start(MoveTo),
+    Word,
+    Char,
+    /// Continuing on the next row, then with the character at the byte index of the word or the
+    /// rest of the word.
+    Break(BreakLine, Option<(usize, char)>),
+    Put(PutChar),
+}
+
+impl<'s> Wrapped<'s> {
+    pub(crate) fn new(start_row: u8, text: &'s str) -> Self {
+        Self {
+            text,
+            words: text.split_whitespace(),
+            word: (0, "".char_indices()),
+            row: start_row,
+            col: 0,
+            rows: 1,
+            written: 0,
+            stage: WrapStage::Start(MoveTo::cursor(start_row, 0)),
+        }
+    }
+
+    fn put(&mut self, offset: usize, c: char, width: u8) {
+        self.col += width;
+        self.written += 1;
+        self.stage = WrapStage::Put(PutChar::new(c, self.word.0 + offset));
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Wrapped<'_> {
+    type Output = u8;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, u8>, Error<E>> {
+        let columns = state.columns();
+        loop {
+            match &mut self.stage {
+                WrapStage::Start(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = WrapStage::Word;
+                }
+                WrapStage::Word => {
+                    let word = match self.words.next() {
+                        Some(word) => word,
+                        None => return done(self.rows),
+                    };
+                    let start = word.as_ptr() as usize - self.text.as_ptr() as usize;
+                    let width: usize = word.chars().map(|c| state.char_width(c) as usize).sum();
+                    self.word = (start, word.char_indices());
+                    if self.col > 0 && self.col as usize + 1 + width > columns as usize {
+                        self.stage = WrapStage::Break(BreakLine::new(self.row, self.written), None);
+                    } else if self.col > 0 {
+                        self.col += 1;
+                        self.stage = WrapStage::Put(PutChar::new(' ', start - 1));
+                    } else {
+                        self.stage = WrapStage::Char;
+                    }
+                }
+                WrapStage::Char => match self.word.1.next() {
+                    None => self.stage = WrapStage::Word,
+                    Some((offset, c)) => {
+                        let width = state.char_width(c);
+                        if self.col + width > columns {
+                            let plan = BreakLine::new(self.row, self.written);
+                            self.stage = WrapStage::Break(plan, Some((offset, c)));
+                        } else {
+                            self.put(offset, c, width);
+                        }
+                    }
+                },
+                WrapStage::Break(plan, then) => {
+                    if !nested!(plan, state, &mut outcome) {
+                        return done(self.rows);
+                    }
+                    let then = *then;
+                    self.row = state.get_cursor().0;
+                    self.col = 0;
+                    self.rows = self.rows.saturating_add(1);
+                    match then {
+                        Some((offset, c)) => self.put(offset, c, state.char_width(c)),
+                        None => self.stage = WrapStage::Char,
+                    }
+                }
+                WrapStage::Put(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = WrapStage::Char;
+                }
+            }
+        }
+    }
+}
+
+/// Continue at the start of the row after `row`, returning if writing can continue.
+pub(crate) struct BreakLine {
+    written: usize,
+    stage: BreakStage,
+}
+
+enum BreakStage {
+    Start(u8),
+    Next(MoveTo),
+    Overflow(Overflow),
+}
+
+impl BreakLine {
+    fn new(row: u8, written: usize) -> Self {
+        Self {
+            written,
+            stage: BreakStage::Start(row),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for BreakLine {
+    type Output = bool;
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, bool>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                BreakStage::Start(row) => {
+                    let row = *row;
+                    self.stage = match row + 1 < state.rows() {
+                        true => BreakStage::Next(MoveTo::cursor(row + 1, 0)),
+                        false => BreakStage::Overflow(Overflow::new(row)),
+                    };
+                }
+                BreakStage::Next(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(true);
+                }
+                BreakStage::Overflow(plan) => {
+                    return match plan.step(state, core::mem::take(&mut outcome)) {
+                        Err(Error::RowOverflow { .. }) => Err(Error::RowOverflow {
+                            written: self.written,
+                        }),
+                        result => result,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Set up the display, after waiting for it to power on if `power_on` is `true` and after
+/// bringing it into a known state with three function sets if `sync` is `true`.
+pub(crate) struct Init {
+    power_on: bool,
+    sync: bool,
+    syncs: u8,
+    stage: InitStage,
+}
+
+enum InitStage {
+    Start,
+    Interface,
+    Select,
+    Backlight,
+    Settle,
+    Sync,
+    SyncNibble,
+    SyncWait,
+    Switch,
+    SwitchNibble,
+    SwitchWait,
+    Ready,
+    FunctionSet(Send),
+    CharacterMode(Send),
+    Control(Control),
+    Clear(Send),
+    EntryMode(Send),
+    Home(Home),
+}
+
+impl Init {
+    pub(crate) fn new(power_on: bool, sync: bool) -> Self {
+        Self {
+            power_on,
+            sync,
+            syncs: 0,
+            stage: InitStage::Start,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Init {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        let timings = state.config.timings;
+        let rs0010 = state.config.controller == Controller::Rs0010;
+        loop {
+            match &mut self.stage {
+                InitStage::Start => {
+                    state.check_config()?;
+                    // The busy flag can't be read before the controller is set to the width of
+                    // the bus.
+                    state.poll_busy_flag = false;
+                    self.stage = InitStage::Interface;
+                    if self.power_on && !rs0010 {
+                        // Initial delay to wait for init after power on.
+                        return op(Op::Delay(timings.power_on_us));
+                    }
+                }
+                InitStage::Interface => {
+                    self.stage = InitStage::Select;
+                    return op(Op::Init);
+                }
+                InitStage::Select => {
+                    self.stage = InitStage::Backlight;
+                    return op(Op::Select(state.all_controllers()));
+                }
+                InitStage::Backlight => {
+                    self.stage = InitStage::Settle;
+                    return op(Op::Backlight(state.backlight_on()));
+                }
+                InitStage::Settle => {
+                    // Init with 8 bit mode, three times to get in sync whatever state the
+                    // controller is in. The RS0010 loses track of the nibbles with more than one.
+                    self.syncs = match (self.sync, rs0010) {
+                        (false, _) => 0,
+                        (true, true) => 1,
+                        (true, false) => 3,
+                    };
+                    self.stage = InitStage::Sync;
+                    return op(Op::Delay(timings.backlight_us));
+                }
+                InitStage::Sync => {
+                    if self.syncs == 0 {
+                        self.stage = match self.sync && state.bit_mode == BitMode::Bit4 {
+                            true => InitStage::Switch,
+                            false => InitStage::Ready,
+                        };
+                        continue;
+                    }
+                    self.syncs -= 1;
+                    // Only the upper four bits in 4 bit mode.
+                    let mode_8bit = Mode::FunctionSet as u8 | BitMode::Bit8 as u8;
+                    return match state.bit_mode {
+                        BitMode::Bit4 => {
+                            self.stage = InitStage::SyncNibble;
+                            op(Op::Nibble {
+                                rs: false,
+                                nibble: mode_8bit >> 4,
+                            })
+                        }
+                        BitMode::Bit8 => {
+                            self.stage = InitStage::SyncWait;
+                            op(Op::Write {
+                                rs: false,
+                                byte: mode_8bit,
+                            })
+                        }
+                    };
+                }
+                InitStage::SyncNibble => {
+                    self.stage = InitStage::SyncWait;
+                    return op(Op::Delay(timings.nibble_us));
+                }
+                InitStage::SyncWait => {
+                    self.stage = InitStage::Sync;
+                    return op(Op::Delay(timings.function_set_us));
+                }
+                InitStage::Switch => {
+                    // Switch to 4 bit mode
+                    let mode_4bit = Mode::FunctionSet as u8 | BitMode::Bit4 as u8;
+                    self.stage = InitStage::SwitchNibble;
+                    return op(Op::Nibble {
+                        rs: false,
+                        nibble: mode_4bit >> 4,
+                    });
+                }
+                InitStage::SwitchNibble => {
+                    self.stage = match rs0010 {
+                        true => InitStage::SwitchWait,
+                        false => InitStage::Ready,
+                    };
+                    return op(Op::Delay(timings.nibble_us));
+                }
+                InitStage::SwitchWait => {
+                    self.stage = InitStage::Ready;
+                    return op(Op::Delay(timings.function_set_us));
+                }
+                InitStage::Ready => {
+                    if !rs0010 {
+                        state.poll_busy_flag = state.config.busy_flag_polling;
+                    }
+                    self.stage = InitStage::FunctionSet(Send::command(state.function_set()));
+                }
+                InitStage::FunctionSet(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = match rs0010 {
+                        true => InitStage::CharacterMode(Send::command(RS0010_CHARACTER_MODE)),
+                        false => InitStage::Control(Control::update()),
+                    };
+                }
+                InitStage::CharacterMode(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    // The busy flag isn't valid before the internal power is on.
+                    state.poll_busy_flag = state.config.busy_flag_polling;
+                    self.stage = InitStage::Control(Control::update());
+                }
+                InitStage::Control(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    let clear = Mode::Cmd as u8 | Commands::Clear as u8;
+                    self.stage = InitStage::Clear(Send::command(clear));
+                }
+                InitStage::Clear(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    state.clear_ddram();
+                    // Entry right: shifting cursor moves to right
+                    self.stage = InitStage::EntryMode(Send::command(state.entry_mode()));
+                }
+                InitStage::EntryMode(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = InitStage::Home(Home::new());
+                }
+                InitStage::Home(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Return the cursor and the display to their start, see `return_home`, after clearing the
+/// display with `clear`.
+pub(crate) struct Home {
+    clear: bool,
+    stage: HomeStage,
+}
+
+enum HomeStage {
+    Command(Send),
+    Cursor(ShowCursor),
+    EntryMode(Send),
+}
+
+impl Home {
+    pub(crate) fn new() -> Self {
+        Self {
+            clear: false,
+            stage: HomeStage::Command(Send::command(Commands::ReturnHome as u8)),
+        }
+    }
+
+    pub(crate) fn clear() -> Self {
+        Self {
+            clear: true,
+            stage: HomeStage::Command(Send::command(Commands::Clear as u8)),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Home {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                HomeStage::Command(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    if self.clear {
+                        state.clear_ddram();
+                    }
+                    state.home();
+                    self.stage = HomeStage::Cursor(ShowCursor::new(state.controller_of(0)));
+                }
+                HomeStage::Cursor(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    // Clearing resets the entry mode to left to right
+                    if !self.clear || !matches!(state.cursor_dir, CursorMoveDir::Right) {
+                        return done(());
+                    }
+                    self.stage = HomeStage::EntryMode(Send::command(state.entry_mode()));
+                }
+                HomeStage::EntryMode(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Write `rows` to CGRAM starting at `address`, leaving the controller at CGRAM, see
+/// `write_cgram`.
+pub(crate) struct Upload<'r> {
+    rows: iter::Copied<core::slice::Iter<'r, u8>>,
+    command: Option<Send>,
+}
+
+impl<'r> Upload<'r> {
+    pub(crate) fn new(address: u8, rows: &'r [u8]) -> Self {
+        Self {
+            rows: rows.iter().copied(),
+            command: Some(Send::command(Mode::CGRAMAddr as u8 | address)),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Upload<'_> {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        if let Some(command) = &mut self.command {
+            nested!(command, state, &mut outcome);
+            self.command = None;
+        }
+        match self.rows.next() {
+            Some(row) => op(send_op(
+                state,
+                state.all_controllers(),
+                row & 0x1f,
+                Mode::Data,
+            )),
+            None => done(()),
+        }
+    }
+}
+
+/// Set the RAM address with a command and read from there, then restore the DDRAM address.
+pub(crate) struct ReadRam<'b> {
+    buf: &'b mut [u8],
+    /// Bits of the bytes kept.
+    mask: u8,
+    next: usize,
+    stage: ReadStage,
+}
+
+enum ReadStage {
+    Start(u8),
+    Polled(u8),
+    Address(Send),
+    Next,
+    Read,
+    Restore(SetAddress),
+}
+
+impl<'b> ReadRam<'b> {
+    pub(crate) fn ddram(address: u8, buf: &'b mut [u8]) -> Self {
+        Self::new(Mode::DDRAMAddr as u8 | address, buf, 0xff)
+    }
+
+    /// Only the lower five bits of each row are pixels.
+    pub(crate) fn cgram(address: u8, buf: &'b mut [u8]) -> Self {
+        Self::new(Mode::CGRAMAddr as u8 | address, buf, 0x1f)
+    }
+
+    fn new(command: u8, buf: &'b mut [u8], mask: u8) -> Self {
+        Self {
+            buf,
+            mask,
+            next: 0,
+            stage: ReadStage::Start(command),
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for ReadRam<'_> {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                ReadStage::Start(command) => {
+                    self.stage = ReadStage::Polled(*command);
+                    return op(Op::PollReady);
+                }
+                ReadStage::Polled(command) => {
+                    if !matches!(outcome, Outcome::Ready(Some(_))) {
+                        return Err(Error::ReadUnavailable);
+                    }
+                    self.stage = ReadStage::Address(Send::command(*command));
+                    state.address_moved = true;
+                    outcome = Outcome::Done;
+                }
+                ReadStage::Address(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = ReadStage::Next;
+                }
+                ReadStage::Next => {
+                    if self.next == self.buf.len() {
+                        state.address_moved = false;
+                        let address = state.address_counter;
+                        self.stage = ReadStage::Restore(SetAddress::new(address));
+                        continue;
+                    }
+                    self.stage = ReadStage::Read;
+                    return op(Op::Read { rs: true });
+                }
+                ReadStage::Read => {
+                    if let Outcome::Read(byte) = outcome {
+                        self.buf[self.next] = byte & self.mask;
+                    }
+                    self.next += 1;
+                    self.stage = ReadStage::Next;
+                    return op(Op::Wait(state.config.timings.command_us));
+                }
+                ReadStage::Restore(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Read the address counter from the controller.
+pub(crate) struct ReadAddress {
+    polled: bool,
+}
+
+impl ReadAddress {
+    pub(crate) fn new() -> Self {
+        Self { polled: false }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for ReadAddress {
+    type Output = u8;
+
+    fn step<E>(&mut self, _: &mut State<G>, outcome: Outcome) -> Result<Step<'a, u8>, Error<E>> {
+        if !self.polled {
+            self.polled = true;
+            return op(Op::PollReady);
+        }
+        match outcome {
+            Outcome::Ready(Some(address)) => done(address),
+            _ => Err(Error::ReadUnavailable),
+        }
+    }
+}
+
+/// Set the backlight, or toggle it without one, with a single expander write leaving EN and the
+/// data lines low so the controller isn't affected.
+pub(crate) struct SetBacklight {
+    backlight: Option<Backlight>,
+    sent: bool,
+}
+
+impl SetBacklight {
+    pub(crate) fn new(backlight: Backlight) -> Self {
+        Self {
+            backlight: Some(backlight),
+            sent: false,
+        }
+    }
+
+    pub(crate) fn toggle() -> Self {
+        Self {
+            backlight: None,
+            sent: false,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for SetBacklight {
+    type Output = ();
+
+    fn step<E>(&mut self, state: &mut State<G>, _: Outcome) -> Result<Step<'a, ()>, Error<E>> {
+        if self.sent {
+            return done(());
+        }
+        let backlight = self.backlight.unwrap_or(match state.config.backlight {
+            Backlight::On => Backlight::Off,
+            Backlight::Off => Backlight::On,
+        });
+        state.config.backlight = backlight;
+        self.sent = true;
+        op(Op::Backlight(backlight == Backlight::On))
+    }
+}
+
+/// Flash the backlight, see `flash_backlight`.
+pub(crate) struct FlashBacklight {
+    times: u8,
+    on_ms: u32,
+    off_ms: u32,
+    /// Index of the next op, four for each flash.
+    next: u32,
+}
+
+impl FlashBacklight {
+    pub(crate) fn new(times: u8, on_ms: u32, off_ms: u32) -> Self {
+        Self {
+            times,
+            on_ms,
+            off_ms,
+            next: 0,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for FlashBacklight {
+    type Output = ();
+
+    fn step<E>(&mut self, state: &mut State<G>, _: Outcome) -> Result<Step<'a, ()>, Error<E>> {
+        let flashes = u32::from(self.times) * 4;
+        let next = self.next;
+        self.next += 1;
+        if next == flashes {
+            return op(Op::Backlight(state.backlight_on()));
+        }
+        if next > flashes {
+            return done(());
+        }
+        op(match next % 4 {
+            0 => Op::Backlight(false),
+            1 => Op::DelayMs(self.off_ms),
+            2 => Op::Backlight(true),
+            _ => Op::DelayMs(self.on_ms),
+        })
+    }
+}
+
+/// Flash the whole display, see `flash_display`.
+pub(crate) struct FlashDisplay {
+    times: u8,
+    on_ms: u32,
+    off_ms: u32,
+    display_on: bool,
+    flashed: u8,
+    stage: FlashStage,
+}
+
+enum FlashStage {
+    Start,
+    Off(Control),
+    OffWait,
+    On(Control),
+    OnWait,
+    Restore(Control),
+}
+
+impl FlashDisplay {
+    pub(crate) fn new(times: u8, on_ms: u32, off_ms: u32) -> Self {
+        Self {
+            times,
+            on_ms,
+            off_ms,
+            display_on: true,
+            flashed: 0,
+            stage: FlashStage::Start,
+        }
+    }
+
+    fn next<G: Geometry>(&mut self, state: &mut State<G>) {
+        if self.flashed < self.times {
+            self.flashed += 1;
+            state.display_on = false;
+            self.stage = FlashStage::Off(Control::update());
+        } else {
+            state.display_on = self.display_on;
+            self.stage = FlashStage::Restore(Control::update());
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for FlashDisplay {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        loop {
+            match &mut self.stage {
+                FlashStage::Start => {
+                    self.display_on = state.display_on;
+                    self.next(state);
+                }
+                FlashStage::Off(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = FlashStage::OffWait;
+                    return op(Op::DelayMs(self.off_ms));
+                }
+                FlashStage::OffWait => {
+                    state.display_on = true;
+                    self.stage = FlashStage::On(Control::update());
+                }
+                FlashStage::On(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    self.stage = FlashStage::OnWait;
+                    return op(Op::DelayMs(self.on_ms));
+                }
+                FlashStage::OnWait => self.next(state),
+                FlashStage::Restore(plan) => {
+                    nested!(plan, state, &mut outcome);
+                    return done(());
+                }
+            }
+        }
+    }
+}
+
+/// Shift the display or the cursor by a number of characters, one instruction at a time, see
+/// `scroll_display_left` and `shift_cursor_by`.
+pub(crate) struct Shift {
+    display: bool,
+    left: bool,
+    rest: u8,
+    send: Option<Send>,
+}
+
+impl Shift {
+    pub(crate) fn display(n: u8, dir: ShiftDirection) -> Self {
+        Self::new(true, n, dir)
+    }
+
+    pub(crate) fn cursor(n: u8, dir: ShiftDirection) -> Self {
+        Self::new(false, n, dir)
+    }
+
+    fn new(display: bool, rest: u8, dir: ShiftDirection) -> Self {
+        Self {
+            display,
+            left: dir == ShiftDirection::Left,
+            rest,
+            send: None,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for Shift {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        if let Some(send) = &mut self.send {
+            nested!(send, state, &mut outcome);
+            match self.display {
+                true => state.move_display_offset(self.left),
+                false => state.move_address_counter(!self.left),
+            }
+        }
+        if self.rest == 0 {
+            return done(());
+        }
+        self.rest -= 1;
+        let command = match (self.display, self.left) {
+            (true, true) => Commands::ShiftDisplayLeft,
+            (true, false) => Commands::ShiftDisplayRight,
+            (false, true) => Commands::ShiftCursorLeft,
+            (false, false) => Commands::ShiftCursorRight,
+        };
+        self.send
+            .insert(Send::command(command as u8))
+            .step(state, outcome)
+    }
+}
+
+/// Shift the display by a number of characters, in batches if the interface can, see
+/// `shift_display_by`.
+pub(crate) struct ShiftDisplay {
+    command: Commands,
+    left: bool,
+    rest: u8,
+    /// The batch being sent and the number of shifts in it.
+    batch: Option<(Batch<'static>, u8)>,
+}
+
+impl ShiftDisplay {
+    pub(crate) fn new(n: u8, dir: ShiftDirection) -> Self {
+        let (command, left) = match dir {
+            ShiftDirection::Left => (Commands::ShiftDisplayLeft, true),
+            ShiftDirection::Right => (Commands::ShiftDisplayRight, false),
+        };
+        Self {
+            command,
+            left,
+            rest: n,
+            batch: None,
+        }
+    }
+}
+
+impl<'a, G: Geometry> Plan<'a, G> for ShiftDisplay {
+    type Output = ();
+
+    fn step<E>(
+        &mut self,
+        state: &mut State<G>,
+        mut outcome: Outcome,
+    ) -> Result<Step<'a, ()>, Error<E>> {
+        if let Some((batch, count)) = &mut self.batch {
+            nested!(batch, state, &mut outcome);
+            for _ in 0..*count {
+                state.move_display_offset(self.left);
+            }
+        }
+        if self.rest == 0 {
+            return done(());
+        }
+        let count = self.rest.min(BATCH_LEN as u8);
+        self.rest -= count;
+        let commands = Bytes::copy(&[self.command as u8; BATCH_LEN][..usize::from(count)]);
+        let (batch, _) = self.batch.insert((Batch::commands(commands), count));
+        batch.step(state, outcome)
+    }
+}
@@ -0,0 +1,339 @@
+//! Writing to the display without blocking on delays.
+
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+
+use crate::{
+    geometry::Geometry, interface::Pcf8574, state::State, sync_lcd::GenericLcd, Backlight,
+    Commands, CursorMoveDir, Error, Mode,
+};
+
+/// Expander writes needed to send one byte to the controller.
+pub const WRITES_PER_BYTE: usize = 6;
+
+/// One expander write and the time to wait after it.
+#[derive(Copy, Clone)]
+struct Step {
+    byte: u8,
+    wait_us: u32,
+}
+
+/// Driver that queues the expander writes of each operation instead of waiting for the display,
+/// created with [`GenericLcd::into_queued`].
+///
+/// The queue holds `N` expander writes, every byte sent to the controller takes
+/// [`WRITES_PER_BYTE`]. Operations that don't fit into the queue return [`Error::QueueFull`]
+/// without queueing anything. Call [`poll`](Self::poll) regularly, e.g. from the main loop, to
+/// send the queued writes once the display is ready for them.
+///
+/// ```ignore
+/// let (mut lcd, delay) = lcd.into_queued::<256>();
+/// lcd.print_at(0, 0, "Hello")?;
+/// loop {
+///     lcd.poll(now_us())?;
+///     // Other work
+/// }
+/// ```
+pub struct QueuedLcd<G, I, const N: usize>
+where
+    I: I2c,
+{
+    interface: Pcf8574<I>,
+    state: State<G>,
+    steps: [Step; N],
+    head: usize,
+    len: usize,
+    ready_at: u64,
+}
+
+impl<G, I, const N: usize> QueuedLcd<G, I, N>
+where
+    G: Geometry,
+    I: I2c,
+{
+    pub(crate) fn new(interface: Pcf8574<I>, state: State<G>) -> Self {
+        Self {
+            interface,
+            state,
+            steps: [Step {
+                byte: 0,
+                wait_us: 0,
+            }; N],
+            head: 0,
+            len: 0,
+            ready_at: 0,
+        }
+    }
+
+    /// Send all queued writes, waiting with `delay`, and return to the blocking driver.
+    pub fn into_blocking<D: DelayNs>(
+        mut self,
+        mut delay: D,
+    ) -> Result<GenericLcd<G, Pcf8574<I>, D>, Error<I::Error>> {
+        self.flush_blocking(&mut delay)?;
+        Ok(GenericLcd::from_state(self.interface, delay, self.state))
+    }
+
+    /// Send the next queued write if the display is ready for it, `now_us` is the current time
+    /// in µs, counted from any point.
+    ///
+    /// At most one expander write is sent per call. Returns whether writes are still queued. If
+    /// the write fails, it stays queued and is sent again on the next call.
+    pub fn poll(&mut self, now_us: u64) -> Result<bool, Error<I::Error>> {
+        if self.len == 0 {
+            return Ok(false);
+        }
+        if now_us < self.ready_at {
+            return Ok(true);
+        }
+        let step = self.steps[self.head];
+        self.write_port(step.byte)?;
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        self.ready_at = now_us.saturating_add(step.wait_us.into());
+        Ok(self.len > 0)
+    }
+
+    /// Send all queued writes, waiting with `delay` instead of returning.
+    pub fn flush_blocking<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<I::Error>> {
+        while self.len > 0 {
+            let step = self.steps[self.head];
+            self.write_port(step.byte)?;
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            delay.delay_us(step.wait_us);
+        }
+        self.ready_at = 0;
+        Ok(())
+    }
+
+    /// Whether all queued writes were sent.
+    pub fn is_idle(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of expander writes that still fit into the queue.
+    pub fn free(&self) -> usize {
+        N - self.len
+    }
+
+    /// Write `text` at the cursor, translated like by [`GenericLcd::write_str`].
+    ///
+    /// Control characters are skipped and text past the end of the row is dropped, the
+    /// [`NewlineMode`](crate::NewlineMode) and [`OverflowPolicy`](crate::OverflowPolicy) don't
+    /// apply. Returns [`Error::QueueFull`] if the text doesn't fit into the queue and
+    /// [`Error::UnmappableChar`] according to the [`UnmappablePolicy`](crate::UnmappablePolicy),
+    /// nothing is queued then.
+    pub fn write_str(&mut self, text: &str) -> Result<(), Error<I::Error>> {
+        // Check the whole text first, so it's either queued completely or not at all.
+        let bytes = self.text_bytes(text, self.state.get_cursor().1)?;
+        self.reserve(bytes)?;
+        self.push_text(text)
+    }
+
+    /// Set the cursor to (row, col) and write `text` from there, see [`write_str`](Self::write_str).
+    ///
+    /// Nothing is queued, not even the cursor, if the position is invalid or the text can't be
+    /// queued.
+    pub fn print_at(&mut self, row: u8, col: u8, text: &str) -> Result<(), Error<I::Error>> {
+        self.state.check_position(row, col)?;
+        let bytes = self.text_bytes(text, col)?;
+        self.reserve(1 + bytes)?;
+        self.set_cursor(row, col)?;
+        self.push_text(text)
+    }
+
+    /// Write raw bytes, see [`GenericLcd::write_bytes`].
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error<I::Error>> {
+        self.reserve(data.len())?;
+        for &byte in data {
+            self.push_data(byte);
+        }
+        Ok(())
+    }
+
+    /// See [`GenericLcd::set_cursor`].
+    pub fn set_cursor(&mut self, row: u8, col: u8) -> Result<(), Error<I::Error>> {
+        self.state.check_position(row, col)?;
+        self.reserve(1)?;
+        let address = self.state.address_of(row, col);
+        self.push_byte(Mode::DDRAMAddr as u8 | address, Mode::Cmd);
+        self.state.set_address_counter(address);
+        Ok(())
+    }
+
+    /// See [`GenericLcd::clear`].
+    pub fn clear(&mut self) -> Result<(), Error<I::Error>> {
+        // Clearing resets the entry mode to left to right
+        let right_to_left = matches!(self.state.cursor_dir, CursorMoveDir::Right);
+        self.reserve(1 + right_to_left as usize)?;
+        self.push_byte(Commands::Clear as u8, Mode::Cmd);
+        self.state.clear_ddram();
+        self.state.home();
+        if right_to_left {
+            self.push_byte(self.state.entry_mode(), Mode::Cmd);
+        }
+        Ok(())
+    }
+
+    /// See [`GenericLcd::return_home`].
+    pub fn return_home(&mut self) -> Result<(), Error<I::Error>> {
+        self.reserve(1)?;
+        self.push_byte(Commands::ReturnHome as u8, Mode::Cmd);
+        self.state.home();
+        Ok(())
+    }
+
+    /// See [`GenericLcd::backlight`].
+    pub fn backlight(&mut self, backlight: Backlight) -> Result<(), Error<I::Error>> {
+        if self.free() == 0 {
+            return Err(Error::QueueFull);
+        }
+        self.state.config.backlight = backlight;
+        self.push(Step {
+            byte: self.interface.backlight_bit(backlight == Backlight::On),
+            wait_us: 0,
+        });
+        Ok(())
+    }
+
+    /// See [`GenericLcd::display_on`].
+    pub fn display_on(&mut self) -> Result<(), Error<I::Error>> {
+        self.set_display_control(|state| state.display_on = true)
+    }
+
+    /// See [`GenericLcd::display_off`].
+    pub fn display_off(&mut self) -> Result<(), Error<I::Error>> {
+        self.set_display_control(|state| state.display_on = false)
+    }
+
+    /// See [`GenericLcd::cursor_on`].
+    pub fn cursor_on(&mut self, on: bool) -> Result<(), Error<I::Error>> {
+        self.set_display_control(|state| state.config.cursor_on = on)
+    }
+
+    /// See [`GenericLcd::cursor_blink`].
+    pub fn cursor_blink(&mut self, blink: bool) -> Result<(), Error<I::Error>> {
+        self.set_display_control(|state| state.config.cursor_blink = blink)
+    }
+
+    /// See [`GenericLcd::create_char`].
+    pub fn create_char(&mut self, location: u8, charmap: &[u8; 8]) -> Result<(), Error<I::Error>> {
+        if location >= 8 {
+            return Err(Error::InvalidCgramSlot);
+        }
+        self.reserve(charmap.len() + 2)?;
+        self.push_byte(Mode::CGRAMAddr as u8 | (location << 3), Mode::Cmd);
+        for &row in charmap {
+            self.push_byte(row & 0x1f, Mode::Data);
+        }
+        self.push_byte(
+            Mode::DDRAMAddr as u8 | self.state.address_counter,
+            Mode::Cmd,
+        );
+        Ok(())
+    }
+
+    /// Get the cursor position as (row, col), including the queued operations.
+    pub fn get_cursor(&self) -> (u8, u8) {
+        self.state.get_cursor()
+    }
+
+    /// Change the display and cursor state with `change` and queue the display control
+    /// instruction.
+    fn set_display_control<F>(&mut self, change: F) -> Result<(), Error<I::Error>>
+    where
+        F: FnOnce(&mut State<G>),
+    {
+        self.reserve(1)?;
+        change(&mut self.state);
+        self.push_byte(self.state.display_control(), Mode::Cmd);
+        Ok(())
+    }
+
+    /// Number of bytes `text` written from `col` on is sent as, checking that all of its
+    /// characters can be translated.
+    fn text_bytes(&self, text: &str, col: u8) -> Result<usize, Error<I::Error>> {
+        let mut col = col;
+        let mut bytes = 0;
+        for (index, c) in text.char_indices() {
+            if c.is_control() {
+                continue;
+            }
+            let (_, mark) = self.state.map_char(c, index)?;
+            for _ in 0..1 + mark.is_some() as u8 {
+                if self.fits(col) {
+                    bytes += 1;
+                    col += 1;
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Queue the characters of `text` checked by [`text_bytes`](Self::text_bytes).
+    fn push_text(&mut self, text: &str) -> Result<(), Error<I::Error>> {
+        for (index, c) in text.char_indices().filter(|(_, c)| !c.is_control()) {
+            let (byte, mark) = self.state.map_char(c, index)?;
+            for byte in core::iter::once(byte).chain(mark) {
+                if self.fits(self.state.get_cursor().1) {
+                    self.push_data(byte);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a character written at `col` is shown, see `make_room` of the blocking driver.
+    fn fits(&self, col: u8) -> bool {
+        col < self.state.columns() || !self.state.writes_forward() || self.state.offscreen
+    }
+
+    /// Check that `bytes` bytes for the controller fit into the queue.
+    fn reserve(&self, bytes: usize) -> Result<(), Error<I::Error>> {
+        if bytes * WRITES_PER_BYTE > self.free() {
+            return Err(Error::QueueFull);
+        }
+        Ok(())
+    }
+
+    /// Queue a character and track it.
+    fn push_data(&mut self, byte: u8) {
+        self.push_byte(byte, Mode::Data);
+        self.state.record_data(byte);
+    }
+
+    /// Queue the writes of both nibbles of `data` with the waits of the blocking driver.
+    fn push_byte(&mut self, data: u8, mode: Mode) {
+        let backlight = self.state.config.backlight == Backlight::On;
+        let nibbles = [
+            (data >> 4, 0),
+            (data & 0x0f, self.state.execution_us(data, mode)),
+        ];
+        for (nibble, wait_us) in nibbles {
+            let [setup, enable, disable] =
+                self.interface
+                    .nibble_bytes(mode == Mode::Data, nibble, backlight);
+            self.push(Step {
+                byte: setup,
+                wait_us: 0,
+            });
+            self.push(Step {
+                byte: enable,
+                wait_us: 0,
+            });
+            self.push(Step {
+                byte: disable,
+                wait_us,
+            });
+        }
+    }
+
+    fn push(&mut self, step: Step) {
+        self.steps[(self.head + self.len) % N] = step;
+        self.len += 1;
+    }
+
+    fn write_port(&mut self, byte: u8) -> Result<(), Error<I::Error>> {
+        self.interface.write_port(byte).map_err(Error::I2c)
+    }
+}
@@ -0,0 +1,166 @@
+//! Backlight color of the Grove LCD RGB modules, set with a PCA9633 LED driver.
+//!
+//! The modules pair the controller, e.g. with an
+//! [`Aip31068Interface`](crate::interface::Aip31068Interface), with a PCA9633 at a second address
+//! on the same bus, so both need their own shared bus device.
+//!
+//! ```ignore
+//! let lcd = Lcd::<2, 16, _, _>::init_with_interface(
+//!     Aip31068Interface::new(RefCellDevice::new(&bus)),
+//!     delay,
+//!     LcdConfig::default(),
+//! )?;
+//! let mut backlight = RgbBacklight::new(RefCellDevice::new(&bus));
+//! backlight.init()?;
+//! let mut lcd = RgbLcd::new(lcd, backlight);
+//! lcd.backlight_rgb(255, 0, 0)?;
+//! lcd.write_str("Alarm")?;
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{Error as _, ErrorKind, I2c, NoAcknowledgeSource},
+};
+
+use crate::{geometry::Geometry, interface::LcdInterface, sync_lcd::GenericLcd, Error};
+
+/// I2C address of the PCA9633 on the Grove LCD RGB modules.
+pub const PCA9633_ADDRESS: u8 = 0x62;
+
+/// Register with the sleep mode, cleared to run the oscillator.
+const MODE1: u8 = 0x00;
+/// Register with the output configuration.
+const MODE2: u8 = 0x01;
+/// Brightness of the blue LED, green and red follow.
+const PWM0: u8 = 0x02;
+/// Register with the driver state of each LED.
+const LEDOUT: u8 = 0x08;
+
+// The values written by the Grove library: group control set to blinking, with its default duty
+// cycle leaving the LEDs on, and every LED driven by its own and the group brightness.
+const MODE2_GROUP_BLINK: u8 = 0x20;
+const LEDOUT_PWM_AND_GROUP: u8 = 0xff;
+
+/// PCA9633 LED driver setting the color of an RGB backlight.
+#[derive(Debug)]
+pub struct RgbBacklight<I> {
+    i2c: I,
+    address: u8,
+}
+
+impl<I: I2c> RgbBacklight<I> {
+    /// LED driver at [`PCA9633_ADDRESS`].
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            address: PCA9633_ADDRESS,
+        }
+    }
+
+    /// Use the LED driver at `address` instead.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// I2C address of the LED driver.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Wake the LED driver up and let it drive the LEDs with their brightness.
+    ///
+    /// Returns [`Error::BacklightNotFound`] if the LED driver doesn't acknowledge its address,
+    /// like on clones without it, and [`Error::I2c`] for other bus errors.
+    pub fn init(&mut self) -> Result<(), Error<I::Error>> {
+        match self.i2c.write(self.address, &[MODE1, 0x00]) {
+            Ok(()) => (),
+            Err(e) => match e.kind() {
+                ErrorKind::NoAcknowledge(
+                    NoAcknowledgeSource::Address | NoAcknowledgeSource::Unknown,
+                ) => return Err(Error::BacklightNotFound),
+                _ => return Err(Error::I2c(e)),
+            },
+        }
+        self.write(MODE2, MODE2_GROUP_BLINK)?;
+        self.write(LEDOUT, LEDOUT_PWM_AND_GROUP)
+    }
+
+    /// Set the brightness of the red, green and blue LED.
+    pub fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Error<I::Error>> {
+        self.write(PWM0 + 2, r)?;
+        self.write(PWM0 + 1, g)?;
+        self.write(PWM0, b)
+    }
+
+    /// Return the I2C instance.
+    pub fn into_inner(self) -> I {
+        self.i2c
+    }
+
+    fn write(&mut self, register: u8, value: u8) -> Result<(), Error<I::Error>> {
+        self.i2c
+            .write(self.address, &[register, value])
+            .map_err(Error::I2c)
+    }
+}
+
+/// A display with an [`RgbBacklight`], used like the display itself.
+///
+/// The backlight of the display isn't switched through its interface on these modules, use
+/// [`backlight_rgb`](Self::backlight_rgb) instead, `(0, 0, 0)` turns it off.
+pub struct RgbLcd<G, I, D, B>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    lcd: GenericLcd<G, I, D>,
+    backlight: RgbBacklight<B>,
+}
+
+impl<G, I, D, B> RgbLcd<G, I, D, B>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+    B: I2c,
+{
+    /// Bundle an initialized display with its initialized backlight.
+    pub fn new(lcd: GenericLcd<G, I, D>, backlight: RgbBacklight<B>) -> Self {
+        Self { lcd, backlight }
+    }
+
+    /// Set the color of the backlight, see [`RgbBacklight::set_rgb`].
+    pub fn backlight_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Error<B::Error>> {
+        self.backlight.set_rgb(r, g, b)
+    }
+
+    /// Return the display and the backlight.
+    pub fn into_parts(self) -> (GenericLcd<G, I, D>, RgbBacklight<B>) {
+        (self.lcd, self.backlight)
+    }
+}
+
+impl<G, I, D, B> Deref for RgbLcd<G, I, D, B>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    type Target = GenericLcd<G, I, D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lcd
+    }
+}
+
+impl<G, I, D, B> DerefMut for RgbLcd<G, I, D, B>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lcd
+    }
+}
@@ -0,0 +1,445 @@
+//! Simulated display for developing layouts on the host.
+//!
+//! [`Simulator`] emulates a HD44780 controller behind a PCF8574 expander with the default pin
+//! mapping. It takes the place of the I2C bus, so the normal driver runs unchanged against it,
+//! including the DDRAM layout of the rows, the display shift and the entry mode:
+//!
+//! ```
+//! use lcd_lcm1602_i2c::simulator::{NoDelay, Simulator, SimulatorLcd};
+//!
+//! let mut lcd = SimulatorLcd::<2, 16>::new(Simulator::new(), NoDelay)
+//!     .with_address(0x27)
+//!     .init()
+//!     .unwrap();
+//! lcd.create_char(0, &[0x0e; 8]).unwrap();
+//! lcd.print_at(0, 0, "Hello").unwrap();
+//! lcd.print_at(1, 9, "42 °C").unwrap();
+//! lcd.write_custom_char(0).unwrap();
+//!
+//! assert_eq!(
+//!     lcd.render(),
+//!     "+----------------+\n\
+//!      |Hello           |\n\
+//!      |         42 °C0 |\n\
+//!      +----------------+\n\
+//!      cursor (1, 15)\n"
+//! );
+//! ```
+
+use std::{fmt, string::String};
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation},
+};
+
+use crate::{
+    charset::shown_char,
+    geometry::{Fixed, Geometry},
+    interface::Pcf8574,
+    sync_lcd::GenericLcd,
+    testing::Snapshot,
+    Backlight,
+};
+
+/// Driver writing to a [`Simulator`].
+pub type SimulatorLcd<const ROWS: u8, const COLUMNS: u8> =
+    GenericLcd<Fixed<ROWS, COLUMNS>, Pcf8574<Simulator>, NoDelay>;
+
+const RS: u8 = 0x01;
+const RW: u8 = 0x02;
+const EN: u8 = 0x04;
+const BACKLIGHT: u8 = 0x08;
+
+/// Emulated HD44780 controller behind a PCF8574 expander, used as the I2C bus of the driver.
+///
+/// Characters are shown as the A00 ROM would show them, codes without a matching character as
+/// `'?'`. Custom characters are shown as the number of their CGRAM slot, or the character set
+/// with [`with_glyph_char`](Self::with_glyph_char).
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    address: u8,
+    port: u8,
+    eight_bit: bool,
+    /// Nibble transferred first in 4 bit mode, until the second one completes the byte.
+    first_nibble: Option<u8>,
+    two_lines: bool,
+    ddram: [u8; 128],
+    cgram: [u8; 64],
+    address_counter: u8,
+    cgram_selected: bool,
+    increment: bool,
+    shift_on_write: bool,
+    display_on: bool,
+    cursor_on: bool,
+    cursor_blink: bool,
+    display_shift: u8,
+    glyph_chars: [Option<char>; 8],
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// Controller in its power on state, answering at the common address `0x27`.
+    pub fn new() -> Self {
+        Self {
+            address: 0x27,
+            port: 0,
+            eight_bit: true,
+            first_nibble: None,
+            two_lines: false,
+            ddram: [b' '; 128],
+            cgram: [0; 64],
+            address_counter: 0,
+            cgram_selected: false,
+            increment: true,
+            shift_on_write: false,
+            display_on: false,
+            cursor_on: false,
+            cursor_blink: false,
+            display_shift: 0,
+            glyph_chars: [None; 8],
+        }
+    }
+
+    /// Answer at `address` instead, writes to other addresses aren't acknowledged.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Show the custom character in CGRAM slot `slot` as `c`.
+    pub fn with_glyph_char(mut self, slot: u8, c: char) -> Self {
+        self.glyph_chars[slot as usize & 7] = Some(c);
+        self
+    }
+
+    /// Character code at (row, col) of a display with `columns` columns, as it is shown with the
+    /// current display shift.
+    pub fn code_at(&self, row: u8, col: u8, columns: u8) -> u8 {
+        match self.visible_address(row, col, columns) {
+            Some(address) => self.ddram[address as usize],
+            None => b' ',
+        }
+    }
+
+    /// Text of `row` of a display with `columns` columns, as it is shown.
+    pub fn row_text(&self, row: u8, columns: u8) -> String {
+        (0..columns)
+            .map(|col| match self.display_on {
+                true => self.char_for(self.code_at(row, col, columns)),
+                false => ' ',
+            })
+            .collect()
+    }
+
+    /// Character codes shown by a display with `rows` rows and `columns` columns, to compare in
+    /// tests, see [`testing`](crate::testing).
+    pub fn snapshot(&self, rows: u8, columns: u8) -> Snapshot {
+        Snapshot::from_fn(rows, columns, |row, col| match self.display_on {
+            true => self.code_at(row, col, columns),
+            false => b' ',
+        })
+    }
+
+    /// Position of the cursor as (row, col) on a display with `rows` rows and `columns` columns,
+    /// `None` if the address counter isn't in a visible part of the DDRAM.
+    pub fn cursor(&self, rows: u8, columns: u8) -> Option<(u8, u8)> {
+        if self.cgram_selected {
+            return None;
+        }
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |col| (row, col)))
+            .find(|&(row, col)| {
+                self.visible_address(row, col, columns) == Some(self.address_counter)
+            })
+    }
+
+    /// The rows of a display with `rows` rows and `columns` columns in a box, followed by the
+    /// cursor position and the state of the display.
+    pub fn render(&self, rows: u8, columns: u8) -> String {
+        let border = format!("+{}+\n", "-".repeat(columns as usize));
+        let mut out = border.clone();
+        for row in 0..rows {
+            out += &format!("|{}|\n", self.row_text(row, columns));
+        }
+        out += &border;
+        match self.cursor(rows, columns) {
+            Some(position) => out += &format!("cursor {:?}", position),
+            None => out += "cursor offscreen",
+        }
+        if self.cursor_on {
+            out += ", underline";
+        }
+        if self.cursor_blink {
+            out += ", blinking";
+        }
+        if !self.display_on {
+            out += ", display off";
+        }
+        if self.port & BACKLIGHT == 0 {
+            out += ", backlight off";
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Rows of the custom character in CGRAM slot `slot`.
+    pub fn glyph(&self, slot: u8) -> [u8; 8] {
+        let start = (slot as usize & 7) * 8;
+        let mut rows = [0; 8];
+        rows.copy_from_slice(&self.cgram[start..start + 8]);
+        rows
+    }
+
+    /// Whether the backlight is on.
+    pub fn backlight(&self) -> Backlight {
+        match self.port & BACKLIGHT {
+            0 => Backlight::Off,
+            _ => Backlight::On,
+        }
+    }
+
+    /// Character shown for `code`.
+    fn char_for(&self, code: u8) -> char {
+        match code {
+            0x00..=0x0F => self.glyph_chars[code as usize & 7].unwrap_or_else(|| shown_char(code)),
+            _ => shown_char(code),
+        }
+    }
+
+    /// DDRAM address shown at (row, col). Rows after the second one continue the lines of the
+    /// first two rows, like on 20x4 displays.
+    fn visible_address(&self, row: u8, col: u8, columns: u8) -> Option<u8> {
+        if !self.two_lines {
+            return (row == 0).then(|| (col + self.display_shift) % 80);
+        }
+        let position = (row / 2) * columns + col;
+        if position >= 40 {
+            return None;
+        }
+        Some((row % 2) * 0x40 + (position + self.display_shift) % 40)
+    }
+
+    /// Handle a write to the expander, the controller latches the data lines when EN falls.
+    fn write_port(&mut self, byte: u8) {
+        if self.port & EN != 0 && byte & EN == 0 {
+            self.latch(self.port);
+        }
+        self.port = byte;
+    }
+
+    /// State of the expander pins, with the data lines driven by the controller while it's read.
+    fn read_port(&self) -> u8 {
+        if self.port & RW == 0 || self.port & EN == 0 {
+            return self.port;
+        }
+        let value = if self.port & RS != 0 {
+            self.read_ram()
+        } else {
+            // Never busy
+            self.address_counter & 0x7f
+        };
+        let nibble = match self.first_nibble {
+            Some(_) if !self.eight_bit => value & 0x0f,
+            _ => value >> 4,
+        };
+        (nibble << 4) | (self.port & 0x0f)
+    }
+
+    fn latch(&mut self, port: u8) {
+        let nibble = port >> 4;
+        let byte = if self.eight_bit {
+            // D0 to D3 aren't connected and read as low.
+            nibble << 4
+        } else if let Some(first) = self.first_nibble.take() {
+            (first << 4) | nibble
+        } else {
+            self.first_nibble = Some(nibble);
+            return;
+        };
+        match (port & RS != 0, port & RW != 0) {
+            (false, false) => self.execute(byte),
+            (true, false) => self.write_ram(byte),
+            (true, true) => self.move_address_counter(self.increment),
+            (false, true) => {}
+        }
+    }
+
+    fn execute(&mut self, instruction: u8) {
+        match instruction {
+            0x80..=0xFF => {
+                self.address_counter = instruction & 0x7f;
+                self.cgram_selected = false;
+            }
+            0x40..=0x7F => {
+                self.address_counter = instruction & 0x3f;
+                self.cgram_selected = true;
+            }
+            0x20..=0x3F => {
+                self.eight_bit = instruction & 0x10 != 0;
+                self.two_lines = instruction & 0x08 != 0;
+            }
+            0x10..=0x1F => {
+                let right = instruction & 0x04 != 0;
+                if instruction & 0x08 != 0 {
+                    self.shift_display(!right);
+                } else {
+                    self.move_address_counter(right);
+                }
+            }
+            0x08..=0x0F => {
+                self.display_on = instruction & 0x04 != 0;
+                self.cursor_on = instruction & 0x02 != 0;
+                self.cursor_blink = instruction & 0x01 != 0;
+            }
+            0x04..=0x07 => {
+                self.increment = instruction & 0x02 != 0;
+                self.shift_on_write = instruction & 0x01 != 0;
+            }
+            0x02..=0x03 => {
+                self.address_counter = 0;
+                self.cgram_selected = false;
+                self.display_shift = 0;
+            }
+            0x01 => {
+                self.ddram = [b' '; 128];
+                self.address_counter = 0;
+                self.cgram_selected = false;
+                self.display_shift = 0;
+                self.increment = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self) -> u8 {
+        match self.cgram_selected {
+            true => self.cgram[self.address_counter as usize & 0x3f],
+            false => self.ddram[self.address_counter as usize & 0x7f],
+        }
+    }
+
+    fn write_ram(&mut self, byte: u8) {
+        if self.cgram_selected {
+            self.cgram[self.address_counter as usize & 0x3f] = byte & 0x1f;
+        } else {
+            self.ddram[self.address_counter as usize & 0x7f] = byte;
+            if self.shift_on_write {
+                self.shift_display(self.increment);
+            }
+        }
+        self.move_address_counter(self.increment);
+    }
+
+    /// Move the displayed part of the lines by one, to the left shows the next address.
+    fn shift_display(&mut self, left: bool) {
+        let line_length = if self.two_lines { 40 } else { 80 };
+        self.display_shift = if left {
+            (self.display_shift + 1) % line_length
+        } else {
+            (self.display_shift + line_length - 1) % line_length
+        };
+    }
+
+    fn move_address_counter(&mut self, forward: bool) {
+        let address = self.address_counter;
+        self.address_counter = match (self.cgram_selected, self.two_lines, forward) {
+            (true, _, true) => (address + 1) & 0x3f,
+            (true, _, false) => address.wrapping_sub(1) & 0x3f,
+            (false, false, true) => (address + 1) % 80,
+            (false, false, false) => (address + 79) % 80,
+            (false, true, true) => match address {
+                0x27 => 0x40,
+                0x67 => 0x00,
+                _ => address + 1,
+            },
+            (false, true, false) => match address {
+                0x40 => 0x27,
+                0x00 => 0x67,
+                _ => address - 1,
+            },
+        };
+    }
+}
+
+/// Error of the [`Simulator`] when it's addressed with another address.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SimulatorError;
+
+impl embedded_hal::i2c::Error for SimulatorError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    }
+}
+
+impl fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("address not acknowledged")
+    }
+}
+
+impl std::error::Error for SimulatorError {}
+
+impl ErrorType for Simulator {
+    type Error = SimulatorError;
+}
+
+impl I2c for Simulator {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address != self.address {
+            return Err(SimulatorError);
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => bytes.iter().for_each(|&byte| self.write_port(byte)),
+                Operation::Read(buf) => buf.fill(self.read_port()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Delay returning immediately, the [`Simulator`] is never busy.
+#[derive(Debug, Copy, Clone)]
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl<G, D> GenericLcd<G, Pcf8574<Simulator>, D>
+where
+    G: Geometry,
+    D: DelayNs,
+{
+    /// The simulated controller.
+    pub fn simulator(&self) -> &Simulator {
+        self.i2c()
+    }
+
+    /// Character codes shown by the simulated display, see [`Simulator::snapshot`].
+    pub fn simulated_snapshot(&self) -> Snapshot {
+        let (rows, columns) = self.size();
+        self.simulator().snapshot(rows, columns)
+    }
+
+    /// The display in a box, see [`Simulator::render`].
+    pub fn render(&self) -> String {
+        let (rows, columns) = self.size();
+        self.simulator().render(rows, columns)
+    }
+
+    /// Print the display to stdout, see [`Simulator::render`].
+    pub fn print(&self) {
+        print!("{}", self.render());
+    }
+}
@@ -0,0 +1,502 @@
+//! What the driver knows about the controller and how instructions are built from it, shared by
+//! the sync and async driver. The drivers only add the transfers through the interface and the
+//! delays.
+
+use crate::{
+    charset::{translate, UnmappableChar},
+    config::LcdConfig,
+    cursor_position, ddram_index, default_row_offsets,
+    geometry::Geometry,
+    glyph::GlyphAllocator,
+    next_address, row_capacity,
+    testing::Snapshot,
+    Backlight, BitMode, Commands, CursorMoveDir, DisplayControl, DisplayShift, Error, Font, Mode,
+    UnmappablePolicy,
+};
+
+pub(crate) struct State<G> {
+    pub(crate) geometry: G,
+    pub(crate) config: LcdConfig,
+    pub(crate) cursor_dir: CursorMoveDir,
+    pub(crate) display_shift: DisplayShift,
+    pub(crate) address_counter: u8,
+    pub(crate) display_offset: u8,
+    pub(crate) saved_cursor: Option<u8>,
+    pub(crate) clipped: bool,
+    pub(crate) offscreen: bool,
+    pub(crate) full_row: Option<u8>,
+    /// Copy of the DDRAM of each controller.
+    pub(crate) ddram: [u8; 160],
+    pub(crate) display_on: bool,
+    pub(crate) glyphs: GlyphAllocator,
+    /// Whether the driver polls the busy flag instead of waiting the execution times, off until
+    /// the init sequence set up the width of the bus.
+    pub(crate) poll_busy_flag: bool,
+    /// Whether a status read went to a board with RW tied to ground, which took it as an
+    /// instruction moving the address counter, see `restore_address`.
+    pub(crate) address_lost: bool,
+    /// Whether a read moved the address counter of the controller into the RAM read from, set
+    /// again if the read fails partway.
+    pub(crate) address_moved: bool,
+    /// Width of the data bus of the interface, set by the init sequence.
+    pub(crate) bit_mode: BitMode,
+    /// Controller showing the cursor, as a mask like the controllers selected on the interface.
+    pub(crate) cursor_controller: u8,
+}
+
+impl<G: Geometry> State<G> {
+    pub(crate) fn new(geometry: G, config: LcdConfig) -> Self {
+        Self {
+            geometry,
+            config,
+            cursor_dir: CursorMoveDir::Left,
+            display_shift: DisplayShift::Decrement,
+            address_counter: 0,
+            display_offset: 0,
+            saved_cursor: None,
+            clipped: false,
+            offscreen: false,
+            full_row: None,
+            ddram: [b' '; 160],
+            display_on: true,
+            glyphs: GlyphAllocator::default(),
+            poll_busy_flag: false,
+            address_lost: false,
+            address_moved: false,
+            bit_mode: BitMode::Bit4,
+            cursor_controller: 1,
+        }
+    }
+
+    pub(crate) fn rows(&self) -> u8 {
+        self.geometry.rows()
+    }
+
+    pub(crate) fn columns(&self) -> u8 {
+        self.geometry.columns()
+    }
+
+    /// First column of the second half of a split row, see [`LcdConfig::with_split_row`].
+    pub(crate) fn split_column(&self) -> Option<u8> {
+        (self.config.split_row && self.rows() == 1).then_some(self.columns() / 2)
+    }
+
+    /// Number of lines the controller is set up with, two for a split row.
+    pub(crate) fn lines(&self) -> u8 {
+        if self.split_column().is_some() {
+            2
+        } else {
+            self.rows()
+        }
+    }
+
+    /// DDRAM address of (row, col).
+    pub(crate) fn address_of(&self, row: u8, col: u8) -> u8 {
+        match self.split_column() {
+            Some(split) if col >= split => 0x40 + col - split,
+            _ => self.row_offsets()[row as usize] + col,
+        }
+    }
+
+    /// Address the controller has to be moved to after the address counter moved from `previous`
+    /// over the seam of a split row.
+    pub(crate) fn seam_address(&self, previous: u8) -> Option<u8> {
+        let split = self.split_column()?;
+        if previous + 1 == split && self.address_counter == split {
+            Some(0x40)
+        } else if previous == 0x40 && self.address_counter == 0x27 {
+            Some(split - 1)
+        } else {
+            None
+        }
+    }
+
+    /// DDRAM addresses of the first column of each row.
+    pub(crate) fn row_offsets(&self) -> [u8; 4] {
+        self.config.row_offsets.unwrap_or_else(|| {
+            default_row_offsets(self.rows(), self.columns(), self.geometry.controllers())
+        })
+    }
+
+    /// Mask of all controllers of the display, bit 0 is the first one.
+    pub(crate) fn all_controllers(&self) -> u8 {
+        (1 << self.geometry.controllers()) - 1
+    }
+
+    /// Mask of the controller holding the DDRAM address `address`.
+    pub(crate) fn controller_of(&self, address: u8) -> u8 {
+        1 << (address >> 7)
+    }
+
+    /// Controllers `mode` goes to: characters go to the controller holding the cursor,
+    /// instructions to all of them.
+    pub(crate) fn controllers_for(&self, mode: Mode) -> u8 {
+        match mode {
+            Mode::Data => self.controller_of(self.address_counter),
+            _ => self.all_controllers(),
+        }
+    }
+
+    /// Whether the cursor is shown as an underline or blinking block.
+    pub(crate) fn cursor_visible(&self) -> bool {
+        self.config.cursor_on || self.config.cursor_blink
+    }
+
+    /// Forget the contents of the DDRAM after a clear.
+    pub(crate) fn clear_ddram(&mut self) {
+        self.ddram = [b' '; 160];
+    }
+
+    pub(crate) fn backlight_on(&self) -> bool {
+        self.config.backlight == Backlight::On
+    }
+
+    /// Check the config before the display is set up with it.
+    pub(crate) fn check_config<E>(&self) -> Result<(), Error<E>> {
+        if self.row_offsets()[..self.rows() as usize]
+            .iter()
+            .any(|&offset| {
+                let address = offset & 0x7f;
+                // In 2-line mode, the DDRAM has a gap between the lines.
+                let gap = self.lines() > 1 && (0x28..0x40).contains(&address);
+                address >= 0x68 || gap || offset >> 7 >= self.geometry.controllers()
+            })
+        {
+            return Err(Error::InvalidRowOffset);
+        }
+        self.check_font(self.config.font)?;
+        Ok(())
+    }
+
+    pub(crate) fn check_position<E>(&self, row: u8, col: u8) -> Result<(), Error<E>> {
+        if row >= self.rows() {
+            return Err(Error::InvalidRow);
+        }
+        if col >= self.columns() {
+            return Err(Error::InvalidColumn);
+        }
+        Ok(())
+    }
+
+    /// Check that the controller supports `font` with the number of rows of the display.
+    pub(crate) fn check_font<E>(&self, font: Font) -> Result<(), Error<E>> {
+        if font == Font::Font5x10 && self.lines() > 1 {
+            return Err(Error::UnsupportedFontForGeometry);
+        }
+        Ok(())
+    }
+
+    /// DDRAM address of (row, col), allowing columns outside of the display up to the end of the
+    /// DDRAM of the row, see `set_cursor_ddram`.
+    pub(crate) fn ddram_address<E>(&self, row: u8, col: u8) -> Result<u8, Error<E>> {
+        if row >= self.rows() {
+            return Err(Error::InvalidRow);
+        }
+        let offset = self.row_offsets()[row as usize];
+        if col >= row_capacity(self.lines(), offset) {
+            return Err(Error::InvalidColumn);
+        }
+        Ok(offset + col)
+    }
+
+    /// CGRAM address of the custom character in slot `location`, see `create_char`.
+    pub(crate) fn cgram_address<E>(&self, location: u8) -> Result<u8, Error<E>> {
+        if location >= 8 {
+            return Err(Error::InvalidCgramSlot);
+        }
+        Ok(location << 3)
+    }
+
+    /// Check that glyphs can be used with the font, the custom characters of the tall font have
+    /// more rows than a glyph, see `install_glyph`.
+    pub(crate) fn check_glyph_font<E>(&self) -> Result<(), Error<E>> {
+        if self.config.font == Font::Font5x10 {
+            return Err(Error::WrongFont);
+        }
+        Ok(())
+    }
+
+    /// CGRAM address of the custom character of the tall font in slot `location`, see
+    /// `create_char_5x10`.
+    pub(crate) fn tall_cgram_address<E>(&self, location: u8) -> Result<u8, Error<E>> {
+        if self.config.font != Font::Font5x10 {
+            return Err(Error::WrongFont);
+        }
+        if location >= 4 {
+            return Err(Error::InvalidCgramSlot);
+        }
+        Ok(location << 4)
+    }
+
+    /// Character code showing CGRAM slot `index` with the font of the display, `None` if the
+    /// font has no such slot.
+    ///
+    /// A slot of the tall font takes the CGRAM of two slots of eleven rows, so its code is
+    /// twice the slot.
+    pub(crate) fn custom_char_code(&self, index: u8) -> Option<u8> {
+        match self.config.font {
+            Font::Font5x10 if index < 4 => Some(index * 2),
+            Font::Font5x8 if index < 8 => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Cursor position as (row, col), see `get_cursor`.
+    pub(crate) fn get_cursor(&self) -> (u8, u8) {
+        if let Some(row) = self.full_row {
+            return (row, self.columns());
+        }
+        match self.split_column() {
+            Some(split) if self.address_counter >= 0x40 => {
+                return (0, self.address_counter - 0x40 + split);
+            }
+            _ => (),
+        }
+        cursor_position(self.rows(), &self.row_offsets(), self.address_counter)
+    }
+
+    /// Track the cursor being moved to `address`.
+    pub(crate) fn set_address_counter(&mut self, address: u8) {
+        self.address_counter = address;
+        self.clipped = false;
+        self.offscreen = false;
+        self.full_row = None;
+    }
+
+    /// Remember the cursor position, see `save_cursor`.
+    pub(crate) fn save_cursor<E>(&mut self) -> Result<(), Error<E>> {
+        if self.saved_cursor.is_some() {
+            return Err(Error::CursorAlreadySaved);
+        }
+        self.saved_cursor = Some(self.address_counter);
+        Ok(())
+    }
+
+    /// Track the cursor and the display returning to their start.
+    pub(crate) fn home(&mut self) {
+        self.set_address_counter(0);
+        self.display_offset = 0;
+    }
+
+    /// Track `data` being written at the address counter.
+    pub(crate) fn record_data(&mut self, data: u8) {
+        self.ddram[ddram_index(self.lines(), self.address_counter)] = data;
+        let forward = matches!(self.cursor_dir, CursorMoveDir::Left);
+        self.move_address_counter(forward);
+        if let DisplayShift::Increment = self.display_shift {
+            self.move_display_offset(forward);
+        }
+    }
+
+    /// Track the display being shifted by one.
+    pub(crate) fn move_display_offset(&mut self, left: bool) {
+        let line_length = if self.lines() == 1 { 80 } else { 40 };
+        self.display_offset = if left {
+            (self.display_offset + 1) % line_length
+        } else {
+            (self.display_offset + line_length - 1) % line_length
+        };
+    }
+
+    /// Track the address counter of the controller moving by one.
+    pub(crate) fn move_address_counter(&mut self, forward: bool) {
+        let (row, col) = self.get_cursor();
+        self.address_counter = next_address(self.lines(), self.address_counter, forward);
+        // The address behind the end of a row can be the start of another row, e.g. on 20x4
+        // displays, remember which row was filled.
+        self.full_row = (forward && col + 1 == self.columns()).then_some(row);
+    }
+
+    /// Whether written text moves the cursor to the right over a still display.
+    pub(crate) fn writes_forward(&self) -> bool {
+        matches!(self.cursor_dir, CursorMoveDir::Left)
+            && matches!(self.display_shift, DisplayShift::Decrement)
+    }
+
+    /// Code of `c` if it can be sent together with the `pending` characters before it: a single
+    /// code written forward that still fits into the row, without any character handling.
+    pub(crate) fn batchable(&self, c: char, pending: usize) -> Option<u8> {
+        let plain = !matches!(c, '\n' | '\r' | '\t');
+        if !plain || self.clipped || self.offscreen || self.split_column().is_some() {
+            return None;
+        }
+        let col = usize::from(self.get_cursor().1);
+        if !self.writes_forward() || col + pending >= usize::from(self.columns()) {
+            return None;
+        }
+        match self.translate(c) {
+            Some((code, None)) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Column of the next tab stop, but not past the end of the row.
+    pub(crate) fn next_tab_stop(&self) -> u8 {
+        let col = self.get_cursor().1;
+        let width = self.config.tab_width;
+        (col / width + 1).saturating_mul(width).min(self.columns())
+    }
+
+    /// Characters written to `row` as far as the driver knows, padded with spaces.
+    pub(crate) fn row_contents(&self, row: u8) -> [u8; 80] {
+        let mut contents = [b' '; 80];
+        let mut address = self.row_offsets()[row as usize];
+        for (col, byte) in (0..self.columns()).zip(&mut contents) {
+            if Some(col) == self.split_column() {
+                address = 0x40;
+            }
+            *byte = self.ddram[ddram_index(self.lines(), address)];
+            address = next_address(self.lines(), address, true);
+        }
+        contents
+    }
+
+    /// Characters shown on the display as far as the driver knows, with the display shift.
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        let line_length = if self.lines() == 1 { 80 } else { 40 };
+        let offsets = self.row_offsets();
+        Snapshot::from_fn(self.rows(), self.columns(), |row, col| {
+            let (offset, col) = match self.split_column() {
+                Some(split) if col >= split => (0x40, col - split),
+                _ => (offsets[row as usize], col),
+            };
+            let line = offset & 0xc0;
+            let position = (offset & 0x3f) + col + self.display_offset;
+            self.ddram[ddram_index(self.lines(), line | (position % line_length))]
+        })
+    }
+
+    /// Translate a character with the installed `CharMapper` and the `Charset`.
+    pub(crate) fn translate(&self, c: char) -> Option<(u8, Option<u8>)> {
+        translate_char(&self.config, c)
+    }
+
+    /// Translate a character at byte `index` of a string to its character code, applying the
+    /// [`UnmappablePolicy`] to characters the display can't show.
+    ///
+    /// Some characters are written as two character codes, the second one is returned
+    /// separately.
+    pub(crate) fn map_char<E>(&self, c: char, index: usize) -> Result<(u8, Option<u8>), Error<E>> {
+        match self.translate(c) {
+            Some(codes) => Ok(codes),
+            None => self.unmappable(index),
+        }
+    }
+
+    /// Apply the [`UnmappablePolicy`] to a character at byte `index` the display can't show.
+    fn unmappable<E>(&self, index: usize) -> Result<(u8, Option<u8>), Error<E>> {
+        match self.config.unmappable_policy {
+            UnmappablePolicy::Replace(byte) => Ok((byte, None)),
+            UnmappablePolicy::Error => Err(Error::UnmappableChar { index }),
+        }
+    }
+
+    /// Character codes of a row showing the first `len` characters of `s` after `pad_left`
+    /// spaces, filled up with spaces, see `write_line`.
+    ///
+    /// Control characters aren't interpreted, the [`UnmappablePolicy`] applies to them like to
+    /// characters the display can't show.
+    pub(crate) fn padded_row<E>(
+        &self,
+        s: &str,
+        pad_left: u8,
+        len: u8,
+    ) -> Result<[u8; 80], Error<E>> {
+        let mut row = [b' '; 80];
+        let mut col = usize::from(pad_left);
+        for (index, c) in s.char_indices().take(len.into()) {
+            let (code, mark) = match c.is_control() {
+                true => self.unmappable(index)?,
+                false => self.map_char(c, index)?,
+            };
+            for code in core::iter::once(code).chain(mark) {
+                row[col] = code;
+                col += 1;
+            }
+        }
+        Ok(row)
+    }
+
+    /// Check if the display can show all characters of `s`.
+    pub(crate) fn validate_str(&self, s: &str) -> Result<(), UnmappableChar> {
+        match s.char_indices().find(|&(_, c)| self.translate(c).is_none()) {
+            Some((index, character)) => Err(UnmappableChar { index, character }),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of columns `c` takes on the display.
+    pub(crate) fn char_width(&self, c: char) -> u8 {
+        match self.translate(c) {
+            Some((_, Some(_))) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Number of characters of `s` fitting into one row and the number of columns they take.
+    pub(crate) fn fitting_len(&self, s: &str) -> (u8, u8) {
+        let mut len = 0;
+        let mut width = 0;
+        for c in s.chars() {
+            let char_width = self.char_width(c);
+            if width + char_width > self.columns() {
+                break;
+            }
+            len += 1;
+            width += char_width;
+        }
+        (len, width)
+    }
+
+    /// Display control instruction for the display and cursor state.
+    pub(crate) fn display_control(&self) -> u8 {
+        let mut display_ctrl = if self.display_on {
+            DisplayControl::DisplayOn as u8
+        } else {
+            DisplayControl::Off as u8
+        };
+        if self.config.cursor_on {
+            display_ctrl |= DisplayControl::CursorOn as u8;
+        }
+        if self.config.cursor_blink {
+            display_ctrl |= DisplayControl::CursorBlink as u8;
+        }
+        Mode::DisplayControl as u8 | display_ctrl
+    }
+
+    /// Entry mode instruction for the text direction and autoscroll.
+    pub(crate) fn entry_mode(&self) -> u8 {
+        Mode::EntrySet as u8 | self.cursor_dir as u8 | self.display_shift as u8
+    }
+
+    /// Function set instruction for the number of rows and the font.
+    pub(crate) fn function_set(&self) -> u8 {
+        // The tall font only exists in one line mode.
+        let (lines, font) = match self.lines() {
+            1 => (0x00, self.config.font),
+            _ => (0x08, Font::Font5x8), // Two line display
+        };
+        Mode::FunctionSet as u8 | self.bit_mode as u8 | font as u8 | lines
+    }
+
+    /// Time the controller needs to execute `data` sent with `mode`.
+    pub(crate) fn execution_us(&self, data: u8, mode: Mode) -> u32 {
+        match mode {
+            // Return home ignores the lowest bit.
+            Mode::Cmd
+                if data == Commands::Clear as u8 || data & !1 == Commands::ReturnHome as u8 =>
+            {
+                self.config.timings.clear_us
+            }
+            _ => self.config.timings.command_us,
+        }
+    }
+}
+
+/// Translate a character with the `CharMapper` and the `Charset` of `config`.
+pub(crate) fn translate_char(config: &LcdConfig, c: char) -> Option<(u8, Option<u8>)> {
+    if let Some(code) = config.char_mapper.and_then(|mapper| mapper.map(c)) {
+        return Some((code, None));
+    }
+    translate(config.charset, c)
+}
@@ -1,72 +1,351 @@
+use core::fmt;
+use core::ops::Range;
+
 use embedded_hal::delay::DelayNs;
 use embedded_hal::i2c::I2c;
 
+use ufmt::{uDebug, uDisplay, Formatter};
 use ufmt_write::uWrite;
 
 use crate::{
-    Backlight, BitMode, Commands, CursorMoveDir, DisplayControl, DisplayShift, Font, Mode,
-    OFFSETS_16X4, OFFSETS_NORMAL,
+    acknowledged,
+    charset::{CharMapper, UnmappableChar},
+    check_address,
+    config::{LcdConfig, PinMapping, Timings},
+    format::{format_fixed, format_i32, format_u32, FIXED_LEN, NUMBER_LEN},
+    geometry::{Dual, Dynamic, Fixed, Geometry, SingleController},
+    glyph::GlyphHandle,
+    interface::{LcdInterface, Pcf8574, St7032iInterface, Us2066Interface},
+    plan::{
+        number, restore_address, Align, Batch, Blank, Bytes, Control, FlashBacklight, FlashDisplay,
+        Home, Init, MoveTo, Op, Outcome, Plan, PutChar, ReadAddress, ReadRam, Row, Send,
+        SetAddress, SetBacklight, Shift, ShiftDisplay, Step, Text, Upload, Wrapped,
+    },
+    queued::QueuedLcd,
+    state::State,
+    testing::Snapshot,
+    Backlight, BitMode, Charset, Controller, CursorMoveDir, DisplayControl, DisplayShift, Error,
+    Font, Mode, NewlineMode, OverflowPolicy, Polarity, ShiftDirection, UnmappablePolicy,
+    BUSY_POLLS, BUSY_POLL_DELAY, RW_GROUNDED,
 };
 
-/// API to write to the LCD.
-pub struct Lcd<'a, const ROWS: u8, const COLUMNS: u8, I, D>
+/// API to write to a LCD with a size known at compile time.
+pub type Lcd<const ROWS: u8, const COLUMNS: u8, I, D> =
+    GenericLcd<Fixed<ROWS, COLUMNS>, Pcf8574<I>, D>;
+
+/// API to write to a LCD with a size only known at runtime.
+pub type DynLcd<I, D> = GenericLcd<Dynamic, Pcf8574<I>, D>;
+
+/// API to write to a LCD with four rows on two controllers, like 40x4 displays, see [`Dual`].
+pub type DualLcd<const COLUMNS: u8, I, D> = GenericLcd<Dual<COLUMNS>, Pcf8574<I>, D>;
+
+/// API to write to the LCD, generic over the [`Geometry`] of the display.
+///
+/// Use it through [`Lcd`], [`DynLcd`] or [`DualLcd`].
+pub struct GenericLcd<G, I, D>
 where
-    I: I2c,
+    I: LcdInterface,
     D: DelayNs,
 {
-    i2c: &'a mut I,
-    address: u8,
-    delay: &'a mut D,
-    backlight_state: Backlight,
-    cursor_on: bool,
-    cursor_blink: bool,
-    font_mode: Font,
+    interface: I,
+    delay: D,
+    state: State<G>,
+    last_error: Option<Error<I::Error>>,
 }
 
-impl<'a, const ROWS: u8, const COLUMNS: u8, I, D> Lcd<'a, ROWS, COLUMNS, I, D>
+// Creating the builder through the driver type keeps `Lcd::new(..).init()` working.
+#[allow(clippy::new_ret_no_self)]
+impl<const ROWS: u8, const COLUMNS: u8, I, D> Lcd<ROWS, COLUMNS, I, D>
 where
     I: I2c,
     D: DelayNs,
 {
     /// Create new instance with only the I2C and delay instance.
-    pub fn new(i2c: &'a mut I, delay: &'a mut D) -> Self {
-        const {
-            assert!(ROWS > 0, "ROWS needs to be larger than zero!");
-            assert!(COLUMNS > 0, "COLUMNS needs to be larger than zero!");
-            assert!(
-                ROWS < 5,
-                "This library only supports LCDs with up to four rows!"
-            ); // Because we don't have offsets for more than four rows
-        };
+    ///
+    /// Both are taken by value, pass `&mut i2c` and `&mut delay` to only borrow them.
+    pub fn new(i2c: I, delay: D) -> LcdBuilder<ROWS, COLUMNS, I, D> {
+        Self::new_with_config(i2c, delay, LcdConfig::default())
+    }
+
+    /// Create new instance with the I2C and delay instance and the given config.
+    pub fn new_with_config(i2c: I, delay: D, config: LcdConfig) -> LcdBuilder<ROWS, COLUMNS, I, D> {
+        GenericLcdBuilder::new(i2c, delay, Fixed::new(), config)
+    }
+}
+
+// Creating the builder through the driver type keeps `Lcd::new(..).init()` working.
+#[allow(clippy::new_ret_no_self)]
+impl<I, D> DynLcd<I, D>
+where
+    I: I2c,
+    D: DelayNs,
+{
+    /// Create new instance for a display with `rows` rows and `columns` columns, see
+    /// [`Lcd::new`].
+    ///
+    /// Returns [`Error::InvalidGeometry`] if the size isn't supported.
+    pub fn new(
+        i2c: I,
+        delay: D,
+        rows: u8,
+        columns: u8,
+    ) -> Result<DynLcdBuilder<I, D>, Error<I::Error>> {
+        Self::new_with_config(i2c, delay, rows, columns, LcdConfig::default())
+    }
+
+    /// Create new instance with the given config, see [`new`](Self::new).
+    pub fn new_with_config(
+        i2c: I,
+        delay: D,
+        rows: u8,
+        columns: u8,
+        config: LcdConfig,
+    ) -> Result<DynLcdBuilder<I, D>, Error<I::Error>> {
+        let geometry = Dynamic::new(rows, columns).ok_or(Error::InvalidGeometry)?;
+        Ok(GenericLcdBuilder::new(i2c, delay, geometry, config))
+    }
+}
+
+// Creating the builder through the driver type keeps `Lcd::new(..).init()` working.
+#[allow(clippy::new_ret_no_self)]
+impl<const COLUMNS: u8, I, D> DualLcd<COLUMNS, I, D>
+where
+    I: I2c,
+    D: DelayNs,
+{
+    /// Create new instance with only the I2C and delay instance, see [`Lcd::new`].
+    pub fn new(i2c: I, delay: D) -> DualLcdBuilder<COLUMNS, I, D> {
+        Self::new_with_config(i2c, delay, LcdConfig::default())
+    }
+
+    /// Create new instance with the I2C and delay instance and the given config.
+    pub fn new_with_config(i2c: I, delay: D, config: LcdConfig) -> DualLcdBuilder<COLUMNS, I, D> {
+        GenericLcdBuilder::new(i2c, delay, Dual::new(), config)
+    }
+}
+
+/// Builder for a [`Lcd`], returned by [`Lcd::new`].
+pub type LcdBuilder<const ROWS: u8, const COLUMNS: u8, I, D> =
+    GenericLcdBuilder<Fixed<ROWS, COLUMNS>, I, D>;
+
+/// Builder for a [`DynLcd`], returned by [`DynLcd::new`].
+pub type DynLcdBuilder<I, D> = GenericLcdBuilder<Dynamic, I, D>;
+
+/// Builder for a [`DualLcd`], returned by [`DualLcd::new`].
+pub type DualLcdBuilder<const COLUMNS: u8, I, D> = GenericLcdBuilder<Dual<COLUMNS>, I, D>;
+
+/// Config of a display that isn't initialized yet.
+///
+/// Only [`init`](Self::init) and [`init_warm`](Self::init_warm) give access to the display, so it
+/// can't be written to before it's set up:
+///
+/// ```compile_fail
+/// # use embedded_hal::{delay::DelayNs, i2c::I2c};
+/// # use lcd_lcm1602_i2c::sync_lcd::Lcd;
+/// fn greet<I: I2c, D: DelayNs>(i2c: I, delay: D) {
+///     let mut lcd = Lcd::<2, 16, I, D>::new(i2c, delay).with_address(0x27);
+///     lcd.write_str("Hello");
+/// }
+/// ```
+pub struct GenericLcdBuilder<G, I, D>
+where
+    I: I2c,
+    D: DelayNs,
+{
+    i2c: I,
+    geometry: G,
+    config: LcdConfig,
+    delay: D,
+}
+
+impl<G, I, D> GenericLcdBuilder<G, I, D>
+where
+    G: Geometry,
+    I: I2c,
+    D: DelayNs,
+{
+    fn new(i2c: I, delay: D, geometry: G, config: LcdConfig) -> Self {
         Self {
             i2c,
+            geometry,
+            config,
             delay,
-            backlight_state: Backlight::On,
-            address: 0,
-            cursor_blink: false,
-            cursor_on: false,
-            font_mode: Font::Font5x8,
         }
     }
 
-    /// Set I2C address, see [lcd address].
-    ///
-    /// [lcd address]: https://badboi.dev/rust,/microcontrollers/2020/11/09/i2c-hello-world.html
+    /// Set I2C address, see [`LcdConfig::with_address`].
     pub fn with_address(mut self, address: u8) -> Self {
-        self.address = address;
+        self.config = self.config.with_address(address);
         self
     }
 
+    /// Set the backlight state the display comes up with, see [`LcdConfig::with_backlight`].
+    ///
+    /// The backlight stays in this state during the whole init sequence.
+    pub fn with_backlight(mut self, backlight: Backlight) -> Self {
+        self.config = self.config.with_backlight(backlight);
+        self
+    }
+
+    /// Set the font used from init on, see [`LcdConfig::with_font`].
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.config = self.config.with_font(font);
+        self
+    }
+
+    /// Override the DDRAM address of the first column of each row, see
+    /// [`LcdConfig::with_row_offsets`].
+    pub fn with_row_offsets(mut self, offsets: [u8; 4]) -> Self {
+        self.config = self.config.with_row_offsets(offsets);
+        self
+    }
+
+    /// See [`LcdConfig::with_pin_mapping`].
+    pub fn with_pin_mapping(mut self, pins: PinMapping) -> Self {
+        self.config = self.config.with_pin_mapping(pins);
+        self
+    }
+
+    /// See [`LcdConfig::with_backlight_polarity`].
+    pub fn with_backlight_polarity(mut self, polarity: Polarity) -> Self {
+        self.config = self.config.with_backlight_polarity(polarity);
+        self
+    }
+
+    /// See [`LcdConfig::with_cursor_on`].
     pub fn with_cursor_on(mut self, on: bool) -> Self {
-        self.cursor_on = on;
+        self.config = self.config.with_cursor_on(on);
         self
     }
 
+    /// See [`LcdConfig::with_cursor_blink`].
     pub fn with_cursor_blink(mut self, blink: bool) -> Self {
-        self.cursor_blink = blink;
+        self.config = self.config.with_cursor_blink(blink);
+        self
+    }
+
+    /// See [`LcdConfig::with_newline_mode`].
+    pub fn with_newline_mode(mut self, mode: NewlineMode) -> Self {
+        self.config = self.config.with_newline_mode(mode);
+        self
+    }
+
+    /// See [`LcdConfig::with_clamp_regions`].
+    pub fn with_clamp_regions(mut self, clamp: bool) -> Self {
+        self.config = self.config.with_clamp_regions(clamp);
+        self
+    }
+
+    /// See [`LcdConfig::with_tab_width`].
+    pub fn with_tab_width(mut self, width: u8) -> Self {
+        self.config = self.config.with_tab_width(width);
+        self
+    }
+
+    /// See [`LcdConfig::with_overflow_policy`].
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.config = self.config.with_overflow_policy(policy);
+        self
+    }
+
+    /// See [`LcdConfig::with_unmappable_policy`].
+    pub fn with_unmappable_policy(mut self, policy: UnmappablePolicy) -> Self {
+        self.config = self.config.with_unmappable_policy(policy);
+        self
+    }
+
+    /// See [`LcdConfig::with_charset`].
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.config = self.config.with_charset(charset);
+        self
+    }
+
+    /// See [`LcdConfig::with_controller`].
+    pub fn with_controller(mut self, controller: Controller) -> Self {
+        self.config = self.config.with_controller(controller);
+        self
+    }
+
+    /// See [`LcdConfig::with_split_row`].
+    pub fn with_split_row(mut self, split: bool) -> Self {
+        self.config = self.config.with_split_row(split);
+        self
+    }
+
+    /// See [`LcdConfig::with_char_mapper`].
+    pub fn with_char_mapper(mut self, mapper: &'static dyn CharMapper) -> Self {
+        self.config = self.config.with_char_mapper(mapper);
+        self
+    }
+
+    /// See [`LcdConfig::with_busy_flag_polling`].
+    pub fn with_busy_flag_polling(mut self, poll: bool) -> Self {
+        self.config = self.config.with_busy_flag_polling(poll);
+        self
+    }
+
+    /// See [`LcdConfig::with_timings`].
+    pub fn with_timings(mut self, timings: Timings) -> Self {
+        self.config = self.config.with_timings(timings);
+        self
+    }
+
+    /// See [`LcdConfig::with_i2c_retries`].
+    pub fn with_i2c_retries(mut self, retries: u8) -> Self {
+        self.config = self.config.with_i2c_retries(retries);
+        self
+    }
+
+    /// See [`LcdConfig::with_i2c_transfer_limit`].
+    pub fn with_i2c_transfer_limit(mut self, bytes: u8) -> Self {
+        self.config = self.config.with_i2c_transfer_limit(bytes);
         self
     }
 
+    /// Return the I2C and delay instance without initializing the display.
+    pub fn into_parts(self) -> (I, D) {
+        (self.i2c, self.delay)
+    }
+
+    /// Check if a display answers at the configured address, e.g. to skip the display on boards
+    /// without one.
+    ///
+    /// Only the backlight state is written, so the controller isn't affected. Returns `false` if the
+    /// address isn't acknowledged and [`Error::I2c`] for other bus errors. The address is checked
+    /// like in [`init`](Self::init).
+    pub fn probe(&mut self) -> Result<bool, Error<I::Error>> {
+        check_address(self.config.address)?;
+        self.probe_address(self.config.address)
+    }
+
+    /// Probe the `candidates` in order, see [`probe`](Self::probe), and use the first address
+    /// that is acknowledged.
+    ///
+    /// Returns `None` and keeps the configured address if no candidate answers. Reserved addresses
+    /// in `candidates` are rejected like in [`init`](Self::init).
+    ///
+    /// ```ignore
+    /// let mut builder = Lcd::new(i2c, delay);
+    /// if builder.detect_address(&BACKPACK_ADDRESSES)?.is_some() {
+    ///     let lcd = builder.init()?;
+    /// }
+    /// ```
+    pub fn detect_address(&mut self, candidates: &[u8]) -> Result<Option<u8>, Error<I::Error>> {
+        for &address in candidates {
+            check_address(address)?;
+            if self.probe_address(address)? {
+                self.config.address = address;
+                return Ok(Some(address));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write the backlight state to `address`, returning whether it was acknowledged.
+    fn probe_address(&mut self, address: u8) -> Result<bool, Error<I::Error>> {
+        acknowledged(self.i2c.write(address, &[self.config.probe_byte()]))
+    }
+
     /// Initializes the hardware.
     ///
     /// Actual procedure is a bit obscure. This one was compiled from this [blog post],
@@ -75,188 +354,1019 @@ where
     /// [datasheet]: https://www.openhacks.com/uploadsproductos/eone-1602a1.pdf
     /// [code]: https://github.com/jalhadi/i2c-hello-world/blob/main/src/main.rs
     /// [blog post]: https://badboi.dev/rust,/microcontrollers/2020/11/09/i2c-hello-world.html
-    pub fn init(mut self) -> Result<Self, I::Error> {
-        // Initial delay to wait for init after power on.
-        self.delay.delay_ms(80);
-
-        self.backlight(self.backlight_state)?;
-
-        self.delay.delay_ms(1);
-
-        // Init with 8 bit mode
-        let mode_8bit = Mode::FunctionSet as u8 | BitMode::Bit8 as u8;
-        self.write4bits(mode_8bit)?;
-        self.delay.delay_ms(5);
-        self.write4bits(mode_8bit)?;
-        self.delay.delay_ms(5);
-        self.write4bits(mode_8bit)?;
-        self.delay.delay_ms(5);
-
-        // Switch to 4 bit mode
-        let mode_4bit = Mode::FunctionSet as u8 | BitMode::Bit4 as u8;
-        self.write4bits(mode_4bit)?;
-
-        self.update_function_set()?;
-
-        self.update_display_control()?;
-        self.command(Mode::Cmd as u8 | Commands::Clear as u8)?; // Clear Display
-
-        self.delay.delay_ms(2);
-
-        // Entry right: shifting cursor moves to right
-        self.command(
-            Mode::EntrySet as u8 | CursorMoveDir::Left as u8 | DisplayShift::Decrement as u8,
-        )?;
-        self.return_home()?;
-        Ok(self)
-    }
-
-    fn write4bits(&mut self, data: u8) -> Result<(), I::Error> {
-        self.i2c.write(
-            self.address,
-            &[data | DisplayControl::Off as u8 | self.backlight_state as u8],
-        )?;
-        self.i2c.write(
-            self.address,
-            &[data | DisplayControl::DisplayOn as u8 | self.backlight_state as u8],
-        )?;
-        self.i2c.write(
-            self.address,
-            &[DisplayControl::Off as u8 | self.backlight_state as u8],
-        )?;
-        self.delay.delay_us(700);
+    pub fn init(self) -> Result<GenericLcd<G, Pcf8574<I>, D>, Error<I::Error>> {
+        let mut lcd = self.into_lcd()?;
+        lcd.init_sequence(true, true)?;
+        Ok(lcd)
+    }
+
+    /// Initializes the hardware of a display that stayed powered, e.g. after a reset of the
+    /// microcontroller only.
+    ///
+    /// The power-on delay is skipped. If `in_4bit_mode` is `true`, the three function sets
+    /// bringing the controller into a known state are skipped too. The display is still set up
+    /// like with [`init`](Self::init).
+    ///
+    /// Only use this if the display is known to be powered and initialized, e.g. because it has
+    /// its own supply. Otherwise the display shows garbage or nothing at all. If the
+    /// microcontroller was reset in the middle of a byte, the controller is out of sync in 4 bit
+    /// mode, so `in_4bit_mode` is only safe if that can't happen.
+    pub fn init_warm(
+        self,
+        in_4bit_mode: bool,
+    ) -> Result<GenericLcd<G, Pcf8574<I>, D>, Error<I::Error>> {
+        let mut lcd = self.into_lcd()?;
+        lcd.init_sequence(false, !in_4bit_mode)?;
+        Ok(lcd)
+    }
+
+    /// Check the expander settings and create the driver talking to it.
+    fn into_lcd(self) -> Result<GenericLcd<G, Pcf8574<I>, D>, Error<I::Error>> {
+        check_address(self.config.address)?;
+        if !self.config.pins.is_valid() {
+            return Err(Error::InvalidPinMapping);
+        }
+        let interface = Pcf8574::new(self.i2c, &self.config);
+        Ok(GenericLcd::with_geometry(
+            interface,
+            self.delay,
+            self.geometry,
+            self.config,
+        ))
+    }
+}
+
+impl<const ROWS: u8, const COLUMNS: u8, I, D> GenericLcd<Fixed<ROWS, COLUMNS>, I, D>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    /// Initialize a display wired through `interface` instead of the PCF8574 of `Lcd::new`.
+    ///
+    /// The address, pin mapping, backlight polarity and I2C retries of `config` are left to the
+    /// interface.
+    pub fn init_with_interface(
+        interface: I,
+        delay: D,
+        config: LcdConfig,
+    ) -> Result<Self, Error<I::Error>> {
+        let mut lcd = Self::with_geometry(interface, delay, Fixed::new(), config);
+        lcd.init_sequence(true, true)?;
+        Ok(lcd)
+    }
+}
+
+impl<I, D> GenericLcd<Dynamic, I, D>
+where
+    I: LcdInterface,
+    D: DelayNs,
+{
+    /// Initialize a display with `rows` and `columns` wired through `interface`, see
+    /// [`init_with_interface`](GenericLcd::init_with_interface) of the fixed size driver.
+    ///
+    /// Returns [`Error::InvalidGeometry`] if the size isn't supported.
+    pub fn init_with_interface(
+        interface: I,
+        delay: D,
+        rows: u8,
+        columns: u8,
+        config: LcdConfig,
+    ) -> Result<Self, Error<I::Error>> {
+        let geometry = Dynamic::new(rows, columns).ok_or(Error::InvalidGeometry)?;
+        let mut lcd = Self::with_geometry(interface, delay, geometry, config);
+        lcd.init_sequence(true, true)?;
+        Ok(lcd)
+    }
+}
+
+impl<G, I, D> GenericLcd<G, Pcf8574<I>, D>
+where
+    G: Geometry,
+    I: I2c,
+    D: DelayNs,
+{
+    /// Consume the driver and return the I2C and delay instance.
+    pub fn into_parts(self) -> (I, D) {
+        (self.interface.into_inner(), self.delay)
+    }
+
+    /// Switch to the [`QueuedLcd`] driver queueing up to `N` expander writes, returned with the
+    /// delay instance it doesn't need.
+    ///
+    /// [`QueuedLcd::into_blocking`] switches back, busy flag polling is turned off then.
+    /// Displays with two controllers, see [`Dual`], aren't supported by the queued driver:
+    ///
+    /// ```compile_fail
+    /// # use embedded_hal::{delay::DelayNs, i2c::I2c};
+    /// # use lcd_lcm1602_i2c::{geometry::Dual, interface::Pcf8574, sync_lcd::GenericLcd};
+    /// fn queue<I: I2c, D: DelayNs>(lcd: GenericLcd<Dual<40>, Pcf8574<I>, D>) {
+    ///     let _ = lcd.into_queued::<64>();
+    /// }
+    /// ```
+    pub fn into_queued<const N: usize>(self) -> (QueuedLcd<G, I, N>, D)
+    where
+        G: SingleController,
+    {
+        (QueuedLcd::new(self.interface, self.state), self.delay)
+    }
+
+    /// Turn off the display and the backlight, then return the I2C and delay instance.
+    ///
+    /// The contents of the display are kept by the controller.
+    pub fn release_with_shutdown(mut self) -> Result<(I, D), Error<I::Error>> {
+        self.command(Mode::DisplayControl as u8 | DisplayControl::Off as u8)?;
+        self.backlight(Backlight::Off)?;
+        Ok(self.into_parts())
+    }
+
+    /// Number of failed I2C transfers that were retried since the driver was created.
+    pub fn i2c_retry_count(&self) -> u32 {
+        self.interface.retry_count()
+    }
+
+    /// I2C address of the display.
+    pub fn address(&self) -> u8 {
+        self.interface.address()
+    }
+
+    /// Send everything from now on to the display at `address`, e.g. to drive several identical
+    /// displays strapped to different addresses with one driver.
+    ///
+    /// Only the address is switched. The cursor, the contents used for scrolling, the installed
+    /// glyphs and the backlight state are still tracked for a single display, so set the cursor
+    /// before writing after a switch and run [`re_init`](Self::re_init) once for each display.
+    /// Addresses are checked like in `init()`, an invalid address keeps the current one.
+    pub fn set_address(&mut self, address: u8) -> Result<(), Error<I::Error>> {
+        check_address(address)?;
+        self.interface.set_address(address);
+        self.state.config.address = address;
         Ok(())
     }
 
-    fn send(&mut self, data: u8, mode: Mode) -> Result<(), I::Error> {
-        let high_bits: u8 = data & 0xf0;
-        let low_bits: u8 = (data << 4) & 0xf0;
-        self.write4bits(high_bits | mode as u8)?;
-        self.write4bits(low_bits | mode as u8)?;
+    #[cfg(feature = "std")]
+    pub(crate) fn i2c(&self) -> &I {
+        self.interface.i2c()
+    }
+}
+
+impl<G, I, D> GenericLcd<G, St7032iInterface<I>, D>
+where
+    G: Geometry,
+    I: I2c,
+    D: DelayNs,
+{
+    /// Set the contrast of the display, `level` is in `0..64`.
+    ///
+    /// Returns [`Error::InvalidContrast`] for other levels.
+    pub fn set_contrast(&mut self, level: u8) -> Result<(), Error<I::Error>> {
+        if level >= 64 {
+            return Err(Error::InvalidContrast);
+        }
+        let function_set = self.state.function_set();
+        for command in self.interface.set_contrast(level, function_set) {
+            self.command(command)?;
+        }
         Ok(())
     }
 
-    fn command(&mut self, data: u8) -> Result<(), I::Error> {
-        self.send(data, Mode::Cmd)
+    /// Contrast of the display, `0..64`.
+    pub fn contrast(&self) -> u8 {
+        self.interface.contrast()
+    }
+}
+
+impl<G, I, D> GenericLcd<G, Us2066Interface<I>, D>
+where
+    G: Geometry,
+    I: I2c,
+    D: DelayNs,
+{
+    /// Set the contrast of the display.
+    pub fn set_contrast(&mut self, level: u8) -> Result<(), Error<I::Error>> {
+        let function_set = self.state.function_set();
+        for command in self.interface.set_contrast(level, function_set) {
+            self.command(command)?;
+        }
+        Ok(())
     }
 
-    pub fn backlight(&mut self, backlight: Backlight) -> Result<(), I::Error> {
-        self.backlight_state = backlight;
-        self.i2c
-            .write(self.address, &[DisplayControl::Off as u8 | backlight as u8])
+    /// Contrast of the display.
+    pub fn contrast(&self) -> u8 {
+        self.interface.contrast()
     }
+}
 
-    /// Write string to display.
-    pub fn write_str(&mut self, data: &str) -> Result<(), I::Error> {
-        for c in data.chars() {
-            self.send(c as u8, Mode::Data)?;
+impl<G, I, D> GenericLcd<G, I, D>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+{
+    fn with_geometry(interface: I, delay: D, geometry: G, config: LcdConfig) -> Self {
+        Self::from_state(interface, delay, State::new(geometry, config))
+    }
+
+    pub(crate) fn from_state(interface: I, delay: D, mut state: State<G>) -> Self {
+        state.poll_busy_flag = false;
+        Self {
+            interface,
+            delay,
+            state,
+            last_error: None,
+        }
+    }
+
+    /// Consume the driver and return the interface and delay instance.
+    pub fn into_interface(self) -> (I, D) {
+        (self.interface, self.delay)
+    }
+
+    /// Take the error of the last failed write through [`core::fmt::Write`].
+    ///
+    /// `fmt::Write` can only report [`fmt::Error`], the actual error is stored until taken.
+    pub fn take_last_error(&mut self) -> Option<Error<I::Error>> {
+        self.last_error.take()
+    }
+
+    /// Current state of the backlight.
+    pub fn backlight_state(&self) -> Backlight {
+        self.state.config.backlight
+    }
+
+    /// Whether the cursor is visible.
+    pub fn is_cursor_on(&self) -> bool {
+        self.state.config.cursor_on
+    }
+
+    /// Whether the cursor is blinking.
+    pub fn is_cursor_blinking(&self) -> bool {
+        self.state.config.cursor_blink
+    }
+
+    /// Font currently used.
+    pub fn font(&self) -> Font {
+        self.state.config.font
+    }
+
+    /// Number of rows and columns.
+    #[cfg(feature = "std")]
+    pub(crate) fn size(&self) -> (u8, u8) {
+        (self.state.rows(), self.state.columns())
+    }
+
+    /// What the driver knows about the display.
+    #[cfg(feature = "buffered")]
+    pub(crate) fn state(&self) -> &State<G> {
+        &self.state
+    }
+
+    /// Run the init sequence again, e.g. to recover the display after a power glitch.
+    ///
+    /// The backlight, cursor, font and text direction are restored, the display is cleared.
+    pub fn re_init(&mut self) -> Result<(), Error<I::Error>> {
+        self.init_sequence(true, true)
+    }
+
+    /// Initialize a driver stored in a field in place, the same as [`re_init`](Self::re_init).
+    ///
+    /// Driver fields can be initialized in place like this, but the first init takes the
+    /// builder:
+    ///
+    /// ```compile_fail
+    /// # use embedded_hal::{delay::DelayNs, i2c::I2c};
+    /// # use lcd_lcm1602_i2c::sync_lcd::Lcd;
+    /// fn init<I: I2c, D: DelayNs>(i2c: I, delay: D) {
+    ///     let mut lcd = Lcd::<2, 16, I, D>::new(i2c, delay);
+    ///     lcd.init_in_place();
+    /// }
+    /// ```
+    pub fn init_in_place(&mut self) -> Result<(), Error<I::Error>> {
+        self.re_init()
+    }
+
+    /// Set up the display, see [`Init`].
+    fn init_sequence(&mut self, power_on: bool, sync: bool) -> Result<(), Error<I::Error>> {
+        self.state.bit_mode = self.interface.bit_mode();
+        self.run(Init::new(power_on, sync))
+    }
+
+    /// Carry out the ops of `plan` until it's done.
+    fn run<'a, P: Plan<'a, G>>(&mut self, mut plan: P) -> Result<P::Output, Error<I::Error>> {
+        let mut outcome = Outcome::Done;
+        loop {
+            let step = plan.step(&mut self.state, outcome);
+            // Only once the plan recorded the op the status read came after.
+            if core::mem::take(&mut self.state.address_lost) {
+                self.execute(restore_address(&self.state))?;
+            }
+            let result = match step {
+                Ok(Step::Op(op)) => self.execute(op),
+                Ok(Step::Done(output)) => return Ok(output),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(next) => outcome = next,
+                Err(e) => {
+                    if core::mem::take(&mut self.state.address_moved) {
+                        // The error of the read is the one worth returning.
+                        let _ = self.execute(restore_address(&self.state));
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn execute(&mut self, op: Op<'_>) -> Result<Outcome, Error<I::Error>> {
+        match op {
+            Op::Delay(us) => self.delay.delay_us(us),
+            Op::DelayMs(ms) => self.delay.delay_ms(ms),
+            Op::Init => self.interface.init(&mut self.delay).map_err(Error::I2c)?,
+            Op::Select(controllers) => self.interface.select_controllers(controllers),
+            Op::Backlight(on) => self.interface.set_backlight(on).map_err(Error::I2c)?,
+            Op::Nibble { rs, nibble } => self.write_nibble(rs, nibble)?,
+            Op::Write { rs, byte } => self.write_byte(rs, byte)?,
+            Op::Send {
+                controllers,
+                rs,
+                byte,
+                wait_us,
+            } => {
+                // Both nibbles at once, e.g. in one I2C transaction, the wait follows the whole
+                // byte.
+                self.interface.select_controllers(controllers);
+                self.write_byte(rs, byte)?;
+                self.wait_ready(wait_us)?;
+            }
+            Op::Batch {
+                controllers,
+                rs,
+                bytes,
+            } => {
+                let backlight = self.state.backlight_on();
+                self.interface.select_controllers(controllers);
+                let bytes = bytes.as_slice();
+                let written = match rs {
+                    true => self.interface.write_data(bytes, backlight, &mut self.delay),
+                    false => self
+                        .interface
+                        .write_commands(bytes, backlight, &mut self.delay),
+                };
+                return written.map(Outcome::Sent).map_err(Error::I2c);
+            }
+            Op::Wait(us) => self.wait_ready(us)?,
+            Op::Read { rs } => return self.read_byte(rs).map(Outcome::Read),
+            Op::PollReady => return self.poll_ready().map(Outcome::Ready),
+        }
+        Ok(Outcome::Done)
+    }
+
+    /// Write the lower four bits of `nibble` to D4 to D7 with RS set to `rs` and RW low,
+    /// without waiting for the controller to process them.
+    fn write_nibble(&mut self, rs: bool, nibble: u8) -> Result<(), Error<I::Error>> {
+        let backlight = self.state.backlight_on();
+        self.interface
+            .write_nibble(rs, nibble, backlight, &mut self.delay)
+            .map_err(Error::I2c)
+    }
+
+    /// Write `byte` to D0 to D7 with RS set to `rs` and RW low in 8 bit mode, or as two
+    /// nibbles in 4 bit mode, without waiting for the controller to execute it.
+    fn write_byte(&mut self, rs: bool, byte: u8) -> Result<(), Error<I::Error>> {
+        let backlight = self.state.backlight_on();
+        self.interface
+            .write_byte(rs, byte, backlight, &mut self.delay)
+            .map_err(Error::I2c)
+    }
+
+    /// Read four bits from D4 to D7 with RS set to `rs` and RW high, returns
+    /// [`Error::ReadUnavailable`] if the interface can't read.
+    fn read_nibble(&mut self, rs: bool) -> Result<u8, Error<I::Error>> {
+        let backlight = self.state.backlight_on();
+        self.interface
+            .read_nibble(rs, backlight, &mut self.delay)
+            .map_err(Error::I2c)?
+            .ok_or(Error::ReadUnavailable)
+    }
+
+    /// Read a byte from the controller, the status with `rs` low or the RAM at the address
+    /// counter with `rs` high.
+    fn read_byte(&mut self, rs: bool) -> Result<u8, Error<I::Error>> {
+        if self.state.bit_mode == BitMode::Bit8 {
+            // None of the interfaces with eight data lines can read.
+            return Err(Error::ReadUnavailable);
+        }
+        if self.state.all_controllers() != 1 {
+            // RW is the enable pin of the second controller.
+            return Err(Error::ReadUnavailable);
         }
+        let high_bits = self.read_nibble(rs)?;
+        let low_bits = self.read_nibble(rs)?;
+        Ok(high_bits << 4 | low_bits)
+    }
+
+    /// Wait until the controller is done with the last instruction. Without busy flag polling, or
+    /// if the busy flag doesn't clear in time, wait `fallback_us` instead.
+    fn wait_ready(&mut self, fallback_us: u32) -> Result<(), Error<I::Error>> {
+        if self.state.poll_busy_flag {
+            match self.poll_ready() {
+                Ok(Some(_)) => return Ok(()),
+                // The interface can't read or the busy flag is stuck, probably because RW isn't
+                // connected.
+                Ok(None) | Err(Error::ReadUnavailable) => self.state.poll_busy_flag = false,
+                Err(e) => return Err(e),
+            }
+        }
+        self.delay.delay_us(fallback_us);
         Ok(())
     }
 
+    /// Poll the busy flag (bit 7 of the status) until the controller is ready, returning the
+    /// address counter (bits 0 to 6) or `None` if the busy flag doesn't clear in time.
+    fn poll_ready(&mut self) -> Result<Option<u8>, Error<I::Error>> {
+        for _ in 0..BUSY_POLLS {
+            let status = self.read_byte(false)?;
+            if status == RW_GROUNDED {
+                // The controller took the read as an instruction, don't keep sending it.
+                self.state.poll_busy_flag = false;
+                self.state.address_lost = true;
+                return Ok(None);
+            }
+            if status & 0x80 == 0 {
+                return Ok(Some(status));
+            }
+            self.delay.delay_us(BUSY_POLL_DELAY);
+        }
+        Ok(None)
+    }
+
+    fn command(&mut self, data: u8) -> Result<(), Error<I::Error>> {
+        self.run(Send::command(data))
+    }
+
+    /// Turn the backlight off if it's on and on if it's off.
+    pub fn toggle_backlight(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(SetBacklight::toggle())
+    }
+
+    pub fn backlight(&mut self, backlight: Backlight) -> Result<(), Error<I::Error>> {
+        self.run(SetBacklight::new(backlight))
+    }
+
+    /// Flash the backlight `times` times, turning it off for `off_ms` and on for `on_ms`.
+    ///
+    /// Only the backlight bit of the expander is changed, the controller isn't touched. The
+    /// backlight is returned to its previous state afterwards.
+    pub fn flash_backlight(
+        &mut self,
+        times: u8,
+        on_ms: u32,
+        off_ms: u32,
+    ) -> Result<(), Error<I::Error>> {
+        self.run(FlashBacklight::new(times, on_ms, off_ms))
+    }
+
+    /// Send an arbitrary command byte (RS low) to the controller.
+    ///
+    /// This is an escape hatch for commands the driver doesn't support. The driver doesn't know
+    /// what the command does, so its cursor tracking and other state may be off afterwards.
+    pub fn raw_command(&mut self, byte: u8) -> Result<(), Error<I::Error>> {
+        self.command(byte)
+    }
+
+    /// Send an arbitrary data byte (RS high) to the controller, see
+    /// [`raw_command`](Self::raw_command).
+    pub fn raw_data(&mut self, byte: u8) -> Result<(), Error<I::Error>> {
+        self.run(Send::data(byte))
+    }
+
+    /// Write string to display.
+    ///
+    /// `'\n'` moves the cursor to the start of the next row and `'\r'` to the start of the
+    /// current row. On the last row, a newline behaves according to the [`NewlineMode`]. `'\t'`
+    /// moves the cursor to the next tab stop, see [`with_tab_width`](GenericLcdBuilder::with_tab_width).
+    ///
+    /// Text reaching past the end of a row is handled according to the [`OverflowPolicy`].
+    pub fn write_str(&mut self, data: &str) -> Result<(), Error<I::Error>> {
+        self.run(Text::new(data.char_indices())).map(|_| ())
+    }
+
+    /// Set the cursor to (row, col) and write the string from there.
+    ///
+    /// By default, the string is truncated at the end of the row, see [`OverflowPolicy`].
+    /// Returns the number of characters written.
+    pub fn print_at(&mut self, row: u8, col: u8, s: &str) -> Result<u8, Error<I::Error>> {
+        self.set_cursor(row, col)?;
+        self.run(Text::new(s.char_indices()))
+    }
+
+    /// Write the string to `row`, filling the rest of the row with spaces.
+    ///
+    /// The string is truncated if it's longer than the row, so exactly one row of characters are
+    /// written. Control characters like `'\n'` aren't interpreted, the [`UnmappablePolicy`]
+    /// applies to them. The cursor is left behind the last column of the row.
+    pub fn write_line(&mut self, row: u8, s: &str) -> Result<(), Error<I::Error>> {
+        self.run(Row::new(row, s, Align::Left))
+    }
+
+    /// Write the string centered on `row`, filling the rest of the row with spaces.
+    ///
+    /// The string is truncated if it's longer than the row, like with
+    /// [`write_line`](Self::write_line).
+    pub fn write_str_centered(&mut self, row: u8, s: &str) -> Result<(), Error<I::Error>> {
+        self.run(Row::new(row, s, Align::Center))
+    }
+
+    /// Write the string right-aligned on `row`, filling the rest of the row with spaces.
+    ///
+    /// The string is truncated if it's longer than the row, like with
+    /// [`write_line`](Self::write_line).
+    pub fn write_str_right(&mut self, row: u8, s: &str) -> Result<(), Error<I::Error>> {
+        self.run(Row::new(row, s, Align::Right))
+    }
+
+    /// Write `text` starting at the beginning of `start_row`, breaking lines at whitespace.
+    ///
+    /// Words longer than a row are broken at the end of the row and whitespace at the start of a
+    /// continued row is skipped. When the text needs more rows than are left, the
+    /// [`OverflowPolicy`] applies. Returns the number of rows written to.
+    pub fn write_wrapped(&mut self, start_row: u8, text: &str) -> Result<u8, Error<I::Error>> {
+        self.run(Wrapped::new(start_row, text))
+    }
+
+    /// Write a number to the display, returning the number of characters written.
+    ///
+    /// The [`OverflowPolicy`] applies like for [`write_str`](Self::write_str).
+    pub fn write_u32(&mut self, value: u32) -> Result<u8, Error<I::Error>> {
+        let mut buf = [0; NUMBER_LEN];
+        self.run(number(0, format_u32(value, &mut buf)))
+    }
+
+    /// Write a number to the display, returning the number of characters written.
+    ///
+    /// The [`OverflowPolicy`] applies like for [`write_str`](Self::write_str).
+    pub fn write_i32(&mut self, value: i32) -> Result<u8, Error<I::Error>> {
+        let mut buf = [0; NUMBER_LEN];
+        self.run(number(0, format_i32(value, &mut buf)))
+    }
+
+    /// Write `value / 10^decimals` with a decimal point, e.g. `write_fixed(-2347, 2)` writes
+    /// `-23.47`. Returns the number of characters written.
+    ///
+    /// No floating point is used and the [`OverflowPolicy`] applies. Returns
+    /// [`Error::InvalidDecimals`] if `decimals` is more than 10, all digits of an `i32`.
+    pub fn write_fixed(&mut self, value: i32, decimals: u8) -> Result<u8, Error<I::Error>> {
+        self.write_fixed_width(value, decimals, 0)
+    }
+
+    /// Like [`write_fixed`](Self::write_fixed), but pads the number with spaces on the left to
+    /// at least `width` characters.
+    pub fn write_fixed_width(
+        &mut self,
+        value: i32,
+        decimals: u8,
+        width: u8,
+    ) -> Result<u8, Error<I::Error>> {
+        let mut buf = [0; FIXED_LEN];
+        let digits = format_fixed(value, decimals, &mut buf).ok_or(Error::InvalidDecimals)?;
+        let padding = width.saturating_sub(digits.len() as u8);
+        self.run(number(padding, digits))
+    }
+
+    /// See [`write_u32`](Self::write_u32).
+    pub fn write_u16(&mut self, value: u16) -> Result<u8, Error<I::Error>> {
+        self.write_u32(value.into())
+    }
+
+    /// See [`write_u32`](Self::write_u32).
+    pub fn write_u8(&mut self, value: u8) -> Result<u8, Error<I::Error>> {
+        self.write_u32(value.into())
+    }
+
+    /// See [`write_i32`](Self::write_i32).
+    pub fn write_i16(&mut self, value: i16) -> Result<u8, Error<I::Error>> {
+        self.write_i32(value.into())
+    }
+
+    /// See [`write_i32`](Self::write_i32).
+    pub fn write_i8(&mut self, value: i8) -> Result<u8, Error<I::Error>> {
+        self.write_i32(value.into())
+    }
+
+    /// Write any [`uDisplay`] value to the display, like `uwrite!(lcd, "{}", value)`.
+    pub fn write_udisplay<T: uDisplay + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error<I::Error>> {
+        value.fmt(&mut Formatter::new(self))
+    }
+
+    /// Write any [`uDebug`] value to the display, like `uwrite!(lcd, "{:?}", value)`.
+    pub fn write_udebug<T: uDebug + ?Sized>(&mut self, value: &T) -> Result<(), Error<I::Error>> {
+        value.fmt(&mut Formatter::new(self))
+    }
+
+    /// Write a single character to the display.
+    ///
+    /// The character is handled the same way as by [`write_str`](Self::write_str).
+    pub fn write_char(&mut self, c: char) -> Result<(), Error<I::Error>> {
+        self.run(PutChar::new(c, 0)).map(|_| ())
+    }
+
+    /// Check if the display can show `c` with the installed [`CharMapper`] and the [`Charset`],
+    /// i.e. it isn't handled by the [`UnmappablePolicy`].
+    pub fn supports_char(&self, c: char) -> bool {
+        self.state.translate(c).is_some()
+    }
+
+    /// Check if the display can show all characters of `s`, see
+    /// [`supports_char`](Self::supports_char).
+    pub fn validate_str(&self, s: &str) -> Result<(), UnmappableChar> {
+        self.state.validate_str(s)
+    }
+
+    /// Write a single raw byte to the display, see [`write_bytes`](Self::write_bytes).
+    pub fn write_raw_char(&mut self, byte: u8) -> Result<(), Error<I::Error>> {
+        self.run(Send::data(byte))
+    }
+
+    /// Write raw bytes to the display.
+    ///
+    /// Every byte is sent as is, without any character handling. Use this for custom characters
+    /// (`0..8`) or characters of the display's character ROM. Interfaces that can, like the
+    /// [`Aip31068Interface`](crate::interface::Aip31068Interface), send them in one transfer.
+    /// The PCF8574 sends them in transactions of up to 16 characters.
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error<I::Error>> {
+        self.run(Batch::data(Bytes::Borrowed(data)))
+    }
+
+    /// Store a custom character in CGRAM slot `location`.
+    ///
+    /// `charmap` has one byte per row of the character, only the lower five bits are used.
+    /// Returns [`Error::InvalidCgramSlot`] if `location` is not in `0..8`. The cursor stays
+    /// where it was.
+    pub fn create_char(&mut self, location: u8, charmap: &[u8; 8]) -> Result<(), Error<I::Error>> {
+        let address = self.state.cgram_address(location)?;
+        self.write_cgram(address, charmap)
+    }
+
+    /// Install a glyph in a free CGRAM slot, sharing the slot if the same glyph is installed
+    /// already.
+    ///
+    /// Returns [`Error::CgramFull`] if all slots are taken. Slots written with
+    /// [`create_char`](Self::create_char) aren't tracked and may be overwritten. A slot shared
+    /// by `u8::MAX` handles is never freed.
+    ///
+    /// Glyphs have the eight rows of [`Font::Font5x8`], with [`Font::Font5x10`]
+    /// [`Error::WrongFont`] is returned, see [`create_char_5x10`](Self::create_char_5x10).
+    pub fn install_glyph(&mut self, bitmap: &[u8; 8]) -> Result<GlyphHandle, Error<I::Error>> {
+        self.state.check_glyph_font()?;
+        let (handle, new) = self.state.glyphs.allocate(bitmap).ok_or(Error::CgramFull)?;
+        if new {
+            if let Err(e) = self.create_char(handle.slot(), bitmap) {
+                self.state.glyphs.free(handle);
+                return Err(e);
+            }
+        }
+        Ok(handle)
+    }
+
+    /// Write a glyph installed with [`install_glyph`](Self::install_glyph).
+    ///
+    /// Returns [`Error::WrongFont`] if the font was switched to [`Font::Font5x10`] since, its
+    /// slots don't hold the glyph.
+    pub fn write_glyph(&mut self, glyph: &GlyphHandle) -> Result<(), Error<I::Error>> {
+        self.state.check_glyph_font()?;
+        self.run(Send::data(glyph.slot()))
+    }
+
+    /// Give back a glyph, its slot is freed once no other handle uses it.
+    pub fn uninstall_glyph(&mut self, glyph: GlyphHandle) {
+        self.state.glyphs.free(glyph);
+    }
+
+    /// Store a custom character for [`Font::Font5x10`] in CGRAM slot `location`.
+    ///
+    /// With the tall font, there are only four slots with eleven rows each, `0..4`. Write the
+    /// character with [`write_custom_char`](Self::write_custom_char), it sends the code of the
+    /// slot, `location * 2`. Returns [`Error::InvalidCgramSlot`] if `location` is not in `0..4`
+    /// and [`Error::WrongFont`] if the display doesn't use the tall font. The cursor stays where
+    /// it was.
+    pub fn create_char_5x10(
+        &mut self,
+        location: u8,
+        charmap: &[u8; 11],
+    ) -> Result<(), Error<I::Error>> {
+        let address = self.state.tall_cgram_address(location)?;
+        self.write_cgram(address, charmap)
+    }
+
+    /// Read the custom character in CGRAM slot `location` back from the controller.
+    ///
+    /// Returns [`Error::InvalidCgramSlot`] if `location` is not in `0..8`. The cursor stays where
+    /// it was. Needs the RW pin of the display connected to the expander, returns
+    /// [`Error::ReadUnavailable`] otherwise.
+    pub fn read_cgram(&mut self, location: u8, buf: &mut [u8; 8]) -> Result<(), Error<I::Error>> {
+        let address = self.state.cgram_address(location)?;
+        self.run(ReadRam::cgram(address, buf))
+    }
+
+    /// Write `rows` to CGRAM starting at `address`, then return to the DDRAM address.
+    ///
+    /// The DDRAM address is restored from the tracked cursor even if the upload failed, so text
+    /// written afterwards doesn't end up in CGRAM.
+    fn write_cgram(&mut self, address: u8, rows: &[u8]) -> Result<(), Error<I::Error>> {
+        let result = self.run(Upload::new(address, rows));
+        let restored = self.run(SetAddress::new(self.state.address_counter));
+        result.and(restored)
+    }
+
+    /// Write the custom character stored in slot `index`.
+    ///
+    /// Returns [`Error::InvalidCgramSlot`] if `index` is not in `0..8`, or not in `0..4` with
+    /// [`Font::Font5x10`], see [`create_char_5x10`](Self::create_char_5x10).
+    pub fn write_custom_char(&mut self, index: u8) -> Result<(), Error<I::Error>> {
+        let code = self
+            .state
+            .custom_char_code(index)
+            .ok_or(Error::InvalidCgramSlot)?;
+        self.run(Send::data(code))
+    }
+
     /// Clear the display
-    pub fn clear(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::Clear as u8)?;
-        self.delay.delay_ms(2);
+    pub fn clear(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Home::clear())
+    }
+
+    /// Clear a single row and move the cursor to its start.
+    ///
+    /// Unlike [`clear`](Self::clear), this doesn't touch the other rows.
+    pub fn clear_row(&mut self, row: u8) -> Result<(), Error<I::Error>> {
+        self.run(Blank::row(row))
+    }
+
+    /// Clear all rows in `rows`, see [`clear_row`](Self::clear_row).
+    pub fn clear_rows(&mut self, rows: Range<u8>) -> Result<(), Error<I::Error>> {
+        for row in rows {
+            self.clear_row(row)?;
+        }
         Ok(())
     }
 
+    /// Clear `len` characters starting at (row, col) and move the cursor to (row, col).
+    ///
+    /// A region reaching past the last column is either clamped or rejected with
+    /// [`Error::InvalidColumn`], see [`with_clamp_regions`](GenericLcdBuilder::with_clamp_regions).
+    pub fn clear_region(&mut self, row: u8, col: u8, len: u8) -> Result<(), Error<I::Error>> {
+        self.run(Blank::region(row, col, len))
+    }
+
+    /// Clear the current row from the cursor to the end of the row, the cursor stays where it
+    /// was.
+    ///
+    /// Does nothing if the cursor is past the end of the row.
+    pub fn clear_to_end_of_line(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Blank::to_end_of_line())
+    }
+
     /// Return cursor to upper left corner, i.e. (0,0).
-    pub fn return_home(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::ReturnHome as u8)?;
-        self.delay.delay_ms(2);
-        Ok(())
+    pub fn return_home(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Home::new())
     }
 
     /// Set the cursor to (rows, col). Coordinates are zero-based.
-    pub fn set_cursor(&mut self, row: u8, col: u8) -> Result<(), I::Error> {
-        assert!(row < ROWS, "Row needs to be smaller than ROWS");
-        assert!(col < COLUMNS, "col needs to be smaller than COLUMNS");
+    ///
+    /// Returns [`Error::InvalidRow`] or [`Error::InvalidColumn`] if the position is outside of the
+    /// display.
+    pub fn set_cursor(&mut self, row: u8, col: u8) -> Result<(), Error<I::Error>> {
+        self.run(MoveTo::cursor(row, col))
+    }
+
+    /// Set the cursor to (row, col), allowing columns outside of the display up to the end of the
+    /// DDRAM of the row, e.g. to write text that is scrolled in with `scroll_display_left` later.
+    ///
+    /// Writes continue past the end of the row regardless of the [`OverflowPolicy`] until the
+    /// cursor is moved with `set_cursor` again. Returns [`Error::InvalidRow`] or
+    /// [`Error::InvalidColumn`] if the position is outside of the DDRAM.
+    pub fn set_cursor_ddram(&mut self, row: u8, col: u8) -> Result<(), Error<I::Error>> {
+        self.run(MoveTo::ddram(row, col))
+    }
 
-        let offset = if ROWS == 4 && COLUMNS == 16 {
-            OFFSETS_16X4[row as usize]
-        } else {
-            OFFSETS_NORMAL[row as usize]
-        };
+    /// Get the current cursor position as (row, col), tracked by the driver.
+    ///
+    /// After writing past the end of a row, the column is outside of the display until the
+    /// cursor reaches the start of another row.
+    pub fn get_cursor(&self) -> (u8, u8) {
+        self.state.get_cursor()
+    }
+
+    /// Read the address counter from the controller, i.e. the DDRAM address the next character is
+    /// written to.
+    ///
+    /// Unlike [`get_cursor`](Self::get_cursor), this asks the controller instead of the driver.
+    /// Needs the RW pin of the display connected to the expander, returns
+    /// [`Error::ReadUnavailable`] otherwise.
+    pub fn read_address_counter(&mut self) -> Result<u8, Error<I::Error>> {
+        self.run(ReadAddress::new())
+    }
 
-        let shift: u8 = col + offset;
-        self.command(Mode::DDRAMAddr as u8 | shift)
+    /// Read the characters at (row, col) from the controller into `buf`.
+    ///
+    /// Reading continues past the end of the row like writing does. The cursor stays where it
+    /// was. Needs the RW pin of the display connected to the expander, returns
+    /// [`Error::ReadUnavailable`] otherwise.
+    pub fn read_ddram(&mut self, row: u8, col: u8, buf: &mut [u8]) -> Result<(), Error<I::Error>> {
+        let address = self.state.ddram_address(row, col)?;
+        self.run(ReadRam::ddram(address, buf))
+    }
+
+    /// Remember the current cursor position to return to it with
+    /// [`restore_cursor`](Self::restore_cursor).
+    ///
+    /// Only one position can be saved, returns [`Error::CursorAlreadySaved`] if the last saved
+    /// position wasn't restored yet.
+    pub fn save_cursor(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.save_cursor()
+    }
+
+    /// Move the cursor back to the position saved with [`save_cursor`](Self::save_cursor).
+    ///
+    /// Returns [`Error::NoSavedCursor`] if no position was saved.
+    pub fn restore_cursor(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(MoveTo::saved())
     }
 
     /// Recomputes display_ctrl and updates the lcd
-    fn update_display_control(&mut self) -> Result<(), I::Error> {
-        let display_ctrl = if self.cursor_on {
-            DisplayControl::DisplayOn as u8 | DisplayControl::CursorOn as u8
-        } else {
-            DisplayControl::DisplayOn as u8
-        };
-        let display_ctrl = if self.cursor_blink {
-            display_ctrl | DisplayControl::CursorBlink as u8
-        } else {
-            display_ctrl
-        };
-        self.command(Mode::DisplayControl as u8 | display_ctrl)
+    fn update_display_control(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Control::update())
+    }
+
+    /// Turn the display on, showing the contents of the DDRAM again.
+    pub fn display_on(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.display_on = true;
+        self.update_display_control()
+    }
+
+    /// Turn the display off while keeping the contents of the DDRAM.
+    ///
+    /// Cursor settings changed while the display is off are applied once it's turned on again.
+    pub fn display_off(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.display_on = false;
+        self.update_display_control()
+    }
+
+    /// Flash the whole display `times` times, turning it off for `off_ms` and on for `on_ms`.
+    ///
+    /// The contents of the display are kept, it's returned to its previous state afterwards.
+    pub fn flash_display(
+        &mut self,
+        times: u8,
+        on_ms: u32,
+        off_ms: u32,
+    ) -> Result<(), Error<I::Error>> {
+        self.run(FlashDisplay::new(times, on_ms, off_ms))
     }
 
     // Set if the cursor is blinking
-    pub fn cursor_blink(&mut self, blink: bool) -> Result<(), I::Error> {
-        self.cursor_blink = blink;
+    pub fn cursor_blink(&mut self, blink: bool) -> Result<(), Error<I::Error>> {
+        self.state.config.cursor_blink = blink;
         self.update_display_control()
     }
 
     // Set the curser visibility
-    pub fn cursor_on(&mut self, on: bool) -> Result<(), I::Error> {
-        self.cursor_on = on;
+    pub fn cursor_on(&mut self, on: bool) -> Result<(), Error<I::Error>> {
+        self.state.config.cursor_on = on;
         self.update_display_control()
     }
 
+    /// Recomputes the entry mode and updates the lcd
+    fn update_entry_mode(&mut self) -> Result<(), Error<I::Error>> {
+        self.command(self.state.entry_mode())
+    }
+
+    /// Shift the whole display with every character written, keeping the cursor at the same
+    /// position on the screen.
+    pub fn autoscroll_on(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.display_shift = DisplayShift::Increment;
+        self.update_entry_mode()
+    }
+
+    /// Stop shifting the display with every character written.
+    pub fn autoscroll_off(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.display_shift = DisplayShift::Decrement;
+        self.update_entry_mode()
+    }
+
+    /// Write text from left to right, the cursor moves to the right after each character.
+    pub fn left_to_right(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.cursor_dir = CursorMoveDir::Left;
+        self.update_entry_mode()
+    }
+
+    /// Write text from right to left, the cursor moves to the left after each character.
+    pub fn right_to_left(&mut self) -> Result<(), Error<I::Error>> {
+        self.state.cursor_dir = CursorMoveDir::Right;
+        self.update_entry_mode()
+    }
+
     /// Recomputes function set and updates the lcd
-    fn update_function_set(&mut self) -> Result<(), I::Error> {
-        // Function set command
-        let lines = match ROWS {
-            1 => 0x00,
-            _ => 0x08,
-        };
-        self.command(
-            Mode::FunctionSet as u8 | self.font_mode as u8 | lines, // Two line display
-        )
+    fn update_function_set(&mut self) -> Result<(), Error<I::Error>> {
+        self.command(self.state.function_set())
     }
 
     /// Set the font mode used (5x8 or 5x10)
-    pub fn font_mode(&mut self, mode: Font) -> Result<(), I::Error> {
-        self.font_mode = mode;
+    ///
+    /// This sends the function set again, which not all controllers accept after
+    /// initialization. Prefer setting the font with [`with_font`](GenericLcdBuilder::with_font) before init.
+    /// Returns [`Error::UnsupportedFontForGeometry`] for [`Font::Font5x10`] on displays with more
+    /// than one row.
+    pub fn font_mode(&mut self, mode: Font) -> Result<(), Error<I::Error>> {
+        self.state.check_font(mode)?;
+        self.state.config.font = mode;
         self.update_function_set()
     }
 
+    /// Shift the display by `n` chars in the given direction.
+    ///
+    /// The shifts are sent in one go if the interface can, see [`write_bytes`](Self::write_bytes).
+    pub fn shift_display_by(&mut self, n: u8, dir: ShiftDirection) -> Result<(), Error<I::Error>> {
+        self.run(ShiftDisplay::new(n, dir))
+    }
+
+    /// Move the cursor by `n` chars in the given direction.
+    pub fn shift_cursor_by(&mut self, n: u8, dir: ShiftDirection) -> Result<(), Error<I::Error>> {
+        self.run(Shift::cursor(n, dir))
+    }
+
+    /// Number of chars the display is currently shifted to the left, tracked by the driver.
+    ///
+    /// Shifting the display to the right wraps around at the end of the DDRAM line.
+    pub fn display_offset(&self) -> u8 {
+        self.state.display_offset
+    }
+
+    /// Characters shown on the display as far as the driver knows, e.g. to compare them with
+    /// [`assert_display!`](crate::assert_display) in tests.
+    ///
+    /// With the display shifted, the snapshot shows the part of the rows scrolled into view.
+    pub fn snapshot(&self) -> Snapshot {
+        self.state.snapshot()
+    }
+
+    /// Set what happens when text is written past the end of a row, see [`OverflowPolicy`].
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.state.config.overflow_policy = policy;
+    }
+
+    /// Set the character ROM of the display text is translated for, see [`Charset`].
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.state.config.charset = charset;
+    }
+
     /// Scrolls the display one char to the left
-    pub fn scroll_display_left(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::ShiftDisplayLeft as u8)
+    pub fn scroll_display_left(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Shift::display(1, ShiftDirection::Left))
     }
 
     /// Scrolls the display one char to the right
-    pub fn scroll_display_right(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::ShiftDisplayRight as u8)
+    pub fn scroll_display_right(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Shift::display(1, ShiftDirection::Right))
     }
 
     /// Scrolls the cursor one char to the left
-    pub fn scroll_cursor_left(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::ShiftCursorLeft as u8)
+    pub fn scroll_cursor_left(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Shift::cursor(1, ShiftDirection::Left))
     }
 
     /// Scrolls the cursor one char to the right
-    pub fn scroll_cursor_right(&mut self) -> Result<(), I::Error> {
-        self.command(Commands::ShiftCursorRight as u8)
+    pub fn scroll_cursor_right(&mut self) -> Result<(), Error<I::Error>> {
+        self.run(Shift::cursor(1, ShiftDirection::Right))
     }
 }
 
-impl<'a, const ROWS: u8, const COLUMNS: u8, I, D> uWrite for Lcd<'a, ROWS, COLUMNS, I, D>
+impl<G, I, D> uWrite for GenericLcd<G, I, D>
 where
-    I: I2c,
+    G: Geometry,
+    I: LcdInterface,
     D: DelayNs,
 {
-    type Error = I::Error;
+    type Error = Error<I::Error>;
 
     fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
         self.write_str(s)
     }
 }
+
+impl<G, I, D> fmt::Write for GenericLcd<G, I, D>
+where
+    G: Geometry,
+    I: LcdInterface,
+    D: DelayNs,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_str(s).map_err(|e| {
+            self.last_error = Some(e);
+            fmt::Error
+        })
+    }
+}
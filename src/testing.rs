@@ -0,0 +1,172 @@
+//! Checking what a display shows in tests, without decoding the I2C traffic.
+//!
+//! ```
+//! use lcd_lcm1602_i2c::{assert_display, testing::Snapshot};
+//! # use embedded_hal::i2c::{ErrorType, I2c, Operation};
+//! # struct Bus;
+//! # impl ErrorType for Bus {
+//! #     type Error = core::convert::Infallible;
+//! # }
+//! # impl I2c for Bus {
+//! #     fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # struct Delay;
+//! # impl embedded_hal::delay::DelayNs for Delay {
+//! #     fn delay_ns(&mut self, _ns: u32) {}
+//! # }
+//!
+//! let mut lcd = lcd_lcm1602_i2c::LCD16x2::new(Bus, Delay)
+//!     .with_address(0x27)
+//!     .init()
+//!     .unwrap();
+//! lcd.print_at(0, 0, "Temp").unwrap();
+//! lcd.print_at(1, 11, "21 °C").unwrap();
+//!
+//! assert_display!(lcd.snapshot(), "Temp\n           21 °C");
+//! ```
+
+use core::fmt;
+
+use crate::charset::shown_char;
+
+/// Character codes shown on each row of a display, see `snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    rows: u8,
+    columns: u8,
+    cells: [u8; 160],
+}
+
+impl Snapshot {
+    /// Snapshot with the code of each cell given by `code(row, col)`.
+    pub(crate) fn from_fn<F>(rows: u8, columns: u8, mut code: F) -> Self
+    where
+        F: FnMut(u8, u8) -> u8,
+    {
+        let mut cells = [b' '; 160];
+        for row in 0..rows {
+            for col in 0..columns {
+                cells[(row * columns + col) as usize] = code(row, col);
+            }
+        }
+        Self {
+            rows,
+            columns,
+            cells,
+        }
+    }
+
+    /// Number of rows.
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Number of columns.
+    pub fn columns(&self) -> u8 {
+        self.columns
+    }
+
+    /// Character codes of `row`, empty if the row is outside of the display.
+    pub fn row(&self, row: u8) -> &[u8] {
+        if row >= self.rows {
+            return &[];
+        }
+        let start = (row * self.columns) as usize;
+        &self.cells[start..start + self.columns as usize]
+    }
+
+    /// Characters of `row` as the A00 ROM shows them, custom characters as the number of their
+    /// CGRAM slot and codes without a matching character as `'?'`.
+    pub fn chars(&self, row: u8) -> impl Iterator<Item = char> + '_ {
+        self.row(row).iter().map(|&code| shown_char(code))
+    }
+
+    /// Compare the snapshot with `expected`, one line per row.
+    ///
+    /// Spaces at the end of a line and missing lines at the end of `expected` match blank
+    /// cells.
+    pub fn compare<'a>(&'a self, expected: &'a str) -> Result<(), Mismatch<'a>> {
+        let matches = expected.split('\n').count() <= self.rows as usize
+            && (0..self.rows).all(|row| self.row_matches(row, expected_line(expected, row)));
+        match matches {
+            true => Ok(()),
+            false => Err(Mismatch {
+                snapshot: self,
+                expected,
+            }),
+        }
+    }
+
+    fn row_matches(&self, row: u8, expected: &str) -> bool {
+        let expected = expected.trim_end_matches(' ');
+        let mut chars = self.chars(row);
+        expected.chars().all(|c| chars.next() == Some(c)) && chars.all(|c| c == ' ')
+    }
+}
+
+/// The rows of the display, separated by newlines.
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows {
+            if row > 0 {
+                f.write_str("\n")?;
+            }
+            self.chars(row)
+                .try_for_each(|c| fmt::Write::write_char(f, c))?;
+        }
+        Ok(())
+    }
+}
+
+/// Line `row` of `expected`, empty if it has fewer lines.
+fn expected_line(expected: &str, row: u8) -> &str {
+    expected.split('\n').nth(row as usize).unwrap_or("")
+}
+
+/// A [`Snapshot`] not showing the expected text, printed as a diff of the rows.
+///
+/// Matching rows are printed as they are, other rows as the expected line marked with `-`
+/// followed by the shown row marked with `+`.
+#[derive(Debug)]
+pub struct Mismatch<'a> {
+    snapshot: &'a Snapshot,
+    expected: &'a str,
+}
+
+impl fmt::Display for Mismatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("display doesn't show the expected text")?;
+        let lines = self.expected.split('\n').count();
+        let columns = self.snapshot.columns as usize;
+        for row in 0..self.snapshot.rows.max(lines as u8) {
+            let expected = expected_line(self.expected, row);
+            if row < self.snapshot.rows && self.snapshot.row_matches(row, expected) {
+                f.write_str("\n  |")?;
+            } else {
+                write!(f, "\n- |{:<width$}|", expected, width = columns)?;
+                if row >= self.snapshot.rows {
+                    continue;
+                }
+                f.write_str("\n+ |")?;
+            }
+            self.snapshot
+                .chars(row)
+                .try_for_each(|c| fmt::Write::write_char(f, c))?;
+            f.write_str("|")?;
+        }
+        Ok(())
+    }
+}
+
+/// Assert that a [`Snapshot`](crate::testing::Snapshot) shows the expected text, panicking with a diff
+/// of the rows otherwise. See [`Snapshot::compare`](crate::testing::Snapshot::compare).
+#[macro_export]
+macro_rules! assert_display {
+    ($snapshot:expr, $expected:expr $(,)?) => {
+        if let Err(mismatch) = $snapshot.compare($expected) {
+            panic!("{}", mismatch);
+        }
+    };
+}
@@ -0,0 +1,124 @@
+//! The control byte framing of the AIP31068 with native I2C.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    config::LcdConfig, geometry::Fixed, interface::Aip31068Interface, sync_lcd::GenericLcd,
+    Backlight, Error,
+};
+
+const AIP_ADDRESS: u8 = 0x3e;
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Aip31068Interface<Transfers>, Delay>;
+
+fn init(config: LcdConfig) -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let interface = Aip31068Interface::new(Transfers { log: log.clone() });
+    let lcd = TestLcd::init_with_interface(interface, delay, config).unwrap();
+    (lcd, log)
+}
+
+fn lcd() -> (TestLcd, Log) {
+    let (lcd, log) = init(LcdConfig::default());
+    log.take();
+    (lcd, log)
+}
+
+/// The transfers recorded since the last call, without the delays.
+fn transfers(log: &Log) -> Vec<Vec<u8>> {
+    log.take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Transfer(AIP_ADDRESS, bytes) => Some(bytes),
+            Event::Transfer(..) => panic!("transfer to another address"),
+            _ => None,
+        })
+        .collect()
+}
+
+fn command(instruction: u8) -> Vec<u8> {
+    vec![0x80, instruction]
+}
+
+#[test]
+fn init_in_8_bit_mode() {
+    let (_, log) = init(LcdConfig::default());
+    let expected = [
+        // Synchronization, without switching to 4 bit mode.
+        command(0x30),
+        command(0x30),
+        command(0x30),
+        // 8 bit, two lines, 5x8 font
+        command(0x38),
+        // Display on, cursor off
+        command(0x0c),
+        // Clear
+        command(0x01),
+        // Left to right without shifting the display
+        command(0x06),
+        // Return home
+        command(0x02),
+    ];
+    assert_eq!(transfers(&log), expected);
+}
+
+#[test]
+fn commands() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 5).unwrap();
+    lcd.clear().unwrap();
+    assert_eq!(transfers(&log), [command(0xc5), command(0x01)]);
+}
+
+#[test]
+fn string_in_one_transfer() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("Hi").unwrap();
+    assert_eq!(transfers(&log), [vec![0x40, b'H', b'i']]);
+}
+
+#[test]
+fn characters_one_at_a_time() {
+    let (mut lcd, log) = lcd();
+    lcd.write_raw_char(b'H').unwrap();
+    lcd.write_raw_char(b'i').unwrap();
+    assert_eq!(transfers(&log), [vec![0x40, b'H'], vec![0x40, b'i']]);
+}
+
+#[test]
+fn raw_bytes_in_one_transfer() {
+    let (mut lcd, log) = lcd();
+    lcd.write_bytes(b"Hi!").unwrap();
+    assert_eq!(transfers(&log), [vec![0x40, b'H', b'i', b'!']]);
+    assert_eq!(lcd.get_cursor(), (0, 3));
+
+    lcd.write_bytes(&[]).unwrap();
+    assert!(transfers(&log).is_empty());
+}
+
+#[test]
+fn create_char() {
+    let (mut lcd, log) = lcd();
+    lcd.create_char(1, &[0x1f; 8]).unwrap();
+    let mut expected = vec![command(0x48)];
+    expected.extend((0..8).map(|_| vec![0x40, 0x1f]));
+    expected.push(command(0x80));
+    assert_eq!(transfers(&log), expected);
+}
+
+#[test]
+fn no_backlight_on_the_bus() {
+    let (mut lcd, log) = lcd();
+    lcd.backlight(Backlight::Off).unwrap();
+    assert!(transfers(&log).is_empty());
+}
+
+#[test]
+fn reading_is_unavailable() {
+    let (mut lcd, log) = init(LcdConfig::default().with_busy_flag_polling(true));
+    log.take();
+    lcd.clear().unwrap();
+    assert_eq!(transfers(&log), [command(0x01)]);
+    assert_eq!(lcd.read_address_counter(), Err(Error::ReadUnavailable));
+}
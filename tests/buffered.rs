@@ -0,0 +1,199 @@
+//! Drawing into a frame and sending only the changed cells.
+#![cfg(feature = "buffered")]
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{assert_display, buffered::BufferedLcd, sync_lcd::Lcd};
+
+type TestLcd<const ROWS: u8, const COLUMNS: u8> = BufferedLcd<
+    lcd_lcm1602_i2c::geometry::Fixed<ROWS, COLUMNS>,
+    lcd_lcm1602_i2c::interface::Pcf8574<Bus>,
+    Delay,
+>;
+
+fn lcd<const ROWS: u8, const COLUMNS: u8>() -> (TestLcd<ROWS, COLUMNS>, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = Lcd::<ROWS, COLUMNS, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (BufferedLcd::new(lcd), log)
+}
+
+fn commands(sent: &[(u8, u8)]) -> usize {
+    sent.iter().filter(|&&(rs, _)| rs == 0).count()
+}
+
+fn data(sent: &[(u8, u8)]) -> Vec<u8> {
+    sent.iter()
+        .filter(|&&(rs, _)| rs == RS)
+        .map(|&(_, byte)| byte)
+        .collect()
+}
+
+#[test]
+fn drawing_sends_nothing_until_flush() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 0, "Hello").unwrap();
+    assert!(sent(&log).is_empty());
+    lcd.flush().unwrap();
+    assert_eq!(data(&sent(&log)), b"Hello");
+    assert_display!(lcd.lcd().snapshot(), "Hello");
+}
+
+#[test]
+fn one_character_change_is_one_command_and_one_character() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(1, 0, "Count: 7").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.print_at(1, 7, "8").unwrap();
+    lcd.flush().unwrap();
+    assert_eq!(sent(&log), [(0, 0x80 | 0x47), (RS, b'8')]);
+}
+
+#[test]
+fn full_screen_change_moves_the_cursor_once_per_row() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(1, 0, "Count: 7").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.print_at(0, 0, "0123456789abcdef").unwrap();
+    lcd.print_at(1, 0, "ghijklmnopqrstuv").unwrap();
+    lcd.flush().unwrap();
+    let sent = sent(&log);
+    assert_eq!(commands(&sent), 2);
+    assert_eq!(data(&sent).len(), 32);
+}
+
+#[test]
+fn flush_without_changes_sends_nothing() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 0, "Same").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.print_at(0, 0, "Same").unwrap();
+    lcd.flush().unwrap();
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn single_unchanged_cell_is_written_along() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 0, "abcdef").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.print_at(0, 0, "xbx").unwrap();
+    lcd.flush().unwrap();
+    assert_eq!(sent(&log), [(0, 0x80), (RS, b'x'), (RS, b'b'), (RS, b'x')]);
+}
+
+#[test]
+fn longer_gaps_move_the_cursor() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 0, "abcdef").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.print_at(0, 0, "xbcx").unwrap();
+    lcd.flush().unwrap();
+    assert_eq!(sent(&log), [(0, 0x80), (RS, b'x'), (0, 0x83), (RS, b'x')]);
+}
+
+#[test]
+fn run_continues_from_row_0_to_row_2_without_moving_the_cursor() {
+    let (mut lcd, log) = lcd::<4, 20>();
+    lcd.print_at(0, 18, "ab").unwrap();
+    lcd.print_at(2, 0, "cd").unwrap();
+    lcd.flush().unwrap();
+    // Row 2 starts at 0x14, right after the end of row 0.
+    assert_eq!(
+        sent(&log),
+        [
+            (0, 0x80 | 18),
+            (RS, b'a'),
+            (RS, b'b'),
+            (RS, b'c'),
+            (RS, b'd')
+        ]
+    );
+    assert_display!(lcd.lcd().snapshot(), "                  ab\n\ncd");
+}
+
+#[test]
+fn flush_after_clear_only_blanks_the_cells_with_text() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 2, "ab").unwrap();
+    lcd.flush().unwrap();
+    log.take();
+    lcd.clear();
+    lcd.flush().unwrap();
+    assert_eq!(sent(&log), [(0, 0x82), (RS, b' '), (RS, b' ')]);
+    assert_display!(lcd.lcd().snapshot(), "");
+}
+
+#[test]
+fn custom_characters_are_sent_as_their_slot() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.set_cursor(0, 0);
+    lcd.write_bytes(&[0, 1]);
+    lcd.flush().unwrap();
+    // The display is at the start of row 0 after init already.
+    assert_eq!(sent(&log), [(RS, 0), (RS, 1)]);
+    lcd.flush().unwrap();
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn flush_all_redraws_after_re_init() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.print_at(0, 0, "Kept").unwrap();
+    lcd.flush().unwrap();
+    lcd.lcd_mut().re_init().unwrap();
+    log.take();
+    lcd.flush_all().unwrap();
+    let sent = sent(&log);
+    // Row 0 starts at the address re_init left the display at.
+    assert_eq!(commands(&sent), 1);
+    assert_eq!(data(&sent).len(), 32);
+    assert_display!(lcd.lcd().snapshot(), "Kept");
+}
+
+#[test]
+fn text_is_truncated_at_the_end_of_the_row() {
+    let (mut lcd, _log) = lcd::<2, 16>();
+    lcd.print_at(0, 14, "abc\nde").unwrap();
+    assert_eq!(&lcd.row(0)[14..], b"ab");
+    assert_eq!(&lcd.row(1)[..2], b"de");
+    assert_eq!(lcd.cursor(), (1, 2));
+}
+
+#[test]
+fn back_buffer_is_only_sent_on_swap() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.back_mut().print_at(0, 0, "Temp 21.5").unwrap();
+    lcd.flush().unwrap();
+    lcd.back_mut().print_at(1, 0, "Hum 40%").unwrap();
+    lcd.flush().unwrap();
+    assert!(log.take().is_empty());
+    assert_display!(lcd.lcd().snapshot(), "");
+
+    lcd.swap_and_flush().unwrap();
+    assert_eq!(data(&sent(&log)), b"Temp 21.5Hum 40%");
+    assert_display!(lcd.lcd().snapshot(), "Temp 21.5\nHum 40%");
+}
+
+#[test]
+fn back_buffer_keeps_the_last_frame() {
+    let (mut lcd, log) = lcd::<2, 16>();
+    lcd.back_mut().print_at(0, 0, "Temp 21.5").unwrap();
+    lcd.swap_and_flush().unwrap();
+    log.take();
+    lcd.back_mut().print_at(0, 5, "22.0").unwrap();
+    lcd.flush().unwrap();
+    assert!(log.take().is_empty());
+    lcd.swap_and_flush().unwrap();
+    assert_eq!(sent(&log), [(0, 0x86), (RS, b'2'), (RS, b'.'), (RS, b'0')]);
+    assert_eq!(lcd.row(0), lcd.back().row(0));
+}
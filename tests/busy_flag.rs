@@ -0,0 +1,81 @@
+//! Polling the busy flag through the PCF8574, and falling back to the delays on boards with RW
+//! tied to ground.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{config::LcdConfig, sync_lcd::Lcd};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+/// RW high, the data lines released and EN pulsed: a status nibble read from the expander.
+fn status_nibble() -> Vec<Event> {
+    let read = 0xf0 | 0x02 | BL;
+    vec![
+        write(read),
+        write(read | EN),
+        Event::Read(ADDRESS),
+        write(read),
+    ]
+}
+
+/// `byte` sent without waiting, as when the busy flag is polled after it.
+fn unwaited(rs: u8, code: u8) -> Vec<Event> {
+    let mut events = byte(rs, code, 0, BL);
+    events.pop();
+    events
+}
+
+#[test]
+fn polls_instead_of_waiting() {
+    let (mut bus, delay, log) = fakes();
+    bus.read = 0x00;
+    let mut lcd = TestLcd::new_with_config(
+        bus,
+        delay,
+        LcdConfig::default().with_busy_flag_polling(true),
+    )
+    .with_address(ADDRESS)
+    .init()
+    .unwrap();
+    log.take();
+
+    lcd.clear().unwrap();
+    let mut expected = unwaited(0, 0x01);
+    expected.extend(status_nibble());
+    expected.extend(status_nibble());
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn rw_tied_to_ground() {
+    // The data lines stay high: one status read, taken by the controller as instruction 0xff.
+    let (mut bus, delay, log) = fakes();
+    bus.read = 0xff;
+    let mut lcd = TestLcd::new_with_config(
+        bus,
+        delay,
+        LcdConfig::default().with_busy_flag_polling(true),
+    )
+    .with_address(ADDRESS)
+    .init()
+    .unwrap();
+
+    let mut expected = init_sequence(BL);
+    // The function set is the first instruction polled after, in place of its wait.
+    let wait = expected
+        .iter()
+        .position(|event| *event == delay_us(50))
+        .unwrap();
+    let mut fallback = status_nibble();
+    fallback.extend(status_nibble());
+    fallback.push(delay_us(50));
+    // The address counter set again.
+    fallback.extend(command(0x80));
+    expected.splice(wait..=wait, fallback);
+    assert_eq!(log.take(), expected);
+
+    // No more reads.
+    lcd.write_str("H").unwrap();
+    assert_eq!(log.take(), data(b'H'));
+}
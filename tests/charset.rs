@@ -0,0 +1,153 @@
+//! Translating characters to the codes of the character ROM.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{charset::CharMapper, sync_lcd::Lcd, Charset};
+
+fn lcd(charset: Charset) -> (Lcd<2, 16, Bus, Delay>, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_charset(charset)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// The codes `text` is written as.
+fn codes(charset: Charset, text: &str) -> Vec<u8> {
+    let (mut lcd, log) = lcd(charset);
+    lcd.write_str(text).unwrap();
+    sent(&log).into_iter().map(|(_, code)| code).collect()
+}
+
+#[test]
+fn a00_symbols() {
+    let cases = [
+        ('°', 0xdf),
+        ('ä', 0xe1),
+        ('ß', 0xe2),
+        ('µ', 0xe4),
+        ('Ω', 0xf4),
+        ('→', 0x7e),
+        ('¥', 0x5c),
+        // Not in the ROM, replaced.
+        ('\\', b'?'),
+        ('é', b'?'),
+    ];
+    for (c, code) in cases {
+        assert_eq!(
+            codes(Charset::A00, c.encode_utf8(&mut [0; 4])),
+            [code],
+            "{}",
+            c
+        );
+    }
+}
+
+#[cfg(feature = "katakana")]
+#[test]
+fn half_width_katakana() {
+    assert_eq!(codes(Charset::A00, "ｺﾝﾆﾁﾊ"), [0xba, 0xdd, 0xc6, 0xc1, 0xca]);
+    assert_eq!(codes(Charset::A00, "｢ｶﾞ｣"), [0xa2, 0xb6, 0xde, 0xa3]);
+}
+
+#[cfg(feature = "katakana")]
+#[test]
+fn full_width_katakana() {
+    // The sound marks are written as a second character.
+    assert_eq!(
+        codes(Charset::A00, "ガイド"),
+        [0xb6, 0xde, 0xb2, 0xc4, 0xde]
+    );
+    assert_eq!(
+        codes(Charset::A00, "パ・ー。"),
+        [0xca, 0xdf, 0xa5, 0xb0, 0xa1]
+    );
+    // Small Katakana missing from the ROM are written with the closest one.
+    assert_eq!(codes(Charset::A00, "ヵ"), [0xb6]);
+}
+
+#[cfg(not(feature = "katakana"))]
+#[test]
+fn katakana_are_replaced_without_the_feature() {
+    assert_eq!(codes(Charset::A00, "ｺガ"), b"??");
+}
+
+#[cfg(feature = "rom-a02")]
+#[test]
+fn a02_codes() {
+    let cases = [
+        ('é', 0xe9),
+        ('ß', 0xdf),
+        ('À', 0xc0),
+        ('ü', 0xfc),
+        ('£', 0xa3),
+        ('Ж', 0x82),
+        // Cyrillic capitals looking like Latin ones.
+        ('К', b'K'),
+        ('Ω', 0x9a),
+        ('α', 0x90),
+        ('→', 0x1a),
+        ('≥', 0x1d),
+        // Unlike the A00 ROM, it has the backslash and tilde.
+        ('\\', b'\\'),
+        ('~', b'~'),
+        ('°', 0xb0),
+        // Not in the ROM, replaced.
+        ('Я', b'?'),
+    ];
+    for (c, code) in cases {
+        assert_eq!(
+            codes(Charset::A02, c.encode_utf8(&mut [0; 4])),
+            [code],
+            "{}",
+            c
+        );
+    }
+}
+
+#[cfg(feature = "rom-a02")]
+#[test]
+fn charset_is_switched_at_runtime() {
+    let (mut lcd, log) = lcd(Charset::A00);
+    lcd.write_str("é").unwrap();
+    lcd.set_charset(Charset::A02);
+    assert!(lcd.supports_char('é'));
+    lcd.write_str("é").unwrap();
+    assert_eq!(sent(&log), [(RS, b'?'), (RS, 0xe9)]);
+}
+
+/// Writes `'a'` as the `'ä'` of the A00 ROM and `'☺'` as custom character 0.
+struct Umlauts;
+
+impl CharMapper for Umlauts {
+    fn map(&self, c: char) -> Option<u8> {
+        match c {
+            'a' => Some(0xe1),
+            '☺' => Some(0),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn char_mapper_is_asked_first() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_char_mapper(&Umlauts)
+        .init()
+        .unwrap();
+    log.take();
+    lcd.write_str("ab☺").unwrap();
+    assert_eq!(sent(&log), [(RS, 0xe1), (RS, b'b'), (RS, 0)]);
+    assert!(lcd.supports_char('☺'));
+    assert!(!lcd.supports_char('😀'));
+
+    // Raw bytes aren't translated.
+    lcd.write_bytes(b"ab").unwrap();
+    assert_eq!(sent(&log), [(RS, b'a'), (RS, b'b')]);
+}
@@ -0,0 +1,275 @@
+//! Fake bus and delay recording what the driver does, and the byte sequences it's expected to
+//! send.
+//!
+//! The expected sequences are spelled out with the expander bits of the default pin mapping, so
+//! a change of what goes out on the wire has to update them here.
+
+#![allow(dead_code)]
+
+use std::{cell::RefCell, convert::Infallible, rc::Rc};
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorType, I2c, Operation},
+};
+
+pub const ADDRESS: u8 = 0x27;
+
+pub const RS: u8 = 0x01;
+pub const EN: u8 = 0x04;
+pub const BL: u8 = 0x08;
+
+/// Something the driver did, in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A byte written to the expander at an address.
+    Write(u8, u8),
+    /// A byte read from the expander at an address.
+    Read(u8),
+    /// A delay in ns.
+    Delay(u64),
+    /// A GPIO pin set high (`true`) or low.
+    Pin(&'static str, bool),
+    /// A byte shifted into a shift register and latched to its outputs.
+    Shift(u8),
+    /// The bytes of one I2C transaction to an address, see [`Transfers`].
+    Transfer(u8, Vec<u8>),
+}
+
+/// Events of a bus and a delay, shared with the test while the driver owns them.
+#[derive(Clone, Default)]
+pub struct Log(Rc<RefCell<Vec<Event>>>);
+
+impl Log {
+    /// Take the events recorded since the last call.
+    pub fn take(&self) -> Vec<Event> {
+        self.0.take()
+    }
+
+    /// Record `event`, e.g. from another fake.
+    pub fn push(&self, event: Event) {
+        self.0.borrow_mut().push(event);
+    }
+}
+
+/// Bus recording every byte written to and read from it, reads return `read`.
+pub struct Bus {
+    pub log: Log,
+    pub read: u8,
+}
+
+impl ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    for &byte in bytes.iter() {
+                        self.log.push(Event::Write(address, byte));
+                    }
+                }
+                Operation::Read(buf) => {
+                    self.log.push(Event::Read(address));
+                    buf.fill(self.read);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bus recording the bytes written in each transaction as one event, for controllers where the
+/// framing of a transaction matters.
+pub struct Transfers {
+    pub log: Log,
+}
+
+impl ErrorType for Transfers {
+    type Error = Infallible;
+}
+
+impl I2c for Transfers {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut bytes = Vec::new();
+        for operation in operations {
+            match operation {
+                Operation::Write(written) => bytes.extend_from_slice(written),
+                Operation::Read(_) => panic!("the controller isn't read from"),
+            }
+        }
+        self.log.push(Event::Transfer(address, bytes));
+        Ok(())
+    }
+}
+
+/// Delay recording how long it was asked to wait.
+pub struct Delay {
+    pub log: Log,
+}
+
+impl DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.log.push(Event::Delay(ns.into()));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.log.push(Event::Delay(u64::from(us) * 1_000));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.log.push(Event::Delay(u64::from(ms) * 1_000_000));
+    }
+}
+
+/// A bus and a delay sharing one log.
+pub fn fakes() -> (Bus, Delay, Log) {
+    let log = Log::default();
+    let bus = Bus {
+        log: log.clone(),
+        read: 0,
+    };
+    let delay = Delay { log: log.clone() };
+    (bus, delay, log)
+}
+
+pub fn write(byte: u8) -> Event {
+    Event::Write(ADDRESS, byte)
+}
+
+pub fn delay_us(us: u64) -> Event {
+    Event::Delay(us * 1_000)
+}
+
+/// Clock the lower four bits of `nibble` into D4 to D7: set up the lines, raise EN, lower EN.
+pub fn nibble(rs: u8, nibble: u8, backlight: u8) -> Vec<Event> {
+    let byte = (nibble << 4) | rs | backlight;
+    vec![write(byte), write(byte | EN), write(backlight)]
+}
+
+/// Send `byte` as two nibbles, high nibble first, and wait `wait_us` for the controller to
+/// execute it.
+pub fn byte(rs: u8, byte: u8, wait_us: u64, backlight: u8) -> Vec<Event> {
+    let mut events = nibble(rs, byte >> 4, backlight);
+    events.extend(nibble(rs, byte & 0x0f, backlight));
+    events.push(delay_us(wait_us));
+    events
+}
+
+/// An instruction taking the default 50 µs to execute, with the backlight on.
+pub fn command(instruction: u8) -> Vec<Event> {
+    byte(0, instruction, 50, BL)
+}
+
+/// Clear display or return home, taking the default 2 ms to execute, with the backlight on.
+pub fn slow_command(instruction: u8) -> Vec<Event> {
+    byte(0, instruction, 2_000, BL)
+}
+
+/// A character written to the RAM, with the backlight on.
+pub fn data(code: u8) -> Vec<Event> {
+    byte(RS, code, 50, BL)
+}
+
+/// Characters sent together, e.g. by `write_str`, with one wait after the last.
+pub fn text(codes: &[u8]) -> Vec<Event> {
+    let mut events: Vec<_> = codes
+        .iter()
+        .flat_map(|&code| {
+            let mut events = data(code);
+            events.pop();
+            events
+        })
+        .collect();
+    events.push(delay_us(50));
+    events
+}
+
+/// The bytes clocked in as nibble pairs, with RS.
+pub fn sent(log: &Log) -> Vec<(u8, u8)> {
+    let strobes: Vec<u8> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(_, byte) if byte & EN != 0 => Some(byte),
+            _ => None,
+        })
+        .collect();
+    strobes
+        .chunks(2)
+        .map(|pair| (pair[0] & RS, (pair[0] & 0xf0) | (pair[1] >> 4)))
+        .collect()
+}
+
+/// `init()` of a two row display with the default config and the backlight set to `backlight`.
+pub fn init_sequence(backlight: u8) -> Vec<Event> {
+    let mut events = vec![delay_us(80_000), write(backlight), delay_us(1_000)];
+    // Three function sets in 8 bit mode synchronize the nibbles, whatever state the controller
+    // is in, then switch to 4 bit mode.
+    for _ in 0..3 {
+        events.extend(nibble(0, 0x3, backlight));
+        events.extend([delay_us(700), delay_us(5_000)]);
+    }
+    events.extend(nibble(0, 0x2, backlight));
+    events.push(delay_us(700));
+    // 4 bit, two lines, 5x8 font
+    events.extend(byte(0, 0x28, 50, backlight));
+    // Display on, cursor off
+    events.extend(byte(0, 0x0c, 50, backlight));
+    // Clear
+    events.extend(byte(0, 0x01, 2_000, backlight));
+    // Left to right without shifting the display
+    events.extend(byte(0, 0x06, 50, backlight));
+    // Return home
+    events.extend(byte(0, 0x02, 2_000, backlight));
+    events
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for Bus {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for Delay {
+    async fn delay_ns(&mut self, ns: u32) {
+        DelayNs::delay_ns(self, ns)
+    }
+
+    async fn delay_us(&mut self, us: u32) {
+        DelayNs::delay_us(self, us)
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        DelayNs::delay_ms(self, ms)
+    }
+}
+
+/// Run a future that never waits, like the async driver with the fakes.
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{Context, Poll};
+
+    let mut future = core::pin::pin!(future);
+    let mut context = Context::from_waker(core::task::Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the fakes never wait"),
+    }
+}
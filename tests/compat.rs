@@ -0,0 +1,98 @@
+//! Driving the display through a HAL implementing the embedded-hal 0.2 traits.
+
+#![cfg(feature = "eh0")]
+
+mod common;
+
+use common::*;
+use eh0::blocking::{
+    delay::{DelayMs, DelayUs},
+    i2c::{Read, Write},
+};
+use lcd_lcm1602_i2c::{
+    compat::{CompatError, DelayCompat, I2cCompat},
+    sync_lcd::{Lcd, LcdBuilder},
+    Error,
+};
+
+/// embedded-hal 0.2 bus recording the bytes written to it, failing once `failing` is set.
+struct OldBus {
+    log: Log,
+    failing: bool,
+}
+
+impl Write for OldBus {
+    type Error = &'static str;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        if self.failing {
+            return Err("nack");
+        }
+        for &byte in bytes {
+            self.log.push(Event::Write(address, byte));
+        }
+        Ok(())
+    }
+}
+
+impl Read for OldBus {
+    type Error = &'static str;
+
+    fn read(&mut self, address: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.log.push(Event::Read(address));
+        buf.fill(0);
+        Ok(())
+    }
+}
+
+/// embedded-hal 0.2 delay recording how long it was asked to wait.
+struct OldDelay {
+    log: Log,
+}
+
+impl DelayUs<u32> for OldDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.log.push(Event::Delay(u64::from(us) * 1_000));
+    }
+}
+
+impl DelayMs<u32> for OldDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        self.log.push(Event::Delay(u64::from(ms) * 1_000_000));
+    }
+}
+
+type CompatLcd = Lcd<2, 16, I2cCompat<OldBus>, DelayCompat<OldDelay>>;
+
+fn builder(
+    failing: bool,
+) -> (
+    LcdBuilder<2, 16, I2cCompat<OldBus>, DelayCompat<OldDelay>>,
+    Log,
+) {
+    let log = Log::default();
+    let bus = OldBus {
+        log: log.clone(),
+        failing,
+    };
+    let delay = OldDelay { log: log.clone() };
+    let builder = CompatLcd::new(I2cCompat(bus), DelayCompat(delay)).with_address(ADDRESS);
+    (builder, log)
+}
+
+#[test]
+fn same_writes_and_waits_as_embedded_hal_1() {
+    let (builder, log) = builder(false);
+    let mut lcd = builder.init().unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+    lcd.write_str("ok").unwrap();
+    assert_eq!(log.take(), text(b"ok"));
+}
+
+#[test]
+fn bus_errors_are_wrapped() {
+    let (mut builder, _log) = builder(true);
+    // A missing display can't be told from other errors.
+    assert_eq!(builder.probe(), Err(Error::I2c(CompatError("nack"))));
+    assert_eq!(builder.init().err(), Some(Error::I2c(CompatError("nack"))));
+}
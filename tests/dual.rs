@@ -0,0 +1,128 @@
+//! 40x4 displays with two controllers, the second one enabled through the RW pin.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::DualLcd, Error, OverflowPolicy};
+
+/// Enable pin of the second controller, RW of the default pin mapping.
+const EN2: u8 = 0x02;
+
+type TestLcd = DualLcd<40, Bus, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_overflow_policy(OverflowPolicy::WrapRow)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// The bytes clocked in as nibble pairs, with the enable pins strobed and RS.
+fn sent(log: &Log) -> Vec<(u8, u8, u8)> {
+    let strobes: Vec<u8> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(_, byte) if byte & (EN | EN2) != 0 => Some(byte),
+            _ => None,
+        })
+        .collect();
+    strobes
+        .chunks(2)
+        .map(|pair| {
+            assert_eq!(pair[0] & (EN | EN2), pair[1] & (EN | EN2));
+            let byte = (pair[0] & 0xf0) | (pair[1] >> 4);
+            (pair[0] & (EN | EN2), pair[0] & RS, byte)
+        })
+        .collect()
+}
+
+#[test]
+fn init_reaches_both_controllers() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    let strobes: Vec<u8> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(_, byte) if byte & (EN | EN2) != 0 => Some(byte & (EN | EN2)),
+            _ => None,
+        })
+        .collect();
+    assert!(!strobes.is_empty());
+    assert!(strobes.iter().all(|&enable| enable == EN | EN2));
+}
+
+#[test]
+fn write_from_row_1_to_row_2_switches_controllers() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 38).unwrap();
+    lcd.write_str("abcd").unwrap();
+    assert_eq!(
+        sent(&log),
+        [
+            (EN, 0, 0x80 | (0x40 + 38)),
+            (EN, RS, b'a'),
+            (EN, RS, b'b'),
+            (EN2, 0, 0x80),
+            (EN2, RS, b'c'),
+            (EN2, RS, b'd'),
+        ]
+    );
+    assert_eq!(lcd.get_cursor(), (2, 2));
+}
+
+#[test]
+fn rows_2_and_3_start_at_the_second_controller() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(3, 5).unwrap();
+    assert_eq!(sent(&log), [(EN2, 0, 0x80 | (0x40 + 5))]);
+    lcd.print_at(2, 0, "B").unwrap();
+    lcd.print_at(0, 0, "A").unwrap();
+    let snapshot = lcd.snapshot();
+    assert_eq!(snapshot.row(0)[0], b'A');
+    assert_eq!(snapshot.row(1)[0], b' ');
+    assert_eq!(snapshot.row(2)[0], b'B');
+}
+
+#[test]
+fn clear_clears_both_halves() {
+    let (mut lcd, log) = lcd();
+    lcd.print_at(3, 0, "x").unwrap();
+    log.take();
+    lcd.clear().unwrap();
+    assert_eq!(sent(&log), [(EN | EN2, 0, 0x01)]);
+    assert_eq!(lcd.get_cursor(), (0, 0));
+    assert_eq!(lcd.snapshot().row(3)[0], b' ');
+}
+
+#[test]
+fn cursor_follows_the_active_controller() {
+    let (mut lcd, log) = lcd();
+    lcd.cursor_on(true).unwrap();
+    assert_eq!(sent(&log), [(EN, 0, 0x0e), (EN2, 0, 0x0c)]);
+    lcd.set_cursor(2, 0).unwrap();
+    assert_eq!(sent(&log), [(EN2, 0, 0x80), (EN2, 0, 0x0e), (EN, 0, 0x0c)]);
+    // Clearing homes the cursor to the first controller.
+    lcd.clear().unwrap();
+    assert_eq!(
+        sent(&log),
+        [(EN | EN2, 0, 0x01), (EN, 0, 0x0e), (EN2, 0, 0x0c)]
+    );
+}
+
+#[test]
+fn controllers_are_not_read() {
+    let (mut lcd, _log) = lcd();
+    assert!(matches!(
+        lcd.read_address_counter(),
+        Err(Error::ReadUnavailable)
+    ));
+}
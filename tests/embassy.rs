@@ -0,0 +1,65 @@
+//! The async driver waiting with `embassy_time::Delay`, on the mock time driver of embassy-time.
+
+#![cfg(feature = "async")]
+
+mod common;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use common::*;
+use embassy_executor::raw::Executor;
+use embassy_time::{Duration, Instant, MockDriver};
+use lcd_lcm1602_i2c::async_lcd::Lcd;
+
+type TestLcd = Lcd<2, 16, Bus, embassy_time::Delay>;
+
+/// Set by the task once it checked everything.
+static DONE: AtomicBool = AtomicBool::new(false);
+
+/// The time `events` wait in µs.
+fn waited_us(events: &[Event]) -> u64 {
+    events
+        .iter()
+        .map(|event| match event {
+            Event::Delay(ns) => ns / 1_000,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[embassy_executor::task]
+async fn init_and_clear(bus: Bus, log: Log) {
+    let start = Instant::now();
+    let mut lcd = TestLcd::new(bus, embassy_time::Delay)
+        .with_address(ADDRESS)
+        .init()
+        .await
+        .unwrap();
+
+    // The delays went to the timer, the bytes are the same as with a blocking delay.
+    let expected = init_sequence(BL);
+    assert_eq!(start.elapsed().as_micros(), waited_us(&expected));
+    let written: Vec<_> = expected
+        .into_iter()
+        .filter(|event| !matches!(event, Event::Delay(_)))
+        .collect();
+    assert_eq!(log.take(), written);
+
+    let start = Instant::now();
+    lcd.clear().await.unwrap();
+    assert_eq!(start.elapsed().as_micros(), 2_000);
+    DONE.store(true, Ordering::Relaxed);
+}
+
+#[test]
+fn init_waits_on_the_timer() {
+    let (bus, _, log) = fakes();
+    let executor = Box::leak(Box::new(Executor::new(core::ptr::null_mut())));
+    executor.spawner().spawn(init_and_clear(bus, log)).unwrap();
+    // All delays are multiples of 10 µs, so the time doesn't overshoot them.
+    while !DONE.load(Ordering::Relaxed) {
+        // SAFETY: the executor is only polled from this thread.
+        unsafe { executor.poll() };
+        MockDriver::get().advance(Duration::from_micros(10));
+    }
+}
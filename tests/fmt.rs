@@ -0,0 +1,86 @@
+//! Formatting with `write!` through `core::fmt::Write`.
+
+mod common;
+
+use core::fmt::{self, Write};
+use std::{cell::Cell, rc::Rc};
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error, OverflowPolicy};
+
+fn lcd(policy: OverflowPolicy) -> (Lcd<2, 16, Bus, Delay>, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_overflow_policy(policy)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// The character codes written to the display.
+fn written(log: &Log) -> Vec<u8> {
+    sent(log).into_iter().map(|(_, code)| code).collect()
+}
+
+#[test]
+fn integers_and_floats() {
+    let (mut lcd, log) = lcd(OverflowPolicy::Truncate);
+    write!(lcd, "T={}°C", -4).unwrap();
+    assert_eq!(written(&log), b"T=-4\xdfC");
+    write!(lcd, " {:.2}", 21.5f32).unwrap();
+    assert_eq!(written(&log), b" 21.50");
+    assert_eq!(lcd.take_last_error(), None);
+}
+
+#[test]
+fn driver_error_is_kept() {
+    let (mut lcd, _log) = lcd(OverflowPolicy::Error);
+    assert_eq!(write!(lcd, "{}", "0123456789abcdefgh"), Err(fmt::Error));
+    assert_eq!(
+        lcd.take_last_error(),
+        Some(Error::RowOverflow { written: 16 })
+    );
+    // It's only returned once.
+    assert_eq!(lcd.take_last_error(), None);
+}
+
+/// Bus failing every transaction once `failing` is set.
+struct Failing {
+    failing: Rc<Cell<bool>>,
+}
+
+impl ErrorType for Failing {
+    type Error = ErrorKind;
+}
+
+impl I2c for Failing {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        _operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.failing.get() {
+            return Err(ErrorKind::Bus);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn bus_error_is_kept() {
+    let failing = Rc::new(Cell::new(false));
+    let (_, delay, _log) = fakes();
+    let bus = Failing {
+        failing: failing.clone(),
+    };
+    let mut lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    failing.set(true);
+    assert_eq!(write!(lcd, "{}", 42), Err(fmt::Error));
+    assert_eq!(lcd.take_last_error(), Some(Error::I2c(ErrorKind::Bus)));
+}
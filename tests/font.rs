@@ -0,0 +1,53 @@
+//! Custom characters of the tall 5x10 font.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error, Font};
+
+type TestLcd = Lcd<1, 16, Bus, Delay>;
+
+fn lcd(font: Font) -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_font(font)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn tall_character_is_written_as_twice_its_slot() {
+    let (mut lcd, log) = lcd(Font::Font5x10);
+    let charmap = [0x1f; 11];
+    lcd.create_char_5x10(3, &charmap).unwrap();
+    let written = sent(&log);
+    // Slot 3 starts at the CGRAM of the codes 6 and 7.
+    assert_eq!(written[0], (0, 0x40 | 6 << 3));
+    assert_eq!(written[1..12], [(RS, 0x1f); 11]);
+
+    lcd.write_custom_char(3).unwrap();
+    assert_eq!(sent(&log), [(RS, 6)]);
+}
+
+#[test]
+fn tall_font_has_four_slots() {
+    let (mut lcd, log) = lcd(Font::Font5x10);
+    assert_eq!(
+        lcd.create_char_5x10(4, &[0; 11]),
+        Err(Error::InvalidCgramSlot)
+    );
+    assert_eq!(lcd.write_custom_char(4), Err(Error::InvalidCgramSlot));
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn small_font_has_eight_slots() {
+    let (mut lcd, log) = lcd(Font::Font5x8);
+    lcd.write_custom_char(7).unwrap();
+    assert_eq!(sent(&log), [(RS, 7)]);
+    assert_eq!(lcd.write_custom_char(8), Err(Error::InvalidCgramSlot));
+    assert_eq!(lcd.create_char_5x10(0, &[0; 11]), Err(Error::WrongFont));
+}
@@ -0,0 +1,162 @@
+//! Sharing the CGRAM slots between glyphs.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error, Font};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// A glyph with a single pixel in row `row`, different for every `row`.
+fn bitmap(row: usize) -> [u8; 8] {
+    let mut bitmap = [0; 8];
+    bitmap[row] = 0x1f;
+    bitmap
+}
+
+#[test]
+fn glyph_is_written_to_the_first_free_slot() {
+    let (mut lcd, log) = lcd();
+    let arrow = [0x04, 0x0e, 0x15, 0x04, 0x04, 0x04, 0x04, 0x00];
+    let handle = lcd.install_glyph(&arrow).unwrap();
+    assert_eq!(handle.slot(), 0);
+    let mut expected = vec![(0, 0x40)];
+    expected.extend(arrow.iter().map(|&row| (RS, row)));
+    // The cursor goes back to where it was.
+    expected.push((0, 0x80));
+    assert_eq!(sent(&log), expected);
+
+    let second = lcd.install_glyph(&bitmap(0)).unwrap();
+    assert_eq!(second.slot(), 1);
+    assert_eq!(sent(&log)[0], (0, 0x48));
+}
+
+#[test]
+fn same_glyph_shares_its_slot() {
+    let (mut lcd, log) = lcd();
+    let first = lcd.install_glyph(&bitmap(3)).unwrap();
+    log.take();
+    // Bits outside of the five pixels don't make another glyph.
+    let mut same = bitmap(3);
+    same[3] = 0xff;
+    let second = lcd.install_glyph(&same).unwrap();
+    assert_eq!(second, first);
+    assert!(log.take().is_empty());
+
+    // The slot is only freed once both handles are given back.
+    lcd.uninstall_glyph(first);
+    assert_eq!(lcd.install_glyph(&bitmap(4)).unwrap().slot(), 1);
+    lcd.uninstall_glyph(second);
+    assert_eq!(lcd.install_glyph(&bitmap(5)).unwrap().slot(), 0);
+}
+
+#[test]
+fn ninth_glyph_doesnt_fit() {
+    let (mut lcd, log) = lcd();
+    let handles: Vec<_> = (0..8)
+        .map(|row| lcd.install_glyph(&bitmap(row)).unwrap())
+        .collect();
+    let slots: Vec<_> = handles.iter().map(|handle| handle.slot()).collect();
+    assert_eq!(slots, [0, 1, 2, 3, 4, 5, 6, 7]);
+    log.take();
+    let mut ninth = bitmap(0);
+    ninth[1] = 0x01;
+    assert_eq!(lcd.install_glyph(&ninth), Err(Error::CgramFull));
+    assert!(log.take().is_empty());
+    // An installed glyph still is.
+    assert_eq!(lcd.install_glyph(&bitmap(7)).unwrap().slot(), 7);
+}
+
+#[test]
+fn freed_slot_is_reused() {
+    let (mut lcd, log) = lcd();
+    let handles: Vec<_> = (0..8)
+        .map(|row| lcd.install_glyph(&bitmap(row)).unwrap())
+        .collect();
+    let third = handles.into_iter().nth(2).unwrap();
+    lcd.uninstall_glyph(third);
+    log.take();
+    let mut other = bitmap(0);
+    other[1] = 0x01;
+    let handle = lcd.install_glyph(&other).unwrap();
+    assert_eq!(handle.slot(), 2);
+    assert_eq!(sent(&log)[0], (0, 0x50));
+    lcd.write_glyph(&handle).unwrap();
+    assert_eq!(sent(&log), [(RS, 2)]);
+}
+
+#[test]
+fn slot_shared_too_often_stays_reserved() {
+    let (mut lcd, _log) = lcd();
+    let handles: Vec<_> = (0..300)
+        .map(|_| lcd.install_glyph(&bitmap(0)).unwrap())
+        .collect();
+    for row in 1..8 {
+        lcd.install_glyph(&bitmap(row)).unwrap();
+    }
+    for handle in handles {
+        lcd.uninstall_glyph(handle);
+    }
+    let mut other = bitmap(0);
+    other[1] = 0x01;
+    assert_eq!(lcd.install_glyph(&other), Err(Error::CgramFull));
+}
+
+#[test]
+fn tall_font_has_no_glyphs() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = Lcd::<1, 16, Bus, Delay>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_font(Font::Font5x10)
+        .init()
+        .unwrap();
+    log.take();
+    assert_eq!(lcd.install_glyph(&bitmap(0)), Err(Error::WrongFont));
+    assert!(log.take().is_empty());
+
+    // A glyph installed before switching to the tall font isn't in its slots.
+    lcd.font_mode(Font::Font5x8).unwrap();
+    let glyph = lcd.install_glyph(&bitmap(0)).unwrap();
+    lcd.font_mode(Font::Font5x10).unwrap();
+    log.take();
+    assert_eq!(lcd.write_glyph(&glyph), Err(Error::WrongFont));
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn glyph_macro_examples() {
+    let arrow = lcd_lcm1602_i2c::glyph!(
+        "..#..", ".###.", "#.#.#", "..#..", "..#..", "..#..", "..#..", ".....",
+    );
+    assert_eq!(arrow, [0x04, 0x0e, 0x15, 0x04, 0x04, 0x04, 0x04, 0x00]);
+    let heart = lcd_lcm1602_i2c::glyph!(
+        ".....", "01010", "11111", "11111", "01110", "00100", ".....", ".....",
+    );
+    assert_eq!(heart, [0x00, 0x0a, 0x1f, 0x1f, 0x0e, 0x04, 0x00, 0x00]);
+    let degree = lcd_lcm1602_i2c::glyph!(
+        ".##..", "#..#.", "#..#.", ".##..", ".....", ".....", ".....", ".....",
+    );
+    assert_eq!(degree, [0x0c, 0x12, 0x12, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn glyph_macro_output_is_installed_as_is() {
+    let (mut lcd, log) = lcd();
+    const FULL: [u8; 8] = lcd_lcm1602_i2c::glyph!(
+        "#####", "#####", "#####", "#####", "#####", "#####", "#####", "#####",
+    );
+    lcd.create_char(7, &FULL).unwrap();
+    let sent = sent(&log);
+    assert_eq!(sent[0], (0, 0x40 | 7 << 3));
+    assert!(sent[1..9].iter().all(|&code| code == (RS, 0x1f)));
+}
@@ -0,0 +1,84 @@
+//! The same operations on several displays.
+
+mod common;
+
+use std::{cell::Cell, rc::Rc};
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use lcd_lcm1602_i2c::{group::LcdGroup, sync_lcd::Lcd, Error};
+
+/// Bus recording the bytes written to any address, failing at `broken` once `failing` is set.
+struct Shared {
+    log: Log,
+    broken: u8,
+    failing: Rc<Cell<bool>>,
+}
+
+impl ErrorType for Shared {
+    type Error = ErrorKind;
+}
+
+impl I2c for Shared {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.failing.get() && address == self.broken {
+            return Err(ErrorKind::Bus);
+        }
+        for operation in operations {
+            if let Operation::Write(bytes) = operation {
+                for &byte in bytes.iter() {
+                    self.log.push(Event::Write(address, byte));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn failing_display_doesnt_stop_the_others() {
+    let log = Log::default();
+    let failing = Rc::new(Cell::new(false));
+    let display = |address| {
+        let bus = Shared {
+            log: log.clone(),
+            broken: 0x26,
+            failing: failing.clone(),
+        };
+        let delay = Delay { log: log.clone() };
+        Lcd::<2, 16, _, _>::new(bus, delay)
+            .with_address(address)
+            .init()
+            .unwrap()
+    };
+    let mut group = LcdGroup::new([display(0x25), display(0x26), display(0x27)]);
+    log.take();
+
+    failing.set(true);
+    let error = group.write_str("a").unwrap_err();
+    assert_eq!(error.get(0), None);
+    assert_eq!(error.get(1), Some(&Error::I2c(ErrorKind::Bus)));
+    assert_eq!(error.get(2), None);
+    assert_eq!(error.iter().count(), 1);
+
+    // The displays are written in order, the broken one in between is skipped.
+    let addresses: Vec<u8> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(address, _) => Some(address),
+            _ => None,
+        })
+        .collect();
+    let first = addresses.iter().take_while(|&&a| a == 0x25).count();
+    assert_eq!(first, 6);
+    assert!(addresses[first..].iter().all(|&a| a == 0x27));
+    assert_eq!(addresses.len(), 12);
+
+    failing.set(false);
+    assert_eq!(group.write_str("b"), Ok(()));
+}
@@ -0,0 +1,114 @@
+//! The bytes the 74HC595 interface shifts out over SPI.
+
+mod common;
+
+use std::convert::Infallible;
+
+use common::*;
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+use lcd_lcm1602_i2c::{
+    config::{LcdConfig, PinMapping},
+    geometry::Fixed,
+    interface::Hc595Interface,
+    sync_lcd::GenericLcd,
+};
+
+/// SPI device with the latch of the shift register on its chip select, so the outputs are set
+/// to the last byte of every transfer.
+struct Spi {
+    log: Log,
+}
+
+impl ErrorType for Spi {
+    type Error = Infallible;
+}
+
+impl SpiDevice for Spi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let mut latched = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => latched = bytes.last().copied().or(latched),
+                Operation::DelayNs(_) => {}
+                _ => panic!("the shift register is only written to"),
+            }
+        }
+        if let Some(byte) = latched {
+            self.log.push(Event::Shift(byte));
+        }
+        Ok(())
+    }
+}
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Hc595Interface<Spi>, Delay>;
+
+/// Outputs of one nibble, `outputs` without EN.
+fn pulse(outputs: u8, en: u8) -> Vec<Event> {
+    vec![
+        Event::Shift(outputs),
+        Event::Shift(outputs | en),
+        Event::Shift(outputs),
+    ]
+}
+
+fn init(interface: impl FnOnce(Spi) -> Hc595Interface<Spi>) -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let spi = Spi { log: log.clone() };
+    let lcd = TestLcd::init_with_interface(interface(spi), delay, LcdConfig::default()).unwrap();
+    (lcd, log)
+}
+
+#[test]
+fn init_shifts_the_sequence() {
+    let (_, log) = init(Hc595Interface::new);
+    let mut expected = vec![delay_us(80_000), Event::Shift(BL), delay_us(1_000)];
+    for _ in 0..3 {
+        expected.extend(pulse(BL | 0x30, EN));
+        expected.extend([delay_us(700), delay_us(5_000)]);
+    }
+    expected.extend(pulse(BL | 0x20, EN));
+    expected.push(delay_us(700));
+    // 4 bit, two lines, 5x8 font
+    expected.extend(pulse(BL | 0x20, EN));
+    expected.extend(pulse(BL | 0x80, EN));
+    expected.push(delay_us(50));
+    let events = log.take();
+    assert_eq!(events[..expected.len()], expected[..]);
+}
+
+#[test]
+fn character_write() {
+    let (mut lcd, log) = init(Hc595Interface::new);
+    log.take();
+    lcd.write_str("A").unwrap();
+    // 0x41 with RS, the data lines are kept while EN falls.
+    let mut expected = pulse(BL | 0x40 | RS, EN);
+    expected.extend(pulse(BL | 0x10 | RS, EN));
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn pin_mapping() {
+    // D4 to D7 on QA to QD, then RS, RW, EN and the backlight.
+    let pins = PinMapping {
+        rs: 4,
+        rw: 5,
+        en: 6,
+        backlight: 7,
+        data: [0, 1, 2, 3],
+    };
+    let (mut lcd, log) = init(|spi| Hc595Interface::new(spi).with_pin_mapping(pins).unwrap());
+    log.take();
+    lcd.write_str("A").unwrap();
+    let mut expected = pulse(0x80 | 0x04 | 0x10, 0x40);
+    expected.extend(pulse(0x80 | 0x01 | 0x10, 0x40));
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+
+    let mut pins = PinMapping::default();
+    pins.data[0] = 8;
+    assert!(Hc595Interface::new(Spi { log })
+        .with_pin_mapping(pins)
+        .is_none());
+}
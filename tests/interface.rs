@@ -0,0 +1,149 @@
+//! The driver running on an interface other than the PCF8574.
+
+mod common;
+
+use std::convert::Infallible;
+
+use common::*;
+use embedded_hal::delay::DelayNs;
+use lcd_lcm1602_i2c::{
+    config::LcdConfig,
+    geometry::Fixed,
+    interface::LcdInterface,
+    sync_lcd::{GenericLcd, Lcd},
+    Backlight, Error,
+};
+
+/// Interface recording the nibbles in the expander layout of the PCF8574, so the driver has to
+/// produce the same sequences as on the I2C backpack.
+struct Memory {
+    log: Log,
+}
+
+impl Memory {
+    fn backlight_bit(backlight: bool) -> u8 {
+        if backlight {
+            BL
+        } else {
+            0
+        }
+    }
+
+    fn write(&self, byte: u8) {
+        self.log.push(Event::Write(ADDRESS, byte));
+    }
+}
+
+impl LcdInterface for Memory {
+    type Error = Infallible;
+
+    fn write_nibble(
+        &mut self,
+        rs: bool,
+        nibble: u8,
+        backlight: bool,
+        _delay: &mut impl DelayNs,
+    ) -> Result<(), Self::Error> {
+        let byte = nibble << 4 | if rs { RS } else { 0 } | Self::backlight_bit(backlight);
+        self.write(byte);
+        self.write(byte | EN);
+        self.write(Self::backlight_bit(backlight));
+        Ok(())
+    }
+
+    fn set_backlight(&mut self, backlight: bool) -> Result<(), Self::Error> {
+        self.write(Self::backlight_bit(backlight));
+        Ok(())
+    }
+}
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Memory, Delay>;
+
+fn init(config: LcdConfig) -> Result<(TestLcd, Log), Error<Infallible>> {
+    let (bus, delay, log) = fakes();
+    let memory = Memory { log: bus.log };
+    let lcd = TestLcd::init_with_interface(memory, delay, config)?;
+    Ok((lcd, log))
+}
+
+fn lcd() -> (TestLcd, Log) {
+    let (lcd, log) = init(LcdConfig::default()).unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn init_sends_the_sequence_of_the_backpack() {
+    let (_, log) = init(LcdConfig::default()).unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+}
+
+#[test]
+fn init_with_backlight_off() {
+    let (_, log) = init(LcdConfig::default().with_backlight(Backlight::Off)).unwrap();
+    assert_eq!(log.take(), init_sequence(0));
+}
+
+#[test]
+fn operations() {
+    let (mut lcd, log) = lcd();
+    lcd.clear().unwrap();
+    assert_eq!(log.take(), slow_command(0x01));
+    lcd.set_cursor(1, 5).unwrap();
+    assert_eq!(log.take(), command(0xc5));
+    lcd.write_str("Hi!").unwrap();
+    let expected: Vec<_> = b"Hi!".iter().flat_map(|&c| data(c)).collect();
+    assert_eq!(log.take(), expected);
+    lcd.backlight(Backlight::Off).unwrap();
+    assert_eq!(log.take(), [write(0x00)]);
+}
+
+#[test]
+fn create_char() {
+    let (mut lcd, log) = lcd();
+    let rows = [0x1f; 8];
+    lcd.create_char(0, &rows).unwrap();
+    let mut expected = command(0x40);
+    for row in rows {
+        expected.extend(data(row));
+    }
+    expected.extend(command(0x80));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn address_is_left_to_the_interface() {
+    // The address belongs to the PCF8574, so a reserved one doesn't matter here.
+    let config = LcdConfig::default().with_address(0x00);
+    assert!(init(config).is_ok());
+}
+
+#[test]
+fn reading_is_unavailable() {
+    // Busy flag polling falls back to the delays without reads.
+    let (mut lcd, log) = init(LcdConfig::default().with_busy_flag_polling(true)).unwrap();
+    log.take();
+    lcd.clear().unwrap();
+    assert_eq!(log.take(), slow_command(0x01));
+
+    assert_eq!(lcd.read_address_counter(), Err(Error::ReadUnavailable));
+}
+
+#[test]
+fn into_interface() {
+    let (lcd, _) = lcd();
+    let (memory, _) = lcd.into_interface();
+    memory.write(0x00);
+    assert_eq!(memory.log.take(), [write(0x00)]);
+}
+
+#[test]
+fn i2c_driver_is_the_pcf8574_backend() {
+    let (bus, delay, log) = fakes();
+    let lcd = Lcd::<2, 16, Bus, Delay>::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+    assert_eq!(lcd.address(), ADDRESS);
+}
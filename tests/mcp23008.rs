@@ -0,0 +1,109 @@
+//! The registers the MCP23008 interface writes.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    config::{LcdConfig, PinMapping},
+    geometry::Fixed,
+    interface::Mcp23008Interface,
+    sync_lcd::GenericLcd,
+    Backlight,
+};
+
+const MCP_ADDRESS: u8 = 0x20;
+const IODIR: u8 = 0x00;
+const OLAT: u8 = 0x0a;
+const MCP_BL: u8 = 0x80;
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Mcp23008Interface<Bus>, Delay>;
+
+/// A write of `value` to `register`.
+fn register(register: u8, value: u8) -> [Event; 2] {
+    [
+        Event::Write(MCP_ADDRESS, register),
+        Event::Write(MCP_ADDRESS, value),
+    ]
+}
+
+/// Output latches of one nibble, `latch` without EN.
+fn pulse(latch: u8) -> Vec<Event> {
+    [latch, latch | 0x04, latch]
+        .iter()
+        .flat_map(|&latch| register(OLAT, latch))
+        .collect()
+}
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let interface = Mcp23008Interface::new(bus, MCP_ADDRESS);
+    let lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn init_makes_every_pin_an_output() {
+    let (bus, delay, log) = fakes();
+    let interface = Mcp23008Interface::new(bus, MCP_ADDRESS);
+    TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    let events = log.take();
+    let mut expected = vec![delay_us(80_000)];
+    expected.extend(register(IODIR, 0x00));
+    expected.extend(register(OLAT, MCP_BL));
+    expected.push(delay_us(1_000));
+    // The first function set, 0x3 on D4 (GP3) and D5 (GP4).
+    expected.extend(pulse(MCP_BL | 0x18));
+    assert_eq!(events[..expected.len()], expected[..]);
+}
+
+#[test]
+fn re_init_sets_the_direction_again() {
+    let (mut lcd, log) = lcd();
+    lcd.re_init().unwrap();
+    assert_eq!(log.take()[1..3], register(IODIR, 0x00));
+}
+
+#[test]
+fn command() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 5).unwrap();
+    // 0xc on D6 and D7 (GP5 and GP6), then 0x5 on D4 and D6 (GP3 and GP5), RS low.
+    let mut expected = pulse(MCP_BL | 0x60);
+    expected.extend(pulse(MCP_BL | 0x28));
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn data_sets_rs() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("0").unwrap();
+    // 0x30 with RS on GP1.
+    let mut expected = pulse(MCP_BL | 0x18 | 0x02);
+    expected.extend(pulse(MCP_BL | 0x02));
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn backlight() {
+    let (mut lcd, log) = lcd();
+    lcd.backlight(Backlight::Off).unwrap();
+    assert_eq!(log.take(), register(OLAT, 0x00));
+    lcd.backlight(Backlight::On).unwrap();
+    assert_eq!(log.take(), register(OLAT, MCP_BL));
+}
+
+#[test]
+fn pin_mapping() {
+    let (bus, _, _) = fakes();
+    let interface = Mcp23008Interface::new(bus, MCP_ADDRESS);
+    assert!(interface.with_pin_mapping(PinMapping::default()).is_some());
+
+    let (bus, _, _) = fakes();
+    let mut pins = PinMapping::mcp23008();
+    pins.en = pins.rs;
+    let interface = Mcp23008Interface::new(bus, MCP_ADDRESS);
+    assert!(interface.with_pin_mapping(pins).is_none());
+}
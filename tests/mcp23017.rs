@@ -0,0 +1,119 @@
+//! The MCP23017 interface only writing the registers of the port of the display.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    config::LcdConfig,
+    geometry::Fixed,
+    interface::{Mcp23017Interface, Port},
+    sync_lcd::GenericLcd,
+    Backlight,
+};
+
+const MCP_ADDRESS: u8 = 0x20;
+const IODIRA: u8 = 0x00;
+const IODIRB: u8 = 0x01;
+const OLATA: u8 = 0x14;
+const OLATB: u8 = 0x15;
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Mcp23017Interface<Bus>, Delay>;
+
+/// Initialize a display on `port`, write to it and return the registers written, in order.
+fn registers_written(port: Port) -> Vec<(u8, u8)> {
+    let (bus, delay, log) = fakes();
+    let interface = Mcp23017Interface::new(bus, MCP_ADDRESS, port);
+    let mut lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    lcd.print_at(1, 3, "Hi").unwrap();
+    lcd.backlight(Backlight::Off).unwrap();
+    lcd.re_init().unwrap();
+
+    let bytes: Vec<_> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(MCP_ADDRESS, byte) => Some(byte),
+            Event::Write(..) => panic!("write to another address"),
+            _ => None,
+        })
+        .collect();
+    // Every write is a register followed by its value.
+    assert_eq!(bytes.len() % 2, 0);
+    bytes.chunks(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+#[test]
+fn port_b_never_touches_port_a() {
+    let registers = registers_written(Port::B);
+    assert_eq!(registers[0], (IODIRB, 0x00));
+    assert_eq!(registers[1], (OLATB, BL));
+    assert!(registers
+        .iter()
+        .all(|&(register, _)| register == IODIRB || register == OLATB));
+}
+
+#[test]
+fn port_a_never_touches_port_b() {
+    let registers = registers_written(Port::A);
+    assert_eq!(registers[0], (IODIRA, 0x00));
+    assert!(registers
+        .iter()
+        .all(|&(register, _)| register == IODIRA || register == OLATA));
+}
+
+#[test]
+fn command_through_the_output_latch() {
+    let (bus, delay, log) = fakes();
+    let interface = Mcp23017Interface::new(bus, MCP_ADDRESS, Port::B);
+    let mut lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    log.take();
+    lcd.set_cursor(1, 5).unwrap();
+    // The port is wired like the PCF8574 by default, the data lines are kept while EN falls.
+    let latch = |value: u8| {
+        [
+            Event::Write(MCP_ADDRESS, OLATB),
+            Event::Write(MCP_ADDRESS, value),
+        ]
+    };
+    let mut expected = Vec::new();
+    for value in [0xc8, 0xcc, 0xc8] {
+        expected.extend(latch(value));
+    }
+    for value in [0x58, 0x5c, 0x58] {
+        expected.extend(latch(value));
+    }
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn byte_on_the_other_port_with_an_8_bit_bus() {
+    let (bus, delay, log) = fakes();
+    let interface = Mcp23017Interface::new(bus, MCP_ADDRESS, Port::B).with_8_bit_bus();
+    let mut lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    let events = log.take();
+    // Both ports are made outputs.
+    assert_eq!(
+        events[1..5],
+        [
+            Event::Write(MCP_ADDRESS, IODIRA),
+            Event::Write(MCP_ADDRESS, 0x00),
+            Event::Write(MCP_ADDRESS, IODIRB),
+            Event::Write(MCP_ADDRESS, 0x00),
+        ]
+    );
+    lcd.set_cursor(1, 5).unwrap();
+    // The whole byte is set on port A, then EN is pulsed once on port B.
+    let mut expected = vec![
+        Event::Write(MCP_ADDRESS, OLATA),
+        Event::Write(MCP_ADDRESS, 0xc5),
+    ];
+    for value in [0x08, 0x0c, 0x08] {
+        expected.extend([
+            Event::Write(MCP_ADDRESS, OLATB),
+            Event::Write(MCP_ADDRESS, value),
+        ]);
+    }
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
@@ -0,0 +1,112 @@
+//! Writing numbers without `core::fmt`.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// The character codes written to the display, failing on anything else.
+fn written(log: &Log) -> Vec<u8> {
+    sent(log)
+        .into_iter()
+        .map(|(rs, code)| {
+            assert_eq!(rs, RS, "instruction {:#04x} sent", code);
+            code
+        })
+        .collect()
+}
+
+#[test]
+fn unsigned_numbers() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_u32(0), Ok(1));
+    assert_eq!(written(&log), b"0");
+    assert_eq!(lcd.write_u32(u32::MAX), Ok(10));
+    assert_eq!(written(&log), b"4294967295");
+    assert_eq!(lcd.write_u16(u16::MAX), Ok(5));
+    assert_eq!(written(&log), b"65535");
+}
+
+#[test]
+fn u8_is_written_as_one_transfer() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_u8(u8::MAX), Ok(3));
+    assert_eq!(log.take(), text(b"255"));
+}
+
+#[test]
+fn signed_numbers() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_i32(0), Ok(1));
+    assert_eq!(written(&log), b"0");
+    assert_eq!(lcd.write_i32(-7), Ok(2));
+    assert_eq!(written(&log), b"-7");
+    assert_eq!(lcd.write_i32(i32::MAX), Ok(10));
+    assert_eq!(written(&log), b"2147483647");
+    lcd.set_cursor(1, 0).unwrap();
+    log.take();
+    assert_eq!(lcd.write_i32(i32::MIN), Ok(11));
+    assert_eq!(written(&log), b"-2147483648");
+}
+
+#[test]
+fn fixed_point_numbers() {
+    let (mut lcd, log) = lcd();
+    let cases: [(i32, u8, &[u8]); 8] = [
+        (-2347, 2, b"-23.47"),
+        (0, 0, b"0"),
+        (0, 2, b"0.00"),
+        (42, 0, b"42"),
+        (5, 2, b"0.05"),
+        (-5, 3, b"-0.005"),
+        (i32::MAX, 10, b"0.2147483647"),
+        (i32::MIN, 10, b"-0.2147483648"),
+    ];
+    for (value, decimals, expected) in cases {
+        lcd.set_cursor(0, 0).unwrap();
+        log.take();
+        assert_eq!(
+            lcd.write_fixed(value, decimals),
+            Ok(expected.len() as u8),
+            "{} with {} decimals",
+            value,
+            decimals
+        );
+        assert_eq!(written(&log), expected);
+    }
+}
+
+#[test]
+fn fixed_point_numbers_are_padded_to_the_width() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_fixed_width(215, 1, 6), Ok(6));
+    assert_eq!(written(&log), b"  21.5");
+    // Numbers at least as wide aren't padded, or cut.
+    assert_eq!(lcd.write_fixed_width(-215, 1, 0), Ok(5));
+    assert_eq!(written(&log), b"-21.5");
+    assert_eq!(lcd.write_fixed_width(-215, 1, 3), Ok(5));
+    assert_eq!(written(&log), b"-21.5");
+}
+
+#[test]
+fn more_decimals_than_digits_are_rejected() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_fixed(5, 11), Err(Error::InvalidDecimals));
+    assert_eq!(
+        lcd.write_fixed_width(5, u8::MAX, 4),
+        Err(Error::InvalidDecimals)
+    );
+    assert!(log.take().is_empty());
+}
@@ -0,0 +1,71 @@
+//! Text and numbers written past the end of a row.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{assert_display, sync_lcd::Lcd, Error, OverflowPolicy};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+/// Longer than both rows together.
+const LONG: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCD";
+
+fn lcd(policy: OverflowPolicy) -> TestLcd {
+    let (bus, delay, _log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_overflow_policy(policy)
+        .init()
+        .unwrap()
+}
+
+#[test]
+fn truncate_drops_the_rest_of_the_row() {
+    let mut lcd = lcd(OverflowPolicy::Truncate);
+    assert_eq!(lcd.print_at(0, 0, LONG), Ok(16));
+    assert_display!(lcd.snapshot(), "0123456789abcdef");
+    assert_eq!(lcd.get_cursor(), (0, 16));
+
+    assert_eq!(lcd.set_cursor(1, 14), Ok(()));
+    assert_eq!(lcd.write_u32(12345), Ok(2));
+    assert_display!(lcd.snapshot(), "0123456789abcdef\n              12");
+}
+
+#[test]
+fn wrap_row_continues_on_the_next_row() {
+    let mut lcd = lcd(OverflowPolicy::WrapRow);
+    assert_eq!(lcd.print_at(0, 0, LONG), Ok(32));
+    // The newline after the last row clips the rest.
+    assert_display!(lcd.snapshot(), "0123456789abcdef\nghijklmnopqrstuv");
+
+    lcd.clear().unwrap();
+    lcd.set_cursor(0, 13).unwrap();
+    assert_eq!(lcd.write_fixed_width(-215, 1, 6), Ok(6));
+    assert_display!(lcd.snapshot(), "              -2\n1.5");
+}
+
+#[test]
+fn scroll_moves_the_rows_up() {
+    let mut lcd = lcd(OverflowPolicy::Scroll);
+    assert_eq!(lcd.print_at(0, 0, LONG), Ok(40));
+    assert_display!(lcd.snapshot(), "ghijklmnopqrstuv\nwxyzABCD");
+    assert_eq!(lcd.get_cursor(), (1, 8));
+
+    lcd.set_cursor(1, 14).unwrap();
+    assert_eq!(lcd.write_i32(-1234), Ok(5));
+    assert_display!(lcd.snapshot(), "wxyzABCD      -1\n234");
+}
+
+#[test]
+fn error_stops_at_the_end_of_the_row() {
+    let mut lcd = lcd(OverflowPolicy::Error);
+    assert_eq!(
+        lcd.print_at(0, 0, LONG),
+        Err(Error::RowOverflow { written: 16 })
+    );
+    assert_display!(lcd.snapshot(), "0123456789abcdef");
+
+    lcd.set_cursor(1, 14).unwrap();
+    assert_eq!(lcd.write_u32(12345), Err(Error::RowOverflow { written: 2 }));
+    assert_display!(lcd.snapshot(), "0123456789abcdef\n              12");
+}
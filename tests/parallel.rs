@@ -0,0 +1,247 @@
+//! The pins the parallel interface toggles for a command and a character.
+
+mod common;
+
+use std::convert::Infallible;
+
+use common::*;
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+use lcd_lcm1602_i2c::{
+    config::LcdConfig,
+    geometry::Fixed,
+    interface::{ParallelInterface, ReadableBus},
+    sync_lcd::GenericLcd,
+    Backlight, Error,
+};
+
+/// Pin recording its changes under `name`, reading returns `input`.
+struct Pin {
+    name: &'static str,
+    log: Log,
+    input: bool,
+}
+
+impl ErrorType for Pin {
+    type Error = Infallible;
+}
+
+impl OutputPin for Pin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.log.push(Event::Pin(self.name, false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.log.push(Event::Pin(self.name, true));
+        Ok(())
+    }
+}
+
+impl InputPin for Pin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.input)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.input)
+    }
+}
+
+fn pin(name: &'static str, log: &Log) -> Pin {
+    Pin {
+        name,
+        log: log.clone(),
+        input: false,
+    }
+}
+
+fn data_pins(log: &Log) -> [Pin; 4] {
+    ["d4", "d5", "d6", "d7"].map(|name| pin(name, log))
+}
+
+/// Set RS and D4 to D7, then pulse EN.
+fn pulse(rs: bool, nibble: u8) -> Vec<Event> {
+    let mut events = vec![Event::Pin("rs", rs)];
+    for (bit, name) in ["d4", "d5", "d6", "d7"].iter().enumerate() {
+        events.push(Event::Pin(name, nibble & (1 << bit) != 0));
+    }
+    events.extend([
+        Event::Pin("en", true),
+        Event::Delay(1_000),
+        Event::Pin("en", false),
+        Event::Delay(1_000),
+    ]);
+    events
+}
+
+/// Both nibbles of `byte` with the default timings, see `common::byte`.
+fn pulses(rs: bool, byte: u8, wait_us: u64) -> Vec<Event> {
+    let mut events = pulse(rs, byte >> 4);
+    events.extend(pulse(rs, byte & 0x0f));
+    events.push(delay_us(wait_us));
+    events
+}
+
+type WriteOnly = ParallelInterface<Pin, Pin, [Pin; 4], Pin>;
+type TestLcd = GenericLcd<Fixed<2, 16>, WriteOnly, Delay>;
+
+fn interface(log: &Log) -> WriteOnly {
+    ParallelInterface::new(pin("rs", log), pin("en", log), data_pins(log))
+        .with_backlight(pin("bl", log))
+}
+
+fn lcd() -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let lcd = TestLcd::init_with_interface(interface(&log), delay, LcdConfig::default()).unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn init_switches_to_4_bit_mode() {
+    let (_, delay, log) = fakes();
+    TestLcd::init_with_interface(interface(&log), delay, LcdConfig::default()).unwrap();
+    let mut expected = vec![delay_us(80_000), Event::Pin("bl", true), delay_us(1_000)];
+    for _ in 0..3 {
+        expected.extend(pulse(false, 0x3));
+        expected.extend([delay_us(700), delay_us(5_000)]);
+    }
+    expected.extend(pulse(false, 0x2));
+    expected.push(delay_us(700));
+    expected.extend(pulses(false, 0x28, 50));
+    assert_eq!(log.take()[..expected.len()], expected[..]);
+}
+
+#[test]
+fn command() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 5).unwrap();
+    assert_eq!(log.take(), pulses(false, 0xc5, 50));
+}
+
+#[test]
+fn data_byte() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("H").unwrap();
+    assert_eq!(log.take(), pulses(true, b'H', 50));
+}
+
+#[test]
+fn backlight() {
+    let (mut lcd, log) = lcd();
+    lcd.backlight(Backlight::Off).unwrap();
+    assert_eq!(log.take(), [Event::Pin("bl", false)]);
+}
+
+#[test]
+fn write_only_bus_cant_read() {
+    let (mut lcd, _) = lcd();
+    assert_eq!(lcd.read_address_counter(), Err(Error::ReadUnavailable));
+}
+
+#[test]
+fn readable_bus() {
+    let (_, delay, log) = fakes();
+    let bus = ReadableBus::new(data_pins(&log), pin("rw", &log));
+    let interface = ParallelInterface::new(pin("rs", &log), pin("en", &log), bus);
+    let mut lcd = GenericLcd::<Fixed<2, 16>, _, _>::init_with_interface(
+        interface,
+        delay,
+        LcdConfig::default(),
+    )
+    .unwrap();
+    log.take();
+
+    // The data lines read low: not busy, address counter 0.
+    assert_eq!(lcd.read_address_counter(), Ok(0));
+    let mut expected = Vec::new();
+    for _ in 0..2 {
+        expected.extend([
+            Event::Pin("rs", false),
+            Event::Pin("d4", true),
+            Event::Pin("d5", true),
+            Event::Pin("d6", true),
+            Event::Pin("d7", true),
+            Event::Pin("rw", true),
+            Event::Pin("en", true),
+            Event::Delay(1_000),
+            Event::Pin("en", false),
+            Event::Delay(1_000),
+        ]);
+    }
+    assert_eq!(log.take(), expected);
+
+    // Writing drives RW low again.
+    lcd.write_str("H").unwrap();
+    assert_eq!(
+        log.take()[..2],
+        [Event::Pin("rs", true), Event::Pin("rw", false)]
+    );
+}
+
+/// Set RS and D0 to D7, then pulse EN once.
+fn pulse_8_bit(rs: bool, byte: u8) -> Vec<Event> {
+    let mut events = vec![Event::Pin("rs", rs)];
+    for (bit, name) in ["d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7"]
+        .iter()
+        .enumerate()
+    {
+        events.push(Event::Pin(name, byte & (1 << bit) != 0));
+    }
+    events.extend([
+        Event::Pin("en", true),
+        Event::Delay(1_000),
+        Event::Pin("en", false),
+        Event::Delay(1_000),
+    ]);
+    events
+}
+
+type EightBit = ParallelInterface<Pin, Pin, [Pin; 8]>;
+
+fn lcd_8_bit(log: &Log, delay: Delay) -> GenericLcd<Fixed<2, 16>, EightBit, Delay> {
+    let bus = ["d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7"].map(|name| pin(name, log));
+    let interface = ParallelInterface::new(pin("rs", log), pin("en", log), bus);
+    GenericLcd::<Fixed<2, 16>, _, _>::init_with_interface(interface, delay, LcdConfig::default())
+        .unwrap()
+}
+
+#[test]
+fn init_stays_in_8_bit_mode() {
+    let (_, delay, log) = fakes();
+    lcd_8_bit(&log, delay);
+    let mut expected = vec![delay_us(80_000), delay_us(1_000)];
+    for _ in 0..3 {
+        expected.extend(pulse_8_bit(false, 0x30));
+        expected.push(delay_us(5_000));
+    }
+    // No switch to 4 bit mode, the function set keeps the 8 bit flag.
+    expected.extend(pulse_8_bit(false, 0x38));
+    expected.push(delay_us(50));
+    assert_eq!(log.take()[..expected.len()], expected[..]);
+}
+
+#[test]
+fn one_strobe_per_byte_in_8_bit_mode() {
+    let (_, delay, log) = fakes();
+    let mut lcd = lcd_8_bit(&log, delay);
+    log.take();
+    lcd.write_str("Hi").unwrap();
+    let mut expected = pulse_8_bit(true, b'H');
+    expected.push(delay_us(50));
+    expected.extend(pulse_8_bit(true, b'i'));
+    expected.push(delay_us(50));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn two_strobes_per_byte_in_4_bit_mode() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("Hi").unwrap();
+    let strobes = log
+        .take()
+        .into_iter()
+        .filter(|event| *event == Event::Pin("en", true))
+        .count();
+    assert_eq!(strobes, 4);
+}
@@ -0,0 +1,124 @@
+//! Finding out if and where a backpack answers before the init.
+
+mod common;
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use lcd_lcm1602_i2c::{
+    sync_lcd::{Lcd, LcdBuilder},
+    Error, BACKPACK_ADDRESSES,
+};
+
+/// Bus with backpacks at the `present` addresses, failing with a bus error at `broken`.
+struct Backpacks {
+    present: Vec<u8>,
+    broken: Option<u8>,
+    log: Log,
+}
+
+impl ErrorType for Backpacks {
+    type Error = ErrorKind;
+}
+
+impl I2c for Backpacks {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.broken == Some(address) {
+            return Err(ErrorKind::Bus);
+        }
+        if !self.present.contains(&address) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            if let Operation::Write(bytes) = operation {
+                for &byte in bytes.iter() {
+                    self.log.push(Event::Write(address, byte));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn builder(present: &[u8], broken: Option<u8>) -> (LcdBuilder<2, 16, Backpacks, Delay>, Log) {
+    let (_, delay, log) = fakes();
+    let bus = Backpacks {
+        present: present.to_vec(),
+        broken,
+        log: log.clone(),
+    };
+    (Lcd::<2, 16, _, _>::new(bus, delay), log)
+}
+
+#[test]
+fn present_display_answers() {
+    let (builder, log) = builder(&[ADDRESS], None);
+    let mut builder = builder.with_address(ADDRESS);
+    assert_eq!(builder.probe(), Ok(true));
+    // Only the backlight, EN stays low.
+    assert_eq!(log.take(), [write(BL)]);
+}
+
+#[test]
+fn absent_display_doesnt_answer() {
+    let (builder, log) = builder(&[], None);
+    let mut builder = builder.with_address(ADDRESS);
+    assert_eq!(builder.probe(), Ok(false));
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn bus_error_is_returned() {
+    let (builder, _log) = builder(&[ADDRESS], Some(ADDRESS));
+    let mut builder = builder.with_address(ADDRESS);
+    assert_eq!(builder.probe(), Err(Error::I2c(ErrorKind::Bus)));
+}
+
+#[test]
+fn first_answering_candidate_is_used() {
+    let (mut builder, log) = builder(&[0x3f], None);
+    assert_eq!(builder.detect_address(&BACKPACK_ADDRESSES), Ok(Some(0x3f)));
+    // The other candidates didn't acknowledge the backlight state.
+    assert_eq!(log.take(), [Event::Write(0x3f, BL)]);
+
+    let lcd = builder.init().unwrap();
+    assert_eq!(lcd.address(), 0x3f);
+    let at_0x3f: Vec<_> = init_sequence(BL)
+        .into_iter()
+        .map(|event| match event {
+            Event::Write(_, byte) => Event::Write(0x3f, byte),
+            event => event,
+        })
+        .collect();
+    assert_eq!(log.take(), at_0x3f);
+}
+
+#[test]
+fn configured_address_is_kept_without_an_answer() {
+    let (builder, _log) = builder(&[ADDRESS], None);
+    let mut builder = builder.with_address(ADDRESS);
+    assert_eq!(builder.detect_address(&[0x3f, 0x38]), Ok(None));
+    assert_eq!(builder.init().unwrap().address(), ADDRESS);
+}
+
+#[test]
+fn bus_error_stops_the_detection() {
+    let (mut builder, log) = builder(&[0x3f], Some(0x20));
+    assert_eq!(
+        builder.detect_address(&[0x20, 0x3f]),
+        Err(Error::I2c(ErrorKind::Bus))
+    );
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn reserved_candidates_are_rejected() {
+    let (mut builder, _log) = builder(&[0x3f], None);
+    assert_eq!(
+        builder.detect_address(&[0x78, 0x3f]),
+        Err(Error::ReservedAddress(0x78))
+    );
+}
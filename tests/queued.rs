@@ -0,0 +1,80 @@
+//! Queueing the expander writes and sending them from `poll`.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{queued::QueuedLcd, sync_lcd::Lcd, Error, UnmappablePolicy};
+
+type TestQueue<const N: usize> = QueuedLcd<lcd_lcm1602_i2c::geometry::Fixed<2, 16>, Bus, N>;
+
+fn lcd<const N: usize>(policy: UnmappablePolicy) -> (TestQueue<N>, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_unmappable_policy(policy)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd.into_queued::<N>().0, log)
+}
+
+/// The expander writes of a character, without the wait after it.
+fn writes(code: u8) -> Vec<Event> {
+    let mut events = data(code);
+    events.pop();
+    events
+}
+
+#[test]
+fn poll_waits_for_the_display_between_characters() {
+    let (mut lcd, log) = lcd::<64>(UnmappablePolicy::Replace(b'?'));
+    lcd.write_str("ab").unwrap();
+    assert!(log.take().is_empty());
+    for _ in 0..6 {
+        assert_eq!(lcd.poll(1_000), Ok(true));
+    }
+    assert_eq!(log.take(), writes(b'a'));
+
+    // The character takes 50 µs after its last write.
+    assert_eq!(lcd.poll(1_049), Ok(true));
+    assert!(log.take().is_empty());
+    for _ in 0..5 {
+        assert_eq!(lcd.poll(1_050), Ok(true));
+    }
+    assert_eq!(lcd.poll(1_050), Ok(false));
+    assert_eq!(log.take(), writes(b'b'));
+    assert!(lcd.is_idle());
+    assert_eq!(lcd.poll(1_050), Ok(false));
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn print_at_fits_exactly() {
+    let (mut lcd, log) = lcd::<18>(UnmappablePolicy::Replace(b'?'));
+    lcd.print_at(1, 3, "ab").unwrap();
+    assert_eq!(lcd.free(), 0);
+    assert_eq!(lcd.get_cursor(), (1, 5));
+    while lcd.poll(u64::MAX).unwrap() {}
+    assert_eq!(sent(&log), [(0, 0x80 | 0x43), (RS, b'a'), (RS, b'b')]);
+}
+
+#[test]
+fn print_at_queues_nothing_if_the_text_doesnt_fit() {
+    let (mut lcd, _log) = lcd::<12>(UnmappablePolicy::Replace(b'?'));
+    assert_eq!(lcd.print_at(1, 3, "ab"), Err(Error::QueueFull));
+    assert!(lcd.is_idle());
+    assert_eq!(lcd.get_cursor(), (0, 0));
+}
+
+#[test]
+fn print_at_queues_nothing_for_an_unmappable_character() {
+    let (mut lcd, _log) = lcd::<64>(UnmappablePolicy::Error);
+    assert_eq!(
+        lcd.print_at(1, 3, "a😀"),
+        Err(Error::UnmappableChar { index: 1 })
+    );
+    assert!(lcd.is_idle());
+    assert_eq!(lcd.get_cursor(), (0, 0));
+    assert_eq!(lcd.print_at(2, 0, "a"), Err(Error::InvalidRow));
+    assert!(lcd.is_idle());
+}
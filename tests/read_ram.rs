@@ -0,0 +1,129 @@
+//! Reading the RAM back through the PCF8574, and setting the address counter again afterwards.
+
+mod common;
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error};
+
+/// Bus answering the first `reads` reads like [`Bus`], failing the ones after.
+struct FailingReads {
+    bus: Bus,
+    reads: u8,
+}
+
+impl ErrorType for FailingReads {
+    type Error = ErrorKind;
+}
+
+impl I2c for FailingReads {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::Read(_)))
+        {
+            if self.reads == 0 {
+                return Err(ErrorKind::Bus);
+            }
+            self.reads -= 1;
+        }
+        self.bus
+            .transaction(address, operations)
+            .map_err(|e| match e {})
+    }
+}
+
+/// A 16x2 display with the cursor at (1, 3), reading `0x40` from the expander.
+fn lcd(reads: u8) -> (Lcd<2, 16, FailingReads, Delay>, Log) {
+    let (mut bus, delay, log) = fakes();
+    bus.read = 0x40;
+    let mut lcd = Lcd::<2, 16, _, _>::new(FailingReads { bus, reads }, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    lcd.set_cursor(1, 3).unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// RW high, the data lines released and EN pulsed: a nibble read from the expander.
+fn read_nibble(rs: u8) -> Vec<Event> {
+    let read = 0xf0 | 0x02 | rs | BL;
+    vec![
+        write(read),
+        write(read | EN),
+        Event::Read(ADDRESS),
+        write(read),
+    ]
+}
+
+/// The status read before the RAM address is set.
+fn status() -> Vec<Event> {
+    let mut events = read_nibble(0);
+    events.extend(read_nibble(0));
+    events
+}
+
+/// A byte read from the RAM, then the wait for the address counter to move on.
+fn read_data() -> Vec<Event> {
+    let mut events = read_nibble(RS);
+    events.extend(read_nibble(RS));
+    events.push(delay_us(50));
+    events
+}
+
+#[test]
+fn ddram_is_read_back() {
+    let (mut lcd, log) = lcd(u8::MAX);
+    let mut buf = [0; 2];
+    lcd.read_ddram(0, 5, &mut buf).unwrap();
+    // Each nibble reads the upper four bits of 0x40.
+    assert_eq!(buf, [0x44; 2]);
+
+    let mut expected = status();
+    expected.extend(command(0x85));
+    expected.extend(read_data());
+    expected.extend(read_data());
+    // The cursor is moved back to where it was.
+    expected.extend(command(0xc3));
+    assert_eq!(log.take(), expected);
+    assert_eq!(lcd.get_cursor(), (1, 3));
+}
+
+#[test]
+fn failed_ddram_read_moves_the_address_back() {
+    // The status and the first byte are read, the second byte fails.
+    let (mut lcd, log) = lcd(4);
+    let mut buf = [0; 2];
+    assert_eq!(
+        lcd.read_ddram(0, 5, &mut buf),
+        Err(Error::I2c(ErrorKind::Bus))
+    );
+
+    let mut expected = status();
+    expected.extend(command(0x85));
+    expected.extend(read_data());
+    let read = 0xf0 | 0x02 | RS | BL;
+    expected.extend([write(read), write(read | EN)]);
+    expected.extend(command(0xc3));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn failed_cgram_read_moves_the_address_back() {
+    // Only the status is read.
+    let (mut lcd, log) = lcd(2);
+    let mut buf = [0; 8];
+    assert_eq!(lcd.read_cgram(1, &mut buf), Err(Error::I2c(ErrorKind::Bus)));
+
+    let mut expected = status();
+    expected.extend(command(0x48));
+    let read = 0xf0 | 0x02 | RS | BL;
+    expected.extend([write(read), write(read | EN)]);
+    expected.extend(command(0xc3));
+    assert_eq!(log.take(), expected);
+}
@@ -0,0 +1,122 @@
+//! The PCA9633 of the Grove RGB backlight.
+
+#![cfg(feature = "rgb")]
+
+mod common;
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use lcd_lcm1602_i2c::{
+    config::LcdConfig,
+    geometry::Fixed,
+    interface::Aip31068Interface,
+    rgb::{RgbBacklight, RgbLcd, PCA9633_ADDRESS},
+    sync_lcd::GenericLcd,
+    Error,
+};
+
+/// A write of `value` to `register` of the LED driver.
+fn register(register: u8, value: u8) -> Event {
+    Event::Transfer(PCA9633_ADDRESS, vec![register, value])
+}
+
+fn backlight() -> (RgbBacklight<Transfers>, Log) {
+    let log = Log::default();
+    let backlight = RgbBacklight::new(Transfers { log: log.clone() });
+    (backlight, log)
+}
+
+#[test]
+fn init() {
+    let (mut backlight, log) = backlight();
+    backlight.init().unwrap();
+    // Awake, group blinking, every LED on its own and the group brightness.
+    assert_eq!(
+        log.take(),
+        [
+            register(0x00, 0x00),
+            register(0x01, 0x20),
+            register(0x08, 0xff)
+        ]
+    );
+}
+
+#[test]
+fn set_rgb() {
+    let (mut backlight, log) = backlight();
+    backlight.set_rgb(255, 128, 0).unwrap();
+    // Red on PWM2, green on PWM1, blue on PWM0.
+    assert_eq!(
+        log.take(),
+        [register(0x04, 255), register(0x03, 128), register(0x02, 0)]
+    );
+}
+
+/// Bus of a clone without the LED driver, nothing acknowledges.
+struct Absent;
+
+impl ErrorType for Absent {
+    type Error = ErrorKind;
+}
+
+impl I2c for Absent {
+    fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+    }
+}
+
+#[test]
+fn missing_led_driver() {
+    let mut backlight = RgbBacklight::new(Absent);
+    assert_eq!(backlight.init(), Err(Error::BacklightNotFound));
+}
+
+/// Bus failing with something else than a missing acknowledge.
+struct Broken;
+
+impl ErrorType for Broken {
+    type Error = ErrorKind;
+}
+
+impl I2c for Broken {
+    fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        Err(ErrorKind::ArbitrationLoss)
+    }
+}
+
+#[test]
+fn bus_error_isnt_a_missing_led_driver() {
+    let mut backlight = RgbBacklight::new(Broken);
+    assert_eq!(
+        backlight.init(),
+        Err(Error::I2c(ErrorKind::ArbitrationLoss))
+    );
+}
+
+#[test]
+fn display_with_backlight() {
+    let (_, delay, log) = fakes();
+    let interface = Aip31068Interface::new(Transfers { log: log.clone() });
+    let lcd = GenericLcd::<Fixed<2, 16>, _, _>::init_with_interface(
+        interface,
+        delay,
+        LcdConfig::default(),
+    )
+    .unwrap();
+    let backlight = RgbBacklight::new(Transfers { log: log.clone() });
+    let mut lcd = RgbLcd::new(lcd, backlight);
+    log.take();
+
+    lcd.backlight_rgb(255, 0, 0).unwrap();
+    lcd.write_str("A").unwrap();
+    assert_eq!(
+        log.take(),
+        [
+            register(0x04, 255),
+            register(0x03, 0),
+            register(0x02, 0),
+            Event::Transfer(0x3e, vec![0x40, b'A']),
+            delay_us(50),
+        ]
+    );
+}
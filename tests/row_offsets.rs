@@ -0,0 +1,51 @@
+//! Rows starting at custom DDRAM offsets.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Error};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+#[test]
+fn cursor_is_relative_to_the_offsets() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_row_offsets([0x04, 0x44, 0x00, 0x00])
+        .init()
+        .unwrap();
+    log.take();
+
+    lcd.set_cursor(0, 0).unwrap();
+    assert_eq!(log.take(), command(0x80 | 0x04));
+    assert_eq!(lcd.get_cursor(), (0, 0));
+
+    lcd.set_cursor(1, 2).unwrap();
+    assert_eq!(log.take(), command(0x80 | 0x46));
+    assert_eq!(lcd.get_cursor(), (1, 2));
+
+    // Home is left of the first row.
+    lcd.return_home().unwrap();
+    assert_eq!(lcd.get_cursor(), (0, 0));
+}
+
+#[test]
+fn offset_between_the_lines_is_rejected() {
+    let (bus, delay, _) = fakes();
+    let result = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_row_offsets([0x00, 0x30, 0x00, 0x00])
+        .init();
+    assert_eq!(result.err(), Some(Error::InvalidRowOffset));
+}
+
+#[test]
+fn one_line_has_no_gap() {
+    let (bus, delay, _) = fakes();
+    let result = Lcd::<1, 16, Bus, Delay>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_row_offsets([0x30, 0x00, 0x00, 0x00])
+        .init();
+    assert!(result.is_ok());
+}
@@ -0,0 +1,90 @@
+//! Writing whole rows.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{assert_display, sync_lcd::Lcd, Error, UnmappablePolicy};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+/// The cursor set to `address`, then `row` written.
+fn row_at(address: u8, row: &[u8; 16]) -> Vec<(u8, u8)> {
+    let mut sent = vec![(0, 0x80 | address)];
+    sent.extend(row.iter().map(|&code| (RS, code)));
+    sent
+}
+
+#[test]
+fn short_line_is_padded() {
+    let (mut lcd, log) = lcd();
+    lcd.write_line(1, "Hi").unwrap();
+    assert_eq!(sent(&log), row_at(0x40, b"Hi              "));
+    assert_eq!(lcd.get_cursor(), (1, 16));
+}
+
+#[test]
+fn long_line_is_truncated() {
+    let (mut lcd, log) = lcd();
+    lcd.write_line(0, "0123456789abcdefghij").unwrap();
+    assert_eq!(sent(&log), row_at(0x00, b"0123456789abcdef"));
+    assert_display!(lcd.snapshot(), "0123456789abcdef");
+}
+
+#[test]
+fn line_is_sent_as_one_transfer() {
+    let (mut lcd, log) = lcd();
+    lcd.write_line(1, "Hi").unwrap();
+    let mut expected = command(0xc0);
+    expected.extend(text(b"Hi              "));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn control_characters_are_replaced() {
+    let (mut lcd, log) = lcd();
+    lcd.write_line(0, "a\nb\tc").unwrap();
+    assert_eq!(sent(&log), row_at(0x00, b"a?b?c           "));
+    assert_eq!(lcd.get_cursor(), (0, 16));
+}
+
+#[test]
+fn control_characters_are_rejected_before_writing() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_unmappable_policy(UnmappablePolicy::Error)
+        .init()
+        .unwrap();
+    log.take();
+    assert_eq!(
+        lcd.write_line(0, "ab\r"),
+        Err(Error::UnmappableChar { index: 2 })
+    );
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn centered_and_right_aligned_lines() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str_centered(0, "Menu").unwrap();
+    assert_eq!(sent(&log), row_at(0x00, b"      Menu      "));
+    lcd.write_str_right(1, "42%").unwrap();
+    assert_eq!(sent(&log), row_at(0x40, b"             42%"));
+}
+
+#[test]
+fn invalid_row_is_rejected() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.write_line(2, "Hi"), Err(Error::InvalidRow));
+    assert!(log.take().is_empty());
+}
@@ -0,0 +1,85 @@
+//! The init of the RS0010 of Winstar WEH character OLEDs.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Controller};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn init(controller: Controller) -> Vec<Event> {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_controller(controller)
+        .init()
+        .unwrap();
+    log.take()
+}
+
+#[test]
+fn hd44780_is_the_default() {
+    assert_eq!(init(Controller::Hd44780), init_sequence(BL));
+}
+
+#[test]
+fn init_syncs_once_without_the_power_on_delay() {
+    let mut expected = vec![write(BL), delay_us(1_000)];
+    // A single function set in 8 bit mode, then the switch to 4 bit mode.
+    expected.extend(nibble(0, 0x3, BL));
+    expected.extend([delay_us(700), delay_us(5_000)]);
+    expected.extend(nibble(0, 0x2, BL));
+    expected.extend([delay_us(700), delay_us(5_000)]);
+    // 4 bit, two lines, 5x8 font and the English/Japanese font table
+    expected.extend(command(0x28));
+    // Character mode with the internal power on
+    expected.extend(command(0x17));
+    expected.extend(command(0x0c));
+    expected.extend(slow_command(0x01));
+    expected.extend(command(0x06));
+    expected.extend(slow_command(0x02));
+
+    let events = init(Controller::Rs0010);
+    assert_eq!(events, expected);
+    assert_ne!(events, init_sequence(BL));
+}
+
+#[test]
+fn init_selects_character_mode() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_controller(Controller::Rs0010)
+        .init()
+        .unwrap();
+    let strobes: Vec<u8> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(_, byte) if byte & EN != 0 => Some(byte >> 4),
+            _ => None,
+        })
+        .collect();
+    // The mode instruction follows the sync nibbles and the function set.
+    let mode = (strobes[4] << 4) | strobes[5];
+    assert_eq!(mode & 0xf3, 0x13);
+    assert_eq!(mode & 0x08, 0, "graphic mode bit set");
+    assert_eq!(mode & 0x04, 0x04, "internal power off");
+}
+
+#[test]
+fn re_init_switches_back_to_character_mode() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_controller(Controller::Rs0010)
+        .init()
+        .unwrap();
+    // Graphic mode with the internal power on
+    lcd.raw_command(0x1f).unwrap();
+    log.take();
+    lcd.re_init().unwrap();
+    let events = log.take();
+    let mode = command(0x17);
+    assert!(events.windows(mode.len()).any(|window| window == mode));
+}
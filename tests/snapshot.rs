@@ -0,0 +1,108 @@
+//! Comparing the shown text with `assert_display!`.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{assert_display, sync_lcd::Lcd};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn lcd() -> TestLcd {
+    let (bus, delay, _) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap()
+}
+
+#[test]
+fn snapshot_rows() {
+    let mut lcd = lcd();
+    lcd.print_at(1, 2, "ok").unwrap();
+    let snapshot = lcd.snapshot();
+    assert_eq!((snapshot.rows(), snapshot.columns()), (2, 16));
+    assert_eq!(snapshot.row(0), [b' '; 16]);
+    assert_eq!(&snapshot.row(1)[..4], b"  ok");
+    assert_eq!(snapshot.row(2), []);
+}
+
+#[test]
+fn matching_text() {
+    let mut lcd = lcd();
+    lcd.print_at(0, 0, "Menu").unwrap();
+    lcd.print_at(1, 0, "> Settings").unwrap();
+    assert_display!(lcd.snapshot(), "Menu\n> Settings");
+    // Blank rows at the end can be left out.
+    lcd.clear().unwrap();
+    lcd.write_str("Bye").unwrap();
+    assert_display!(lcd.snapshot(), "Bye");
+}
+
+#[test]
+fn symbols_and_custom_characters() {
+    let mut lcd = lcd();
+    lcd.create_char(2, &[0x1f; 8]).unwrap();
+    lcd.write_str("21°C ").unwrap();
+    lcd.write_custom_char(2).unwrap();
+    assert_display!(lcd.snapshot(), "21°C 2");
+}
+
+#[test]
+fn scrolled_display() {
+    let mut lcd = lcd();
+    lcd.write_str("Hello").unwrap();
+    lcd.scroll_display_left().unwrap();
+    assert_display!(lcd.snapshot(), "ello");
+}
+
+#[test]
+#[should_panic(expected = "display doesn't show the expected text")]
+fn mismatch_panics() {
+    let mut lcd = lcd();
+    lcd.write_str("Hello").unwrap();
+    assert_display!(lcd.snapshot(), "Hallo");
+}
+
+#[test]
+fn mismatch_diff() {
+    let mut lcd = lcd();
+    lcd.print_at(0, 0, "Menu").unwrap();
+    lcd.print_at(1, 0, "> Settings").unwrap();
+    let snapshot = lcd.snapshot();
+    let mismatch = snapshot.compare("Menu\n> About").unwrap_err();
+    assert_eq!(
+        mismatch.to_string(),
+        "display doesn't show the expected text\n  \
+         |Menu            |\n\
+         - |> About         |\n\
+         + |> Settings      |"
+    );
+}
+
+#[test]
+fn too_many_lines() {
+    let lcd = lcd();
+    let snapshot = lcd.snapshot();
+    let mismatch = snapshot.compare("\n\nthird").unwrap_err();
+    assert_eq!(
+        mismatch.to_string(),
+        "display doesn't show the expected text\n  \
+         |                |\n  \
+         |                |\n\
+         - |third           |"
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn simulator() {
+    use lcd_lcm1602_i2c::simulator::{NoDelay, Simulator, SimulatorLcd};
+
+    let mut lcd = SimulatorLcd::<2, 16>::new(Simulator::new(), NoDelay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    lcd.print_at(1, 4, "sim").unwrap();
+    assert_display!(lcd.simulated_snapshot(), "\n    sim");
+    assert_eq!(lcd.simulated_snapshot(), lcd.snapshot());
+}
@@ -0,0 +1,63 @@
+//! 16x1 displays addressed as two halves of 8 columns at DDRAM 0x00 and 0x40.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{assert_display, sync_lcd::Lcd};
+
+type TestLcd = Lcd<1, 16, Bus, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_split_row(true)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn init_sets_up_two_lines() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_split_row(true)
+        .init()
+        .unwrap();
+    // The same init as a two row display.
+    assert_eq!(log.take(), init_sequence(BL));
+}
+
+#[test]
+fn write_hops_the_seam() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("0123456789abcdef").unwrap();
+    let mut expected: Vec<_> = b"01234567".iter().map(|&c| (RS, c)).collect();
+    expected.push((0, 0x80 | 0x40));
+    expected.extend(b"89abcdef".iter().map(|&c| (RS, c)));
+    assert_eq!(sent(&log), expected);
+    assert_eq!(lcd.get_cursor(), (0, 16));
+    assert_display!(lcd.snapshot(), "0123456789abcdef");
+}
+
+#[test]
+fn set_cursor_reaches_the_right_half() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(0, 7).unwrap();
+    lcd.set_cursor(0, 8).unwrap();
+    lcd.set_cursor(0, 15).unwrap();
+    assert_eq!(sent(&log), [(0, 0x87), (0, 0xc0), (0, 0xc7)]);
+    assert_eq!(lcd.get_cursor(), (0, 15));
+}
+
+#[test]
+fn write_from_the_right_half_is_truncated_at_the_end() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(0, 14).unwrap();
+    log.take();
+    lcd.write_str("xyz").unwrap();
+    assert_eq!(sent(&log), [(RS, b'x'), (RS, b'y')]);
+    assert_display!(lcd.snapshot(), "              xy");
+}
@@ -0,0 +1,106 @@
+//! The extended init and the contrast of the ST7032i.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    config::LcdConfig, geometry::Fixed, interface::St7032iInterface, sync_lcd::GenericLcd, Error,
+};
+
+const ST_ADDRESS: u8 = 0x3e;
+
+type TestLcd = GenericLcd<Fixed<2, 16>, St7032iInterface<Transfers>, Delay>;
+
+fn init(interface: impl FnOnce(Transfers) -> St7032iInterface<Transfers>) -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let interface = interface(Transfers { log: log.clone() });
+    let lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    (lcd, log)
+}
+
+/// An instruction and the time it takes.
+fn command(instruction: u8, wait_us: u64) -> [Event; 2] {
+    [
+        Event::Transfer(ST_ADDRESS, vec![0x00, instruction]),
+        delay_us(wait_us),
+    ]
+}
+
+#[test]
+fn extended_init() {
+    let (_, log) = init(St7032iInterface::new);
+    let mut expected = vec![delay_us(80_000)];
+    // 8 bit mode, then the extended instruction set: oscillator, contrast 40 with the booster
+    // on, follower circuit on with ratio 4.
+    for instruction in [0x38, 0x39, 0x14, 0x78, 0x56, 0x6c] {
+        expected.extend(command(instruction, 27));
+    }
+    expected.push(delay_us(200_000));
+    expected.extend(command(0x38, 27));
+    // No backlight to switch, then the usual init in 8 bit mode.
+    expected.push(delay_us(1_000));
+    for _ in 0..3 {
+        expected.extend(command(0x30, 5_000));
+    }
+    expected.extend(command(0x38, 50));
+    expected.extend(command(0x0c, 50));
+    expected.extend(command(0x01, 2_000));
+    expected.extend(command(0x06, 50));
+    expected.extend(command(0x02, 2_000));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn configured_init() {
+    let (_, log) = init(|bus| {
+        St7032iInterface::new(bus)
+            .with_contrast(0x3f)
+            .unwrap()
+            .with_booster(false)
+            .with_follower_ratio(2)
+            .unwrap()
+    });
+    let mut expected = Vec::new();
+    for instruction in [0x38, 0x39, 0x14, 0x7f, 0x53, 0x6a] {
+        expected.extend(command(instruction, 27));
+    }
+    assert_eq!(log.take()[1..=expected.len()], expected[..]);
+}
+
+#[test]
+fn invalid_configuration() {
+    let (_, _, log) = fakes();
+    let interface = St7032iInterface::new(Transfers { log: log.clone() });
+    assert!(interface.with_contrast(64).is_none());
+    let interface = St7032iInterface::new(Transfers { log });
+    assert!(interface.with_follower_ratio(8).is_none());
+}
+
+#[test]
+fn set_contrast() {
+    let (mut lcd, log) = init(St7032iInterface::new);
+    log.take();
+    lcd.set_contrast(20).unwrap();
+    // Into the extended instruction set and back, keeping lines and font.
+    let mut expected = Vec::new();
+    for instruction in [0x39, 0x74, 0x55, 0x38] {
+        expected.extend(command(instruction, 50));
+    }
+    assert_eq!(log.take(), expected);
+    assert_eq!(lcd.contrast(), 20);
+
+    assert_eq!(lcd.set_contrast(64), Err(Error::InvalidContrast));
+    assert!(log.take().is_empty());
+    assert_eq!(lcd.contrast(), 20);
+}
+
+#[test]
+fn operations_are_hd44780_compatible() {
+    let (mut lcd, log) = init(St7032iInterface::new);
+    log.take();
+    lcd.set_cursor(1, 2).unwrap();
+    lcd.write_str("A").unwrap();
+    let mut expected = command(0xc2, 50).to_vec();
+    expected.extend([Event::Transfer(ST_ADDRESS, vec![0x40, b'A']), delay_us(50)]);
+    assert_eq!(log.take(), expected);
+}
@@ -0,0 +1,279 @@
+//! How the writes to the PCF8574 are grouped into I2C transactions.
+
+mod common;
+
+use std::{cell::Cell, rc::Rc};
+
+use common::*;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use lcd_lcm1602_i2c::{sync_lcd::Lcd, Backlight, Error, ShiftDirection};
+
+type TestLcd = Lcd<2, 16, Transfers, Delay>;
+
+fn lcd() -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let lcd = TestLcd::new(Transfers { log: log.clone() }, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+fn transfer(bytes: &[u8]) -> Event {
+    Event::Transfer(ADDRESS, bytes.to_vec())
+}
+
+#[test]
+fn command_is_one_transaction() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 5).unwrap();
+    assert_eq!(
+        log.take(),
+        [
+            transfer(&[0xc8, 0xcc, 0x08, 0x58, 0x5c, 0x08]),
+            delay_us(50),
+        ]
+    );
+}
+
+/// Sizes of the transactions in `events`.
+fn sizes(events: &[Event]) -> Vec<usize> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Transfer(_, bytes) => Some(bytes.len()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Bytes of all transactions in `events`, one after the other.
+fn stream(events: &[Event]) -> Vec<u8> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Transfer(_, bytes) => Some(bytes.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn character_is_one_transaction() {
+    let (mut lcd, log) = lcd();
+    lcd.write_raw_char(b'H').unwrap();
+    lcd.write_raw_char(b'i').unwrap();
+    let h = [0x49, 0x4d, 0x08, 0x89, 0x8d, 0x08];
+    let i = [0x69, 0x6d, 0x08, 0x99, 0x9d, 0x08];
+    assert_eq!(
+        log.take(),
+        [transfer(&h), delay_us(50), transfer(&i), delay_us(50)]
+    );
+}
+
+#[test]
+fn string_is_one_transaction() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("Hi").unwrap();
+    let hi = [
+        0x49, 0x4d, 0x08, 0x89, 0x8d, 0x08, 0x69, 0x6d, 0x08, 0x99, 0x9d, 0x08,
+    ];
+    assert_eq!(log.take(), [transfer(&hi), delay_us(50)]);
+    assert_eq!(lcd.get_cursor(), (0, 2));
+}
+
+#[test]
+fn stream_is_unchanged() {
+    let text = "Hello, world";
+    let (mut reference, expected) = lcd();
+    reference.set_cursor(1, 2).unwrap();
+    for &c in text.as_bytes() {
+        reference.write_raw_char(c).unwrap();
+    }
+    let (mut lcd, log) = lcd();
+    lcd.print_at(1, 2, text).unwrap();
+    let events = log.take();
+    // The address, then the whole text.
+    assert_eq!(sizes(&events), [6, text.len() * 6]);
+    assert_eq!(stream(&events), stream(&expected.take()));
+    assert_eq!(lcd.snapshot(), reference.snapshot());
+}
+
+#[test]
+fn long_rows_are_sent_in_chunks_of_16_characters() {
+    let (_, delay, log) = fakes();
+    let mut lcd = Lcd::<4, 20, _, _>::new(Transfers { log: log.clone() }, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    lcd.write_str("abcdefghijklmnopqrstuvwxyz").unwrap();
+    // The rest of the text is truncated at the end of the row.
+    assert_eq!(sizes(&log.take()), [16 * 6, 4 * 6]);
+}
+
+#[test]
+fn display_shifts_are_one_transaction() {
+    let (mut reference, expected) = lcd();
+    for _ in 0..3 {
+        reference.scroll_display_left().unwrap();
+    }
+    let (mut lcd, log) = lcd();
+    lcd.shift_display_by(3, ShiftDirection::Left).unwrap();
+    assert_eq!(
+        log.take(),
+        [transfer(&stream(&expected.take())), delay_us(50)]
+    );
+    assert_eq!(lcd.display_offset(), 3);
+
+    // Like characters, in chunks of 16.
+    lcd.shift_display_by(20, ShiftDirection::Right).unwrap();
+    assert_eq!(sizes(&log.take()), [16 * 6, 4 * 6]);
+    // 17 to the right of the start, wrapping around the 40 columns of a line.
+    assert_eq!(lcd.display_offset(), 40 - 17);
+}
+
+#[test]
+fn chunks_stay_within_the_transfer_limit() {
+    let (_, delay, log) = fakes();
+    let mut lcd = TestLcd::new(Transfers { log: log.clone() }, delay)
+        .with_address(ADDRESS)
+        .with_i2c_transfer_limit(32)
+        .init()
+        .unwrap();
+    log.take();
+    lcd.write_str("0123456789ab").unwrap();
+    // Whole characters, five of them fit into 32 bytes.
+    assert_eq!(sizes(&log.take()), [30, 30, 12]);
+}
+
+#[test]
+fn limit_below_one_character_falls_back_to_single_characters() {
+    let (_, delay, log) = fakes();
+    let mut lcd = TestLcd::new(Transfers { log: log.clone() }, delay)
+        .with_address(ADDRESS)
+        .with_i2c_transfer_limit(4)
+        .init()
+        .unwrap();
+    log.take();
+    lcd.write_str("Hi").unwrap();
+    let events = log.take();
+    assert_eq!(sizes(&events), [4, 2, 4, 2]);
+    assert_eq!(
+        events[3..],
+        [
+            transfer(&[0x69, 0x6d, 0x08, 0x99]),
+            transfer(&[0x9d, 0x08]),
+            delay_us(50),
+        ]
+    );
+}
+
+/// Bus failing the next `failures` transactions with `kind`.
+struct Flaky {
+    log: Log,
+    failures: Rc<Cell<u8>>,
+    kind: ErrorKind,
+}
+
+impl ErrorType for Flaky {
+    type Error = ErrorKind;
+}
+
+impl I2c for Flaky {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.failures.get() > 0 {
+            self.failures.set(self.failures.get() - 1);
+            return Err(self.kind);
+        }
+        for operation in operations {
+            if let Operation::Write(bytes) = operation {
+                self.log.push(Event::Transfer(address, bytes.to_vec()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A display retrying once on a bus failing with `kind`, and the number of failures to come.
+fn flaky(kind: ErrorKind) -> (Lcd<2, 16, Flaky, Delay>, Log, Rc<Cell<u8>>) {
+    let (_, delay, log) = fakes();
+    let failures = Rc::new(Cell::new(0));
+    let bus = Flaky {
+        log: log.clone(),
+        failures: failures.clone(),
+        kind,
+    };
+    let lcd = Lcd::<2, 16, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_i2c_retries(1)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log, failures)
+}
+
+#[test]
+fn unacknowledged_transaction_is_repeated() {
+    let (mut lcd, log, failures) = flaky(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+    failures.set(1);
+    lcd.write_str("H").unwrap();
+    assert_eq!(
+        log.take(),
+        [
+            transfer(&[0x49, 0x4d, 0x08, 0x89, 0x8d, 0x08]),
+            delay_us(50)
+        ]
+    );
+    assert_eq!(lcd.i2c_retry_count(), 1);
+}
+
+#[test]
+fn cut_off_transaction_isnt_repeated() {
+    let (mut lcd, log, failures) = flaky(ErrorKind::ArbitrationLoss);
+    failures.set(1);
+    assert_eq!(
+        lcd.write_str("H"),
+        Err(Error::I2c(ErrorKind::ArbitrationLoss))
+    );
+    assert_eq!(log.take(), []);
+    assert_eq!(lcd.i2c_retry_count(), 0);
+}
+
+#[test]
+fn unacknowledged_backlight_write_is_repeated() {
+    let (mut lcd, log, failures) = flaky(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+    failures.set(1);
+    lcd.backlight(Backlight::Off).unwrap();
+    assert_eq!(log.take(), [transfer(&[0x00])]);
+    assert_eq!(lcd.i2c_retry_count(), 1);
+}
+
+#[test]
+fn failed_backlight_write_isnt_repeated() {
+    let (mut lcd, log, failures) = flaky(ErrorKind::Bus);
+    failures.set(1);
+    assert_eq!(
+        lcd.backlight(Backlight::Off),
+        Err(Error::I2c(ErrorKind::Bus))
+    );
+    assert_eq!(log.take(), []);
+    assert_eq!(lcd.i2c_retry_count(), 0);
+}
+
+#[test]
+fn error_is_returned_once_the_retries_are_used_up() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let (mut lcd, log, failures) = flaky(nack);
+    failures.set(u8::MAX);
+    assert_eq!(lcd.write_str("H"), Err(Error::I2c(nack)));
+    assert_eq!(log.take(), []);
+    assert_eq!(failures.get(), u8::MAX - 2);
+    assert_eq!(lcd.i2c_retry_count(), 1);
+}
@@ -0,0 +1,90 @@
+//! Characters the display can't show.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{charset::UnmappableChar, sync_lcd::Lcd, Error, UnmappablePolicy};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+fn lcd(policy: UnmappablePolicy) -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_unmappable_policy(policy)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn supported_characters() {
+    let (lcd, _log) = lcd(UnmappablePolicy::Error);
+    assert!(lcd.supports_char('a'));
+    assert!(lcd.supports_char('°'));
+    assert!(!lcd.supports_char('😀'));
+    assert_eq!(lcd.validate_str("21.5°C"), Ok(()));
+}
+
+#[test]
+fn invalid_character_is_found_at_its_byte_index() {
+    let (lcd, _log) = lcd(UnmappablePolicy::Error);
+    // `°` takes two bytes, the emoji starts at byte 5.
+    assert_eq!(
+        lcd.validate_str("1°C 😀"),
+        Err(UnmappableChar {
+            index: 5,
+            character: '😀'
+        })
+    );
+}
+
+#[test]
+fn writes_report_the_byte_index() {
+    let (mut lcd, log) = lcd(UnmappablePolicy::Error);
+    assert_eq!(
+        lcd.write_str("1°C 😀"),
+        Err(Error::UnmappableChar { index: 5 })
+    );
+    // The characters before it are written.
+    assert_eq!(
+        sent(&log)
+            .into_iter()
+            .map(|(_, code)| code)
+            .collect::<Vec<_>>(),
+        [b'1', 0xdf, b'C', b' ']
+    );
+    lcd.set_cursor(0, 0).unwrap();
+    assert_eq!(
+        lcd.write_wrapped(0, "°° °😀"),
+        Err(Error::UnmappableChar { index: 7 })
+    );
+    assert_eq!(
+        lcd.write_line(1, "ä😀"),
+        Err(Error::UnmappableChar { index: 2 })
+    );
+    assert_eq!(
+        lcd.write_char('😀'),
+        Err(Error::UnmappableChar { index: 0 })
+    );
+}
+
+#[test]
+fn queued_writes_report_the_byte_index() {
+    let (lcd, log) = lcd(UnmappablePolicy::Error);
+    let (mut lcd, mut delay) = lcd.into_queued::<256>();
+    assert_eq!(
+        lcd.write_str("°\n😀"),
+        Err(Error::UnmappableChar { index: 3 })
+    );
+    lcd.flush_blocking(&mut delay).unwrap();
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn replaced_characters() {
+    let (mut lcd, log) = lcd(UnmappablePolicy::Replace(b'?'));
+    lcd.write_str("a😀").unwrap();
+    assert_eq!(sent(&log), [(RS, b'a'), (RS, b'?')]);
+}
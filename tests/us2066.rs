@@ -0,0 +1,125 @@
+//! The init and the contrast of the US2066 OLED controller.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    config::LcdConfig, geometry::Fixed, interface::Us2066Interface, sync_lcd::GenericLcd,
+};
+
+const OLED_ADDRESS: u8 = 0x3c;
+
+type TestLcd = GenericLcd<Fixed<2, 16>, Us2066Interface<Transfers>, Delay>;
+
+fn init(interface: impl FnOnce(Transfers) -> Us2066Interface<Transfers>) -> (TestLcd, Log) {
+    let (_, delay, log) = fakes();
+    let interface = interface(Transfers { log: log.clone() });
+    let lcd = TestLcd::init_with_interface(interface, delay, LcdConfig::default()).unwrap();
+    (lcd, log)
+}
+
+fn lcd() -> (TestLcd, Log) {
+    let (lcd, log) = init(Us2066Interface::new);
+    log.take();
+    (lcd, log)
+}
+
+/// The transfers recorded since the last call, without the delays.
+fn transfers(log: &Log) -> Vec<Vec<u8>> {
+    log.take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Transfer(OLED_ADDRESS, bytes) => Some(bytes),
+            Event::Transfer(..) => panic!("transfer to another address"),
+            _ => None,
+        })
+        .collect()
+}
+
+fn command(instruction: u8) -> Vec<u8> {
+    vec![0x00, instruction]
+}
+
+fn data(byte: u8) -> Vec<u8> {
+    vec![0x40, byte]
+}
+
+/// The init of the Newhaven examples for two line modules, up to the usual init sequence.
+fn oled_init(regulator: u8, contrast: u8) -> Vec<Vec<u8>> {
+    vec![
+        command(0x2a),
+        command(0x71),
+        data(regulator),
+        command(0x28),
+        command(0x08),
+        command(0x2a),
+        command(0x79),
+        command(0xfd),
+        command(0x12),
+        command(0xd5),
+        command(0x70),
+        command(0x78),
+        command(0x08),
+        command(0x06),
+        command(0x72),
+        data(0x00),
+        command(0x2a),
+        command(0x79),
+        command(0xda),
+        command(0x10),
+        command(0xdc),
+        command(0x00),
+        command(0x81),
+        command(contrast),
+        command(0xd9),
+        command(0xf1),
+        command(0xdb),
+        command(0x40),
+        command(0x78),
+        command(0x28),
+    ]
+}
+
+#[test]
+fn init_sequence() {
+    let (_, log) = init(Us2066Interface::new);
+    let mut expected = oled_init(0x5c, 0x7f);
+    for instruction in [0x30, 0x30, 0x30, 0x38, 0x0c, 0x01, 0x06, 0x02] {
+        expected.push(command(instruction));
+    }
+    assert_eq!(transfers(&log), expected);
+}
+
+#[test]
+fn configured_init() {
+    let (_, log) = init(|bus| {
+        Us2066Interface::new(bus)
+            .with_address(OLED_ADDRESS)
+            .with_contrast(0xc0)
+            .with_internal_regulator(false)
+    });
+    let expected = oled_init(0x00, 0xc0);
+    assert_eq!(transfers(&log)[..expected.len()], expected[..]);
+}
+
+#[test]
+fn set_contrast() {
+    let (mut lcd, log) = lcd();
+    lcd.set_contrast(0x20).unwrap();
+    // Through the OLED instruction set and back to the function set of the display.
+    let expected = [0x3a, 0x79, 0x81, 0x20, 0x78, 0x38].map(command);
+    assert_eq!(transfers(&log), expected);
+    assert_eq!(lcd.contrast(), 0x20);
+}
+
+#[test]
+fn text_and_custom_characters() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 0).unwrap();
+    lcd.write_str("A").unwrap();
+    lcd.create_char(0, &[0x0a; 8]).unwrap();
+    let mut expected = vec![command(0xc0), data(b'A'), command(0x40)];
+    expected.extend((0..8).map(|_| data(0x0a)));
+    expected.push(command(0xc1));
+    assert_eq!(transfers(&log), expected);
+}
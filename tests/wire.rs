@@ -0,0 +1,353 @@
+//! The bytes and delays the driver sends for the basic operations.
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::{
+    assert_display,
+    config::{LcdConfig, Timings},
+    sync_lcd::Lcd,
+    Backlight, Error, Polarity,
+};
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+/// A 16x2 display after `init()`, with the init sequence taken from the log.
+fn lcd() -> (TestLcd, Log) {
+    let (bus, delay, log) = fakes();
+    let lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    log.take();
+    (lcd, log)
+}
+
+#[test]
+fn init() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+}
+
+/// An application keeping its display in a field.
+struct App {
+    lcd: TestLcd,
+}
+
+#[test]
+fn stored_display_is_initialized_in_place() {
+    let (lcd, log) = lcd();
+    let mut app = App { lcd };
+    app.lcd.print_at(1, 3, "Hi").unwrap();
+    log.take();
+    app.lcd.init_in_place().unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+    assert_eq!(app.lcd.get_cursor(), (0, 0));
+    assert_display!(app.lcd.snapshot(), "");
+}
+
+#[test]
+fn init_starts_with_the_8_bit_sync() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    let writes: Vec<_> = log
+        .take()
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Write(_, byte) => Some(byte),
+            _ => None,
+        })
+        .take(13)
+        .collect();
+    #[rustfmt::skip]
+    let expected = [
+        0x08,
+        0x38, 0x3c, 0x08,
+        0x38, 0x3c, 0x08,
+        0x38, 0x3c, 0x08,
+        0x28, 0x2c, 0x08,
+    ];
+    assert_eq!(writes, expected);
+}
+
+#[test]
+fn init_with_backlight_off() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_backlight(Backlight::Off)
+        .init()
+        .unwrap();
+    assert_eq!(log.take(), init_sequence(0));
+}
+
+#[test]
+fn init_with_active_low_backlight() {
+    let (bus, delay, log) = fakes();
+    let config = LcdConfig::default()
+        .with_address(ADDRESS)
+        .with_backlight_polarity(Polarity::ActiveLow);
+    TestLcd::new_with_config(bus, delay, config).init().unwrap();
+    assert_eq!(log.take(), init_sequence(0));
+}
+
+#[test]
+fn init_at_another_address() {
+    let (bus, delay, log) = fakes();
+    TestLcd::new(bus, delay).with_address(0x3f).init().unwrap();
+    let expected: Vec<_> = init_sequence(BL)
+        .into_iter()
+        .map(|event| match event {
+            Event::Write(_, byte) => Event::Write(0x3f, byte),
+            event => event,
+        })
+        .collect();
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn init_with_timings() {
+    let (bus, delay, log) = fakes();
+    let timings = Timings {
+        power_on_us: 100_000,
+        backlight_us: 2_000,
+        function_set_us: 6_000,
+        nibble_us: 800,
+        command_us: 60,
+        clear_us: 3_000,
+    };
+    let mut lcd = TestLcd::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_timings(timings)
+        .init()
+        .unwrap();
+    // The init sequence with each of its delays replaced.
+    let expected: Vec<_> = init_sequence(BL)
+        .into_iter()
+        .map(|event| match event {
+            Event::Delay(ns) => Event::Delay(match ns / 1_000 {
+                80_000 => 100_000_000,
+                1_000 => 2_000_000,
+                5_000 => 6_000_000,
+                700 => 800_000,
+                50 => 60_000,
+                2_000 => 3_000_000,
+                us => panic!("unexpected delay of {} µs", us),
+            }),
+            event => event,
+        })
+        .collect();
+    assert_eq!(log.take(), expected);
+
+    lcd.clear().unwrap();
+    assert_eq!(log.take(), byte(0, 0x01, 3_000, BL));
+    lcd.set_cursor(0, 1).unwrap();
+    assert_eq!(log.take(), byte(0, 0x81, 60, BL));
+}
+
+#[test]
+fn clear() {
+    let (mut lcd, log) = lcd();
+    lcd.clear().unwrap();
+    assert_eq!(log.take(), slow_command(0x01));
+}
+
+#[test]
+fn clear_region() {
+    let (mut lcd, log) = lcd();
+    lcd.clear_region(1, 2, 3).unwrap();
+    // Spaces over the region, then the cursor back at its start.
+    let mut expected = command(0xc2);
+    for _ in 0..3 {
+        expected.extend(data(b' '));
+    }
+    expected.extend(command(0xc2));
+    assert_eq!(log.take(), expected);
+    assert_eq!(lcd.get_cursor(), (1, 2));
+}
+
+#[test]
+fn return_home() {
+    let (mut lcd, log) = lcd();
+    lcd.return_home().unwrap();
+    assert_eq!(log.take(), slow_command(0x02));
+}
+
+#[test]
+fn set_cursor() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(0, 3).unwrap();
+    assert_eq!(log.take(), command(0x83));
+    // The second row starts at 0x40
+    lcd.set_cursor(1, 5).unwrap();
+    assert_eq!(log.take(), command(0xc5));
+}
+
+#[test]
+fn set_cursor_outside_of_the_display() {
+    let (mut lcd, log) = lcd();
+    assert_eq!(lcd.set_cursor(2, 0), Err(Error::InvalidRow));
+    assert_eq!(lcd.set_cursor(0, 16), Err(Error::InvalidColumn));
+    assert_eq!(lcd.set_cursor(u8::MAX, u8::MAX), Err(Error::InvalidRow));
+    assert!(log.take().is_empty());
+    // The last cell is still inside.
+    lcd.set_cursor(1, 15).unwrap();
+    assert_eq!(log.take(), command(0xcf));
+    assert_eq!(lcd.get_cursor(), (1, 15));
+}
+
+#[test]
+fn save_and_restore_cursor() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 4).unwrap();
+    log.take();
+    // Saving only remembers the position.
+    lcd.save_cursor().unwrap();
+    assert!(log.take().is_empty());
+    lcd.set_cursor(0, 0).unwrap();
+    log.take();
+    lcd.restore_cursor().unwrap();
+    assert_eq!(log.take(), command(0xc4));
+    assert_eq!(lcd.restore_cursor(), Err(Error::NoSavedCursor));
+    assert!(log.take().is_empty());
+}
+
+#[test]
+fn write_str() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("Hi!").unwrap();
+    assert_eq!(log.take(), text(b"Hi!"));
+}
+
+#[test]
+fn write_str_translates_symbols() {
+    let (mut lcd, log) = lcd();
+    lcd.write_str("°").unwrap();
+    assert_eq!(log.take(), data(0xdf));
+}
+
+#[test]
+fn create_char() {
+    let (mut lcd, log) = lcd();
+    lcd.set_cursor(1, 2).unwrap();
+    log.take();
+    let rows = [0x00, 0x0a, 0x1f, 0xff, 0x0e, 0x04, 0x00, 0x00];
+    lcd.create_char(3, &rows).unwrap();
+
+    // Slot 3 starts at CGRAM address 0x18, only five bits of each row are used.
+    let mut expected = command(0x58);
+    for row in rows {
+        expected.extend(data(row & 0x1f));
+    }
+    // The cursor is moved back to where it was.
+    expected.extend(command(0xc2));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn backlight() {
+    let (mut lcd, log) = lcd();
+    lcd.backlight(Backlight::Off).unwrap();
+    assert_eq!(log.take(), [write(0x00)]);
+    lcd.backlight(Backlight::On).unwrap();
+    assert_eq!(log.take(), [write(BL)]);
+}
+
+#[test]
+fn toggle_backlight() {
+    let (mut lcd, log) = lcd();
+    lcd.toggle_backlight().unwrap();
+    assert_eq!(log.take(), [write(0x00)]);
+    lcd.toggle_backlight().unwrap();
+    assert_eq!(log.take(), [write(BL)]);
+}
+
+#[test]
+fn display_control() {
+    let (mut lcd, log) = lcd();
+    lcd.cursor_on(true).unwrap();
+    assert_eq!(log.take(), command(0x0e));
+    lcd.cursor_blink(true).unwrap();
+    assert_eq!(log.take(), command(0x0f));
+    lcd.display_off().unwrap();
+    assert_eq!(log.take(), command(0x0b));
+}
+
+#[test]
+fn autoscroll() {
+    let (mut lcd, log) = lcd();
+    lcd.autoscroll_on().unwrap();
+    assert_eq!(log.take(), command(0x07));
+    lcd.autoscroll_off().unwrap();
+    assert_eq!(log.take(), command(0x06));
+}
+
+#[test]
+fn text_direction() {
+    let (mut lcd, log) = lcd();
+    lcd.right_to_left().unwrap();
+    assert_eq!(log.take(), command(0x04));
+    lcd.left_to_right().unwrap();
+    assert_eq!(log.take(), command(0x06));
+}
+
+#[test]
+fn release_with_shutdown() {
+    let (lcd, log) = lcd();
+    let (_bus, _delay) = lcd.release_with_shutdown().unwrap();
+    // Display off, then the backlight.
+    let mut expected = command(0x08);
+    expected.push(write(0x00));
+    assert_eq!(log.take(), expected);
+}
+
+#[test]
+fn builder_releases_the_bus_without_sending() {
+    let (bus, delay, log) = fakes();
+    let (bus, _delay) = TestLcd::new(bus, delay).with_address(ADDRESS).into_parts();
+    assert!(log.take().is_empty());
+    // The bus works for the next driver.
+    TestLcd::new(bus, Delay { log: log.clone() })
+        .with_address(ADDRESS)
+        .init()
+        .unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+}
+
+/// Total time the driver asked the delay to wait in µs.
+fn waited_us(events: &[Event]) -> u64 {
+    events
+        .iter()
+        .map(|event| match event {
+            Event::Delay(ns) => ns / 1_000,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn writing_32_characters_waits_once_per_byte() {
+    let (mut lcd, log) = lcd();
+    let rows = [b"0123456789abcdef", b"ghijklmnopqrstuv"];
+    for (row, codes) in (0..).zip(rows) {
+        lcd.set_cursor(row, 0).unwrap();
+        for &code in codes {
+            lcd.write_raw_char(code).unwrap();
+        }
+    }
+    // The execution time of two addresses and 32 characters.
+    let waited = waited_us(&log.take());
+    assert_eq!(waited, 34 * 50);
+    assert!(waited < 5_000);
+
+    lcd.print_at(0, 0, "0123456789abcdef").unwrap();
+    lcd.print_at(1, 0, "ghijklmnopqrstuv").unwrap();
+    assert_eq!(waited_us(&log.take()), 4 * 50);
+}
@@ -0,0 +1,114 @@
+//! The async driver sends the same bytes and delays as the blocking one.
+
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::*;
+use lcd_lcm1602_i2c::async_lcd::Lcd;
+
+type TestLcd = Lcd<2, 16, Bus, Delay>;
+
+#[test]
+fn init() {
+    let (bus, delay, log) = fakes();
+    block_on(TestLcd::new(bus, delay).with_address(ADDRESS).init()).unwrap();
+    assert_eq!(log.take(), init_sequence(BL));
+}
+
+#[test]
+fn operations() {
+    let (bus, delay, log) = fakes();
+    let mut lcd = block_on(TestLcd::new(bus, delay).with_address(ADDRESS).init()).unwrap();
+    log.take();
+
+    block_on(lcd.clear()).unwrap();
+    assert_eq!(log.take(), slow_command(0x01));
+    block_on(lcd.set_cursor(1, 5)).unwrap();
+    assert_eq!(log.take(), command(0xc5));
+    block_on(lcd.write_str("ok")).unwrap();
+    assert_eq!(log.take(), text(b"ok"));
+
+    let rows = [0x1f; 8];
+    block_on(lcd.create_char(0, &rows)).unwrap();
+    let mut expected = command(0x40);
+    for row in rows {
+        expected.extend(data(row));
+    }
+    expected.extend(command(0xc7));
+    assert_eq!(log.take(), expected);
+}
+
+/// The codes sent by writing `text` with `write!` and `write_udisplay` through both drivers on a
+/// 4x20 display scrolling the rows.
+///
+/// Only the codes are compared, the async driver formats into one buffer and can batch the
+/// characters of several parts of the format string.
+fn both(text: &str) -> [Vec<(u8, u8)>; 2] {
+    use core::fmt::Write;
+    use lcd_lcm1602_i2c::{sync_lcd, OverflowPolicy};
+
+    let (bus, delay, sync_log) = fakes();
+    let mut lcd = sync_lcd::Lcd::<4, 20, _, _>::new(bus, delay)
+        .with_address(ADDRESS)
+        .with_overflow_policy(OverflowPolicy::Scroll)
+        .init()
+        .unwrap();
+    sync_log.take();
+    write!(lcd, "{}|", text).unwrap();
+    lcd.write_udisplay(text).unwrap();
+
+    let (bus, delay, async_log) = fakes();
+    let mut lcd = block_on(
+        Lcd::<4, 20, _, _>::new(bus, delay)
+            .with_address(ADDRESS)
+            .with_overflow_policy(OverflowPolicy::Scroll)
+            .init(),
+    )
+    .unwrap();
+    async_log.take();
+    block_on(write!(lcd, "{}|", text)).unwrap();
+    block_on(lcd.write_udisplay(text)).unwrap();
+
+    [sent(&sync_log), sent(&async_log)]
+}
+
+#[test]
+fn formatting_matches_the_blocking_driver() {
+    let [sync, async_] = both("T=21.5°C");
+    assert_eq!(async_, sync);
+}
+
+#[test]
+fn long_formatted_text_isnt_cut_off() {
+    // Longer than the DDRAM, with a two byte character across the 80 byte mark.
+    let text: String = core::iter::repeat_n('x', 79).chain("°yz".chars()).collect();
+    let [sync, async_] = both(&text);
+    // The end past the first 80 bytes was written as well.
+    let codes: Vec<u8> = async_.iter().map(|&(_, code)| code).collect();
+    assert!(codes.ends_with(&[0xdf, b'y', b'z']));
+    assert_eq!(async_, sync);
+}
+
+#[test]
+fn formatting_error_is_kept() {
+    use core::fmt;
+    use lcd_lcm1602_i2c::{Error, OverflowPolicy};
+
+    let (bus, delay, _log) = fakes();
+    let mut lcd = block_on(
+        TestLcd::new(bus, delay)
+            .with_address(ADDRESS)
+            .with_overflow_policy(OverflowPolicy::Error)
+            .init(),
+    )
+    .unwrap();
+    assert_eq!(block_on(write!(lcd, "{}", 42)), Ok(()));
+    assert_eq!(lcd.take_last_error(), None);
+    assert_eq!(block_on(write!(lcd, "0123456789abcdefgh")), Err(fmt::Error));
+    assert_eq!(
+        lcd.take_last_error(),
+        Some(Error::RowOverflow { written: 14 })
+    );
+    assert_eq!(lcd.take_last_error(), None);
+}